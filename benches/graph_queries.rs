@@ -0,0 +1,122 @@
+//! Lookups on synthetic graphs, comparing the indexed queries with a linear scan.
+
+use {
+    crabviz::{
+        lsp_types::{DocumentSymbol, Position, Range, SymbolKind},
+        GlobalPosition, Graph, GraphBuilder, RelationKind,
+    },
+    criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion},
+};
+
+const FUNCS_PER_FILE: u32 = 50;
+
+fn func(line: u32) -> DocumentSymbol {
+    let range = Range {
+        start: Position { line, character: 3 },
+        end: Position {
+            line,
+            character: 10,
+        },
+    };
+    DocumentSymbol {
+        name: format!("f{}", line),
+        detail: None,
+        kind: SymbolKind::Function,
+        tags: None,
+        range,
+        selection_range: range,
+        children: vec![],
+    }
+}
+
+fn position(n: u32) -> GlobalPosition {
+    GlobalPosition {
+        file_id: n / FUNCS_PER_FILE + 1,
+        line: n % FUNCS_PER_FILE,
+        character: 3,
+    }
+}
+
+/// `funcs` functions in files of `FUNCS_PER_FILE`, with `degree` calls from each function to
+/// pseudo-random others.
+fn synthetic_graph(funcs: u32, degree: u32) -> Graph {
+    let mut builder = GraphBuilder::new();
+    for file in 0..funcs.div_ceil(FUNCS_PER_FILE) {
+        builder.add_file_with_id(
+            file + 1,
+            format!("/root/src/file{}.rs", file),
+            (0..FUNCS_PER_FILE).map(func).collect(),
+        );
+    }
+
+    // linear congruential generator, so that every run uses the same graph
+    let mut seed = 12345u64;
+    for from in 0..funcs {
+        for _ in 0..degree {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let to = (seed >> 33) as u32 % funcs;
+            builder.add_relation(position(from), position(to), RelationKind::Call);
+        }
+    }
+    builder.build()
+}
+
+fn symbol_relations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("symbol_relations");
+
+    for funcs in [1_000, 10_000] {
+        let graph = synthetic_graph(funcs, 4);
+        let target = position(funcs / 2);
+        // builds the indexes before measuring
+        graph.get_symbol_relations(target);
+
+        group.bench_with_input(BenchmarkId::new("indexed", funcs), &graph, |b, graph| {
+            b.iter(|| graph.get_symbol_relations(black_box(target)).len())
+        });
+        group.bench_with_input(BenchmarkId::new("scan", funcs), &graph, |b, graph| {
+            b.iter(|| {
+                let target = black_box(target);
+                graph
+                    .relations()
+                    .iter()
+                    .filter(|r| r.from == target || r.to == target)
+                    .count()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn symbol_lookup(c: &mut Criterion) {
+    let graph = synthetic_graph(10_000, 4);
+    let target = position(7_777);
+    graph.get_symbol(target);
+
+    c.bench_function("get_symbol", |b| {
+        b.iter(|| graph.get_symbol(black_box(target)).is_some())
+    });
+    c.bench_function("search_by_symbol_kind", |b| {
+        b.iter(|| {
+            graph
+                .search_by_symbol_kind(black_box(crabviz::SymbolKind::Function))
+                .len()
+        })
+    });
+}
+
+fn index_build(c: &mut Criterion) {
+    let mut graph = synthetic_graph(10_000, 4);
+
+    c.bench_function("reindex", |b| {
+        b.iter(|| {
+            graph.reindex();
+            graph.get_file(1).is_some()
+        })
+    });
+}
+
+criterion_group!(benches, symbol_relations, symbol_lookup, index_build);
+criterion_main!(benches);
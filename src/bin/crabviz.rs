@@ -0,0 +1,337 @@
+//! Command line front end of crabviz.
+//!
+//! It starts a language server over stdio, walks the source files under a directory and
+//! feeds the symbols and call hierarchy reported by the server into a `GraphGenerator`.
+
+use {
+    crabviz::{
+        lsp_client::{Error, LspClient},
+        DotOptions, EdgeRouting, EdgeWeighting, Granularity, GraphGenerator, NodeMetric,
+        TraversalOptions,
+    },
+    std::{
+        env, fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+        process,
+        time::Duration,
+    },
+};
+
+const USAGE: &str = "\
+Usage: crabviz [OPTIONS] --server <COMMAND> [ARGS]...
+
+Options:
+    -r, --root <DIR>            root directory to analyze (default: current directory)
+    -l, --lang <LANG>           language of the source files, e.g. Rust, Go, TypeScript
+    -e, --ext <EXT>             file extension to analyze, can be repeated (default: derived from --lang)
+    -f, --format <FORMAT>       output format: dot, mermaid, plantuml, svg or json (default: dot)
+    -g, --granularity <LEVEL>   one node per symbol, file or directory (default: symbol)
+    -o, --output <FILE>         write the output to FILE instead of stdout
+        --edge-weight <MODE>    weight DOT edges by call count: off, linear or log (default: off)
+        --call-counts           label DOT edges having more than one call with the call count
+        --highlight-cycles      color DOT edges lying on call or dependency cycles
+        --node-metric <METRIC>  color DOT symbols by fan-in, fan-out, depth or betweenness
+        --edge-routing <MODE>   draw SVG edges as spline or orthogonal lines (default: spline)
+        --symbol-ids            use ids independent of symbol positions in DOT, Mermaid, PlantUML and SVG
+    -t, --timeout <SECS>        timeout of each language server request (default: 30)
+    -s, --server <COMMAND>      language server command, all following arguments are passed to it
+    -h, --help                  print this help
+";
+
+#[derive(Clone, Copy)]
+enum Format {
+    Dot,
+    Mermaid,
+    PlantUml,
+    Svg,
+    Json,
+}
+
+struct Options {
+    root: PathBuf,
+    lang: Option<String>,
+    exts: Vec<String>,
+    format: Format,
+    granularity: Granularity,
+    output: Option<PathBuf>,
+    dot: DotOptions,
+    routing: EdgeRouting,
+    timeout: Option<Duration>,
+    server: Vec<String>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut opts = Options {
+        root: PathBuf::from("."),
+        lang: None,
+        exts: vec![],
+        format: Format::Dot,
+        granularity: Granularity::Symbol,
+        output: None,
+        dot: DotOptions::default(),
+        routing: EdgeRouting::default(),
+        timeout: None,
+        server: vec![],
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("missing value for {}", name));
+
+        match arg.as_str() {
+            "-r" | "--root" => opts.root = PathBuf::from(value(&arg)?),
+            "-l" | "--lang" => opts.lang = Some(value(&arg)?),
+            "-e" | "--ext" => opts
+                .exts
+                .push(value(&arg)?.trim_start_matches('.').to_string()),
+            "-f" | "--format" => {
+                opts.format = match value(&arg)?.as_str() {
+                    "dot" => Format::Dot,
+                    "mermaid" => Format::Mermaid,
+                    "plantuml" => Format::PlantUml,
+                    "svg" => Format::Svg,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format '{}'", other)),
+                }
+            }
+            "-g" | "--granularity" => {
+                opts.granularity = match value(&arg)?.as_str() {
+                    "symbol" => Granularity::Symbol,
+                    "file" => Granularity::File,
+                    "directory" => Granularity::Directory,
+                    other => return Err(format!("unknown granularity '{}'", other)),
+                }
+            }
+            "-o" | "--output" => opts.output = Some(PathBuf::from(value(&arg)?)),
+            "--edge-weight" => {
+                opts.dot.edge_weighting = match value(&arg)?.as_str() {
+                    "off" => EdgeWeighting::Off,
+                    "linear" => EdgeWeighting::Linear,
+                    "log" => EdgeWeighting::Logarithmic,
+                    other => return Err(format!("unknown edge weighting '{}'", other)),
+                }
+            }
+            "--call-counts" => opts.dot.call_count_labels = true,
+            "--highlight-cycles" => opts.dot.highlight_cycles = true,
+            "--symbol-ids" => opts.dot.symbol_ids = true,
+            "--node-metric" => {
+                opts.dot.node_metric = match value(&arg)?.as_str() {
+                    "off" => NodeMetric::Off,
+                    "fan-in" => NodeMetric::FanIn,
+                    "fan-out" => NodeMetric::FanOut,
+                    "depth" => NodeMetric::Depth,
+                    "betweenness" => NodeMetric::Betweenness,
+                    other => return Err(format!("unknown node metric '{}'", other)),
+                }
+            }
+            "--edge-routing" => {
+                opts.routing = match value(&arg)?.as_str() {
+                    "spline" => EdgeRouting::Spline,
+                    "orthogonal" => EdgeRouting::Orthogonal,
+                    other => return Err(format!("unknown edge routing '{}'", other)),
+                }
+            }
+            "-t" | "--timeout" => {
+                let secs = value(&arg)?;
+                let secs = secs
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid timeout '{}'", secs))?;
+                opts.timeout = Some(Duration::from_secs(secs));
+            }
+            "-s" | "--server" => {
+                opts.server = args.by_ref().collect();
+                break;
+            }
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    if opts.server.is_empty() {
+        return Err("no language server command given".to_string());
+    }
+
+    if opts.lang.is_none() && opts.exts.is_empty() {
+        return Err("either --lang or --ext should be given".to_string());
+    }
+
+    if let Some(lang) = opts.lang.take() {
+        match lang_extensions(&lang) {
+            Some((name, exts)) => {
+                if opts.exts.is_empty() {
+                    opts.exts = exts.iter().map(|ext| ext.to_string()).collect();
+                }
+                opts.lang = Some(name.to_string());
+            }
+            None if opts.exts.is_empty() => {
+                return Err(format!("unknown language '{}', pass --ext", lang));
+            }
+            None => opts.lang = Some(lang),
+        }
+    }
+
+    if opts.lang.is_none() {
+        opts.lang = Some(ext_lang(&opts.exts[0]).to_string());
+    }
+
+    Ok(opts)
+}
+
+/// Name and file extensions of `lang`, whatever its case
+fn lang_extensions(lang: &str) -> Option<(&'static str, &'static [&'static str])> {
+    const LANGUAGES: &[(&str, &[&str])] = &[
+        ("Rust", &["rs"]),
+        ("Go", &["go"]),
+        ("TypeScript", &["ts", "tsx"]),
+        ("TypeScript JSX", &["ts", "tsx"]),
+        ("JavaScript", &["js", "jsx"]),
+        ("JavaScript JSX", &["js", "jsx"]),
+        ("C", &["c", "h"]),
+        ("C++", &["cc", "cpp", "cxx", "h", "hpp"]),
+        ("Python", &["py"]),
+        ("Java", &["java"]),
+    ];
+
+    LANGUAGES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(lang))
+        .copied()
+}
+
+fn ext_lang(ext: &str) -> &'static str {
+    match ext {
+        "rs" => "Rust",
+        "go" => "Go",
+        "ts" | "tsx" => "TypeScript",
+        "js" | "jsx" => "JavaScript",
+        _ => "",
+    }
+}
+
+fn language_id(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+    {
+        "rs" => "rust",
+        "go" => "go",
+        "ts" => "typescript",
+        "tsx" => "typescriptreact",
+        "js" => "javascript",
+        "jsx" => "javascriptreact",
+        "py" => "python",
+        "java" => "java",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        _ => "plaintext",
+    }
+}
+
+fn collect_files(dir: &Path, exts: &[String], files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'));
+
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            if path
+                .file_name()
+                .is_some_and(|name| name == "target" || name == "node_modules")
+            {
+                continue;
+            }
+            collect_files(&path, exts, files)?;
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| exts.iter().any(|e| e == ext))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn run(opts: Options) -> Result<(), Error> {
+    let root = fs::canonicalize(&opts.root)?;
+    let root_str = root.to_string_lossy().to_string();
+
+    let mut files = vec![];
+    collect_files(&root, &opts.exts, &mut files)?;
+    files.sort();
+
+    let mut client = LspClient::spawn(&opts.server)?;
+    if let Some(timeout) = opts.timeout {
+        client.set_timeout(timeout);
+    }
+
+    let caps = client.initialize(&root_str)?;
+    if !caps.document_symbol {
+        return Err(Error::Unsupported("documentSymbolProvider"));
+    }
+    if !caps.call_hierarchy {
+        eprintln!("warning: the language server does not support call hierarchy, no call edges will be generated");
+    }
+
+    let mut generator = GraphGenerator::new(root_str, opts.lang.as_deref().unwrap_or_default());
+    generator.set_dot_options(opts.dot);
+    generator.set_granularity(opts.granularity);
+
+    let files = files
+        .iter()
+        .map(|path| {
+            let file_path = path.to_string_lossy().to_string();
+            client.did_open(&file_path, language_id(path), &fs::read_to_string(path)?)?;
+            Ok(file_path)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let errors = generator.crawl_files(&mut client, &files, &TraversalOptions::default());
+    errors
+        .iter()
+        .filter(|e| !matches!(e, Error::Unsupported(_)))
+        .for_each(|e| eprintln!("warning: {}", e));
+
+    client.shutdown()?;
+
+    let output = match opts.format {
+        Format::Dot => generator.generate_dot_source(),
+        Format::Mermaid => generator.generate_mermaid_source(),
+        Format::PlantUml => generator.generate_plantuml_source(),
+        Format::Svg => generator.generate_svg(opts.routing),
+        Format::Json => serde_json::to_string_pretty(&generator.generate_graph())?,
+    };
+
+    match opts.output {
+        Some(path) => fs::write(path, output)?,
+        None => io::stdout().write_all(output.as_bytes())?,
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let opts = match parse_args(env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(opts) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
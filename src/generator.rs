@@ -1,3 +1,9 @@
+mod collapse;
+mod dead_code;
+mod focus;
+mod render;
+mod snapshot;
+mod traversal;
 mod types;
 
 #[cfg(feature = "wasm")]
@@ -8,31 +14,96 @@ pub use wasm::{set_panic_hook, GraphGeneratorWasm};
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+mod test_support;
+
 #[cfg(test)]
 mod refcell_tests;
 
 #[cfg(test)]
 mod generator_tests;
 
+#[cfg(test)]
+mod traversal_tests;
+
+#[cfg(test)]
+mod focus_tests;
+
+#[cfg(test)]
+mod snapshot_tests;
+
+#[cfg(test)]
+mod collapse_tests;
+
+#[cfg(test)]
+mod cycles_tests;
+
+#[cfg(test)]
+mod dead_code_tests;
+
+#[cfg(test)]
+mod metrics_tests;
+
+#[cfg(test)]
+mod paths_tests;
+
+#[cfg(test)]
+mod index_tests;
+
+#[cfg(test)]
+mod search_tests;
+
+#[cfg(test)]
+mod query_tests;
+
+#[cfg(test)]
+mod subgraph_tests;
+
+#[cfg(test)]
+mod render_tests;
+
+#[cfg(test)]
+mod svg_tests;
+
+#[cfg(test)]
+mod golden_tests;
+
+#[cfg(test)]
+mod diff_tests;
+
+#[cfg(test)]
+mod symbol_id_tests;
+
+#[cfg(test)]
+mod plantuml_tests;
+
 #[cfg(test)]
 #[cfg(feature = "wasm")]
 mod wasm_tests;
 
+pub use dead_code::DeadCodeOptions;
+pub use snapshot::{SnapshotError, SNAPSHOT_VERSION};
+pub use traversal::{CallHierarchySource, Direction, TraversalOptions};
 pub(crate) use types::*;
 use {
     crate::{
-        graph::{dot::Dot, Cell, CssClass, Edge, Subgraph},
-        graph_model::{Graph, GraphBuilder, RelationKind, GlobalPosition},
+        graph::{
+            dot::{metric_color, DotOptions, NodeMetric},
+            svg::EdgeRouting,
+            Cell, Subgraph,
+        },
+        graph_model::{
+            CycleReport, GlobalPosition, Granularity, Graph, GraphBuilder, GraphMetrics,
+            RelationKind,
+        },
         lang,
         lsp_types::{
             CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall,
-            DocumentSymbol, Location, Position, SymbolKind,
+            DocumentSymbol, Location, Position, SymbolKind, TypeHierarchyItem,
         },
     },
-    enumset::EnumSet,
     std::{
-        cell::RefCell,
-        collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
+        collections::{btree_map, hash_map::Entry, BTreeMap, HashMap, HashSet},
         path::{Path, PathBuf},
     },
 };
@@ -40,29 +111,50 @@ use {
 pub struct GraphGenerator {
     // TODO: use a trie map to store files
     root: String,
-    files: HashMap<String, FileOutline>,
+    /// Files by path, so that the output lists them in path order
+    files: BTreeMap<String, FileOutline>,
     next_file_id: u32,
+    /// Output ids of the files, for generators restricted from a larger one, see
+    /// `output_file_ids`
+    kept_file_ids: Option<HashMap<String, u32>>,
 
+    lang_name: String,
     lang: Box<dyn lang::Language>,
 
-    incoming_calls: HashMap<SymbolLocation, Vec<CallHierarchyIncomingCall>>,
-    outgoing_calls: HashMap<SymbolLocation, Vec<CallHierarchyOutgoingCall>>,
-    interfaces: HashMap<SymbolLocation, Vec<SymbolLocation>>,
+    incoming_calls: BTreeMap<SymbolLocation, Vec<CallHierarchyIncomingCall>>,
+    outgoing_calls: BTreeMap<SymbolLocation, Vec<CallHierarchyOutgoingCall>>,
+    interfaces: BTreeMap<SymbolLocation, Vec<SymbolLocation>>,
+    /// subtypes of each type, from the type hierarchy
+    subtypes: BTreeMap<SymbolLocation, Vec<SymbolLocation>>,
+    /// symbols enclosing the references to each symbol
+    references: BTreeMap<SymbolLocation, Vec<SymbolLocation>>,
 
     highlights: HashMap<u32, HashSet<(u32, u32)>>,
+
+    dot_options: DotOptions,
+    granularity: Granularity,
+    dead_code_options: DeadCodeOptions,
 }
 
 impl GraphGenerator {
     pub fn new(root: String, lang: &str) -> Self {
         Self {
             root,
-            files: HashMap::new(),
+            files: BTreeMap::new(),
             next_file_id: 1,
-            incoming_calls: HashMap::new(),
-            outgoing_calls: HashMap::new(),
-            interfaces: HashMap::new(),
+            kept_file_ids: None,
+            incoming_calls: BTreeMap::new(),
+            outgoing_calls: BTreeMap::new(),
+            interfaces: BTreeMap::new(),
+            subtypes: BTreeMap::new(),
+            references: BTreeMap::new(),
             highlights: HashMap::new(),
 
+            dot_options: DotOptions::default(),
+            granularity: Granularity::Symbol,
+            dead_code_options: DeadCodeOptions::default(),
+
+            lang_name: lang.to_string(),
             lang: lang::language_handler(lang),
         }
     }
@@ -71,6 +163,11 @@ impl GraphGenerator {
         self.lang.should_filter_out_file(file_path)
     }
 
+    /// Sets how `generate_dot_source` weights and labels edges by their call counts.
+    pub fn set_dot_options(&mut self, options: DotOptions) {
+        self.dot_options = options;
+    }
+
     pub fn add_file(&mut self, file_path: String, symbols: Vec<DocumentSymbol>) -> bool {
         if self.lang.should_filter_out_file(&file_path) {
             return false;
@@ -83,16 +180,63 @@ impl GraphGenerator {
         };
 
         match self.files.entry(file_path) {
-            Entry::Vacant(entry) => {
+            btree_map::Entry::Vacant(entry) => {
                 entry.insert(file);
                 self.next_file_id += 1;
             }
-            Entry::Occupied(_) => return false,
+            btree_map::Entry::Occupied(_) => return false,
         }
 
         return true;
     }
 
+    /// Removes a file along with its highlights and every call or implementation entry that
+    /// starts or ends in it. The id of the file is not reused.
+    pub fn remove_file(&mut self, file_path: &str) -> bool {
+        let Some(file) = self.files.remove(file_path) else {
+            return false;
+        };
+
+        self.highlights.remove(&file.id);
+        self.remove_relations_of(file_path);
+
+        true
+    }
+
+    /// Replaces the symbols of a file, keeping its id, so that the file can be crawled again
+    /// after it has been edited. Entries that start or end in the file are dropped, since their
+    /// positions may be stale. The file is added if it does not exist yet.
+    pub fn update_file(&mut self, file_path: String, symbols: Vec<DocumentSymbol>) -> bool {
+        let Some(file) = self.files.get_mut(&file_path) else {
+            return self.add_file(file_path, symbols);
+        };
+
+        file.symbols = symbols;
+        let file_id = file.id;
+
+        self.highlights.remove(&file_id);
+        self.remove_relations_of(&file_path);
+
+        true
+    }
+
+    fn remove_relations_of(&mut self, file_path: &str) {
+        self.incoming_calls.retain(|callee, calls| {
+            calls.retain(|call| call.from.uri.path != file_path);
+            callee.path != file_path
+        });
+        self.outgoing_calls.retain(|caller, calls| {
+            calls.retain(|call| call.to.uri.path != file_path);
+            caller.path != file_path
+        });
+        for targets in [&mut self.interfaces, &mut self.subtypes, &mut self.references] {
+            targets.retain(|target, sources| {
+                sources.retain(|location| location.path != file_path);
+                target.path != file_path
+            });
+        }
+    }
+
     // TODO: graph database
     pub fn add_incoming_calls(
         &mut self,
@@ -149,375 +293,202 @@ impl GraphGenerator {
         self.interfaces.insert(location, implementations);
     }
 
-    pub fn generate_mermaid_source(&self) -> String {
-        let files = &self.files;
-
-        // 构建表格和单元格ID，与generate_dot_source类似
-        let mut tables = files
-            .values()
-            .map(|file| {
-                let mut table = self.lang.file_repr(file);
-                if let Some(cells) = self.highlights.get(&file.id) {
-                    table.highlight_cells(cells);
-                }
-
-                (file.id, table)
-            })
-            .collect::<HashMap<_, _>>();
+    /// Adds the results of a `typeHierarchy/supertypes` request for the type at `position`.
+    pub fn add_supertypes(
+        &mut self,
+        file_path: String,
+        position: Position,
+        supertypes: Vec<TypeHierarchyItem>,
+    ) {
+        let subtype = SymbolLocation::new(file_path, &position);
+        for item in supertypes {
+            let supertype = SymbolLocation::new(item.uri.path, &item.selection_range.start);
+            self.add_inheritance(supertype, subtype.clone());
+        }
+    }
 
-        let mut cell_ids = HashSet::new();
-        tables
-            .iter()
-            .flat_map(|(_, tbl)| tbl.sections.iter().map(|cell| (tbl.id, cell)))
-            .for_each(|(tid, cell)| self.collect_cell_ids(tid, cell, &mut cell_ids));
-        let cell_ids_ref = &cell_ids;
+    /// Adds the results of a `typeHierarchy/subtypes` request for the type at `position`.
+    pub fn add_subtypes(
+        &mut self,
+        file_path: String,
+        position: Position,
+        subtypes: Vec<TypeHierarchyItem>,
+    ) {
+        let supertype = SymbolLocation::new(file_path, &position);
+        for item in subtypes {
+            let subtype = SymbolLocation::new(item.uri.path, &item.selection_range.start);
+            self.add_inheritance(supertype.clone(), subtype);
+        }
+    }
 
-        let updated_files = RefCell::new(HashSet::new());
-        let updated_files_ref = &updated_files;
+    fn add_inheritance(&mut self, supertype: SymbolLocation, subtype: SymbolLocation) {
+        let subtypes = self.subtypes.entry(supertype).or_default();
+        if !subtypes.contains(&subtype) {
+            subtypes.push(subtype);
+        }
+    }
 
-        let inserted_symbols = RefCell::new(HashSet::new());
-        let inserted_symbols_ref = &inserted_symbols;
+    /// Adds the results of a `textDocument/references` request for the symbol at `position`.
+    ///
+    /// Each reference is resolved to the innermost symbol enclosing it, so the files containing
+    /// the references should be added first. References outside of any symbol, and the ones
+    /// inside the referenced symbol itself, are skipped.
+    pub fn add_references(
+        &mut self,
+        file_path: String,
+        position: Position,
+        locations: Vec<Location>,
+    ) {
+        let referenced = SymbolLocation::new(file_path, &position);
 
-        // 收集边，与generate_dot_source类似
-        let incoming_calls = self
-            .incoming_calls
+        let mut referencing = locations
             .iter()
-            .filter_map(|(callee, callers)| {
-                let to = callee.location_id(files)?;
-
-                cell_ids.contains(&to).then_some((to, callers))
-            })
-            .flat_map(|(to, calls)| {
-                calls.into_iter().filter_map(move |call| {
-                    let from = call.from.location_id(files)?;
-
-                    (cell_ids_ref.contains(&from)
-                        || inserted_symbols_ref.try_borrow().map(|b| b.contains(&from)).unwrap_or(false)
-                        || {
-                            let file = files.get(&call.from.uri.path)? as *const FileOutline;
-
-                            let updated = unsafe {
-                                self.try_insert_symbol(
-                                    &call.from,
-                                    file.cast_mut().as_mut().unwrap(),
-                                )
-                            };
-
-                            if updated {
-                                if let Ok(mut updated_files) = updated_files_ref.try_borrow_mut() {
-                                    updated_files.insert(call.from.uri.path.clone());
-                                }
-                                if let Ok(mut inserted_symbols) = inserted_symbols_ref.try_borrow_mut() {
-                                    inserted_symbols.insert(from);
-                                }
-                            }
-                            updated
-                        })
-                    .then_some(Edge {
-                        from,
-                        to,
-                        classes: EnumSet::new(),
-                    })
-                })
-            });
+            .filter_map(|location| self.enclosing_symbol(&location.uri.path, location.range.start))
+            .filter(|symbol| *symbol != referenced)
+            .collect::<Vec<_>>();
+        referencing.sort();
+        referencing.dedup();
 
-        let outgoing_calls = self
-            .outgoing_calls
-            .iter()
-            .filter_map(|(caller, callees)| {
-                let from = caller.location_id(files)?;
+        self.references.insert(referenced, referencing);
+    }
 
-                cell_ids.contains(&from).then_some((from, callees))
-            })
-            .flat_map(|(from, callees)| {
-                callees.into_iter().filter_map(move |call| {
-                    let to = call.to.location_id(files)?;
-
-                    cell_ids_ref.contains(&to).then_some(Edge {
-                        from,
-                        to,
-                        classes: EnumSet::new(),
-                    })
-                })
-            });
+    fn enclosing_symbol(&self, file_path: &str, position: Position) -> Option<SymbolLocation> {
+        let mut symbols = &self.files.get(file_path)?.symbols;
+        let mut enclosing = None;
 
-        let implementations = self
-            .interfaces
+        while let Some(symbol) = symbols
             .iter()
-            .filter_map(|(interface, implementations)| {
-                let to = interface.location_id(files)?;
-
-                cell_ids.contains(&to).then_some((to, implementations))
-            })
-            .flat_map(|(to, implementations)| {
-                implementations.into_iter().filter_map(move |location| {
-                    let from = location.location_id(files)?;
-
-                    cell_ids_ref.contains(&&from).then_some(Edge {
-                        from,
-                        to,
-                        classes: CssClass::Impl.into(),
-                    })
-                })
-            });
+            .find(|symbol| symbol.range.start <= position && position <= symbol.range.end)
+        {
+            enclosing = Some(symbol);
+            symbols = &symbol.children;
+        }
 
-        let edges = incoming_calls
-            .chain(outgoing_calls)
-            .chain(implementations)
-            .collect::<HashSet<_>>();
+        enclosing
+            .map(|symbol| SymbolLocation::new(file_path.to_string(), &symbol.selection_range.start))
+    }
 
-        if let Ok(updated) = updated_files.try_borrow() {
-            updated.iter().for_each(|path| {
-                let file = files.get(path).unwrap();
-                let table = tables.get_mut(&file.id).unwrap();
-                *table = self.lang.file_repr(file);
-            });
+    pub fn generate_mermaid_source(&self) -> String {
+        if self.granularity != Granularity::Symbol {
+            return self.generate_group_mermaid_source();
         }
 
-        // 生成Mermaid格式（使用带subgraph的版本）
-        self.generate_mermaid_from_graph_with_subgraphs(tables.into_values().collect(), edges.into_iter().collect())
+        self.render_mermaid(&self.generate_symbol_graph())
     }
 
-    fn generate_mermaid_from_graph(&self, tables: Vec<crate::graph::TableNode>, edges: Vec<Edge>) -> String {
-        // 生成Mermaid流程图
-        let mut mermaid = String::from("flowchart LR\n");
-        
-        // 添加所有节点（不使用subgraph）
-        for table in &tables {
-            for section in &table.sections {
-                self.add_mermaid_cell(table.id, section, &mut mermaid);
-            }
-        }
-        
-        // 添加边
-        for edge in &edges {
-            let from = format!("{}_{}_{}", edge.from.0, edge.from.1, edge.from.2);
-            let to = format!("{}_{}_{}", edge.to.0, edge.to.1, edge.to.2);
-            mermaid.push_str(&format!("    {} --> {}\n", from, to));
+    /// Generates a PlantUML class diagram, or a component diagram of the files or directories
+    /// with a coarser granularity.
+    pub fn generate_plantuml_source(&self) -> String {
+        if self.granularity != Granularity::Symbol {
+            return self.generate_group_plantuml_source();
         }
-        
-        mermaid
+
+        self.render_plantuml(&self.generate_symbol_graph())
     }
-    
-    fn generate_mermaid_from_graph_with_subgraphs(&self, tables: Vec<crate::graph::TableNode>, edges: Vec<Edge>) -> String {
-        // 生成Mermaid流程图
-        let mut mermaid = String::from("flowchart LR\n");
-        
-        // 创建文件到表格的映射
-        let mut file_tables: HashMap<String, Vec<&crate::graph::TableNode>> = HashMap::new();
-        let mut orphan_tables = Vec::new();
-        
-        // 将表格按文件分组
-        for table in &tables {
-            let file_path = self.files.iter()
-                .find(|(_, file)| file.id == table.id)
-                .map(|(path, _)| path.clone());
-            
-            if let Some(path) = file_path {
-                file_tables.entry(path).or_default().push(table);
-            } else {
-                orphan_tables.push(table);
-            }
-        }
-        
-        // 创建目录到文件的映射
-        let mut dir_files: HashMap<PathBuf, Vec<(String, Vec<&crate::graph::TableNode>)>> = HashMap::new();
-        
-        // 将文件按目录分组
-        for (file_path, tables) in &file_tables {
-            let path = Path::new(file_path);
-            if let Some(parent) = path.parent() {
-                let parent_path = parent.to_path_buf();
-                dir_files.entry(parent_path).or_default().push((file_path.clone(), tables.clone()));
-            } else {
-                // 如果文件没有父目录，将其放入根目录组
-                dir_files.entry(PathBuf::from("")).or_default().push((file_path.clone(), tables.clone()));
-            }
-        }
-        
-        // 为每个目录创建一个subgraph，然后在其中为每个文件创建一个subgraph
-        let mut dir_index = 0;
-        let root_path = Path::new(&self.root);
-        
-        for (dir_path, dir_files) in &dir_files {
-            // 使用相对于项目根目录的目录路径作为subgraph标题，确保以"/"开头
-            let dir_title = if dir_path.as_os_str().is_empty() {
-                "/".to_string() // 根目录使用"/"作为标题
-            } else if let Ok(relative) = dir_path.strip_prefix(root_path) {
-                let rel_str = relative.to_string_lossy().to_string();
-                if rel_str.is_empty() {
-                    "/".to_string()
-                } else {
-                    format!("/{}" , rel_str)
-                }
-            } else {
-                format!("/{}" , dir_path.to_string_lossy())
-            };
-            
-            // 添加目录级subgraph开始标记 - 确保ID格式正确
-            mermaid.push_str(&format!("    subgraph dir{} [\"{}\"]\n", dir_index, dir_title));
-            
-            // 为目录中的每个文件创建一个subgraph
-            let mut file_index = 0;
-            for (file_path, file_tables) in dir_files {
-                // 只使用文件名作为subgraph标题，而不是完整路径
-                let path = Path::new(file_path);
-                let file_title = path.file_name()
-                    .map(|name| name.to_string_lossy())
-                    .unwrap_or_else(|| {
-                        // 如果无法获取文件名，则使用相对路径或完整路径
-                        if let Ok(relative) = path.strip_prefix(root_path) {
-                            relative.to_string_lossy()
-                        } else {
-                            path.to_string_lossy()
-                        }
-                    });
-                
-                // 添加文件级subgraph开始标记 - 确保ID格式正确
-                mermaid.push_str(&format!("        subgraph file{}_{} [\"{}\"]\n", dir_index, file_index, file_title));
-                
-                // 添加当前文件中的所有节点，使用新的层次结构
-                for table in file_tables {
-                    for section in &table.sections {
-                        self.add_mermaid_cell_with_type_subgraphs(table.id, section, &mut mermaid, 3);
-                    }
-                }
-                
-                // 添加文件级subgraph结束标记
-                mermaid.push_str("        end\n");
-                
-                file_index += 1;
-            }
-            
-            // 添加目录级subgraph结束标记
-            mermaid.push_str("    end\n");
-            
-            dir_index += 1;
-        }
-        
-        // 添加未包含在任何文件中的节点
-        for table in &orphan_tables {
-            for section in &table.sections {
-                self.add_mermaid_cell_with_type_subgraphs(table.id, section, &mut mermaid, 1);
+
+    /// Generate a structured graph model for frontend consumption
+    pub fn generate_graph(&self) -> Graph {
+        match self.granularity {
+            Granularity::Symbol => {
+                let mut graph = self.generate_symbol_graph();
+                graph.unreferenced = self.find_dead_code();
+                graph
             }
+            _ => self.generate_group_graph(),
         }
-        
-        // 添加边
-        for edge in &edges {
-            let from = format!("{}_{}_{}", edge.from.0, edge.from.1, edge.from.2);
-            let to = format!("{}_{}_{}", edge.to.0, edge.to.1, edge.to.2);
-            mermaid.push_str(&format!("    {} --> {}\n", from, to));
-        }
-        
-        mermaid
     }
-    
-    fn add_mermaid_cell(&self, table_id: u32, cell: &Cell, mermaid: &mut String) {
-        let id = format!("{}_{}_{}", table_id, cell.range_start.0, cell.range_start.1);
-        let label = cell.title.replace('"', "\\\"").replace('[', "\\[").replace(']', "\\]");
-        
-        mermaid.push_str(&format!("    {}[\"{}\"]" , id, label));
-        
-        // 添加样式
-        if !cell.style.classes.is_empty() {
-            let style_class = format!("{:?}", cell.style.classes.iter().next().unwrap()).to_lowercase();
-            mermaid.push_str(&format!(":::{}\n", style_class));
-        } else {
-            mermaid.push_str("\n");
-        }
-        
-        // 递归处理子节点
-        for child in &cell.children {
-            self.add_mermaid_cell(table_id, child, mermaid);
-        }
+
+    /// Finds the recursive symbols, or the circular dependencies between files or directories
+    /// depending on the granularity.
+    pub fn find_cycles(&self) -> CycleReport {
+        self.generate_symbol_graph().find_cycles(self.granularity)
     }
-    
-    fn add_mermaid_cell_with_type_subgraphs(&self, table_id: u32, cell: &Cell, mermaid: &mut String, indent_level: usize) {
-        let indent = "    ".repeat(indent_level);
-        let id = format!("{}_{}_{}", table_id, cell.range_start.0, cell.range_start.1);
-        let label = cell.title.replace('"', "\\\"").replace('[', "\\[").replace(']', "\\]");
-        
-        // 检查是否是容器类型（可以包含其他符号的类型）
-        let is_container_type = if !cell.style.classes.is_empty() {
-            // 检查是否包含Type、Interface、Module等容器类型的样式类
-            // 或者是只有Cell类但有子节点的情况（如impl块）
-            cell.style.classes.contains(CssClass::Type) ||
-            cell.style.classes.contains(CssClass::Interface) ||
-            cell.style.classes.contains(CssClass::Module) ||
-            (cell.style.classes.contains(CssClass::Cell) && 
-             !cell.style.classes.contains(CssClass::Function) &&
-             !cell.style.classes.contains(CssClass::Method) &&
-             !cell.style.classes.contains(CssClass::Constructor) &&
-             !cell.style.classes.contains(CssClass::Property))
-        } else {
-            false
-        };
-        
-        if is_container_type && !cell.children.is_empty() {
-            // 如果是容器类型且有子节点，创建一个subgraph
-            mermaid.push_str(&format!("{}subgraph {} [\"{}\"]
-", indent, id, label));
-            
-            // 添加子节点（方法、属性等）
-            for child in &cell.children {
-                self.add_mermaid_cell_with_type_subgraphs(table_id, child, mermaid, indent_level + 1);
-            }
-            
-            mermaid.push_str(&format!("{}end\n", indent));
-        } else {
-            // 普通节点或叶子节点
-            mermaid.push_str(&format!("{}{}[\"{}\"]" , indent, id, label));
-            
-            // 添加样式
-            if !cell.style.classes.is_empty() {
-                // 获取主要的样式类用于显示
-                let primary_class = if cell.style.classes.contains(CssClass::Type) {
-                    "type"
-                } else if cell.style.classes.contains(CssClass::Interface) {
-                    "interface"
-                } else if cell.style.classes.contains(CssClass::Module) {
-                    "module"
-                } else if cell.style.classes.contains(CssClass::Function) {
-                    "function"
-                } else if cell.style.classes.contains(CssClass::Method) {
-                    "method"
-                } else if cell.style.classes.contains(CssClass::Constructor) {
-                    "constructor"
-                } else if cell.style.classes.contains(CssClass::Property) {
-                    "property"
-                } else if cell.style.classes.contains(CssClass::Impl) {
-                    "impl"
-                } else {
-                    "cell"
+
+    /// Computes the fan-in, fan-out, depth and betweenness of the symbols, and their per-file
+    /// aggregates. Depths are measured from the symbols without callers.
+    pub fn compute_metrics(&self) -> GraphMetrics {
+        self.generate_symbol_graph().compute_metrics(&[])
+    }
+
+    /// Finds the call chains from `from` to `to`, see `Graph::find_paths`.
+    pub fn find_paths(
+        &self,
+        from: GlobalPosition,
+        to: GlobalPosition,
+        max_paths: usize,
+        max_len: usize,
+    ) -> Vec<Vec<GlobalPosition>> {
+        self.generate_symbol_graph().find_paths(from, to, max_paths, max_len)
+    }
+
+    /// Background colors of the symbols of `graph` for `DotOptions::node_metric`, keyed by file id.
+    fn metric_colors(&self, graph: &Graph) -> HashMap<u32, HashMap<(u32, u32), String>> {
+        let values = graph
+            .compute_metrics(&[])
+            .symbols
+            .into_iter()
+            .filter_map(|metrics| {
+                let value = match self.dot_options.node_metric {
+                    NodeMetric::Off => return None,
+                    NodeMetric::FanIn => metrics.fan_in as f64,
+                    NodeMetric::FanOut => metrics.fan_out as f64,
+                    NodeMetric::Depth => metrics.depth? as f64,
+                    NodeMetric::Betweenness => metrics.betweenness,
                 };
-                mermaid.push_str(&format!(":::{}\n", primary_class));
-            } else {
-                mermaid.push_str("\n");
-            }
-            
-            // 递归处理子节点（对于非容器类型节点）
-            for child in &cell.children {
-                self.add_mermaid_cell_with_type_subgraphs(table_id, child, mermaid, indent_level);
-            }
+                Some((metrics.position, value))
+            })
+            .collect::<Vec<_>>();
+
+        let max = values.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+        let mut colors: HashMap<u32, HashMap<_, _>> = HashMap::new();
+        for (position, value) in values {
+            let ratio = if max > 0.0 { value / max } else { 0.0 };
+            colors
+                .entry(position.file_id)
+                .or_default()
+                .insert((position.line, position.character), metric_color(ratio));
         }
+        colors
     }
-    
 
+    /// Ids of the files in the output, by path. Files are numbered from 1 in path order, so that
+    /// the output does not depend on the order they are added in, while `FileOutline::id` stays
+    /// stable for the highlights. Generators restricted from a larger one keep its numbering, and
+    /// number the files added later after it.
+    fn output_file_ids(&self) -> HashMap<&str, u32> {
+        let Some(kept) = &self.kept_file_ids else {
+            return self.files.keys().map(String::as_str).zip(1..).collect();
+        };
 
-    /// Generate a structured graph model for frontend consumption
-    pub fn generate_graph(&self) -> Graph {
-        let mut builder = GraphBuilder::new();
+        let mut next_id = kept.values().max().map_or(1, |id| id + 1);
+        self.files
+            .keys()
+            .map(|path| {
+                let id = kept.get(path).copied().unwrap_or_else(|| {
+                    next_id += 1;
+                    next_id - 1
+                });
+                (path.as_str(), id)
+            })
+            .collect()
+    }
+
+    fn generate_symbol_graph(&self) -> Graph {
+        let mut builder = GraphBuilder::with_root(&self.root);
+        let file_ids = self.output_file_ids();
         
-        // Add all files and their symbols
-        for (path, file_outline) in &self.files {
-            builder.add_file(path.clone(), file_outline.symbols.clone());
+        // Add all files and their symbols in path order
+        let mut symbols = self.symbols_with_callers();
+        for path in self.files.keys() {
+            let file_symbols = symbols.remove(path.as_str()).unwrap_or_default();
+            builder.add_file_with_id(file_ids[path.as_str()], path.clone(), file_symbols);
         }
         
         // Add call relationships
         for (callee_location, callers) in &self.incoming_calls {
-            if let Some(callee_file) = self.files.get(&callee_location.path) {
+            if let Some(&callee_file_id) = file_ids.get(callee_location.path.as_str()) {
                 let callee_global_pos = GlobalPosition::new(
-                    callee_file.id,
+                    callee_file_id,
                     Position {
                         line: callee_location.line,
                         character: callee_location.character,
@@ -525,8 +496,26 @@ impl GraphGenerator {
                 );
                 
                 for caller in callers {
-                    if let Some(caller_global_pos) = self.call_item_to_global_position(&caller.from) {
-                        builder.add_relation(caller_global_pos, callee_global_pos, RelationKind::Call);
+                    if let Some(caller_global_pos) = call_item_to_global_position(&file_ids, &caller.from) {
+                        builder.add_call_relation(caller_global_pos, callee_global_pos, &caller.from_ranges);
+                    }
+                }
+            }
+        }
+
+        for (caller_location, callees) in &self.outgoing_calls {
+            if let Some(&caller_file_id) = file_ids.get(caller_location.path.as_str()) {
+                let caller_global_pos = GlobalPosition::new(
+                    caller_file_id,
+                    Position {
+                        line: caller_location.line,
+                        character: caller_location.character,
+                    }
+                );
+
+                for callee in callees {
+                    if let Some(callee_global_pos) = call_item_to_global_position(&file_ids, &callee.to) {
+                        builder.add_call_relation(caller_global_pos, callee_global_pos, &callee.from_ranges);
                     }
                 }
             }
@@ -534,9 +523,9 @@ impl GraphGenerator {
         
         // Add interface implementations
         for (interface_location, implementations) in &self.interfaces {
-            if let Some(interface_file) = self.files.get(&interface_location.path) {
+            if let Some(&interface_file_id) = file_ids.get(interface_location.path.as_str()) {
                 let interface_global_pos = GlobalPosition::new(
-                    interface_file.id,
+                    interface_file_id,
                     Position {
                         line: interface_location.line,
                         character: interface_location.character,
@@ -544,9 +533,9 @@ impl GraphGenerator {
                 );
                 
                 for impl_location in implementations {
-                    if let Some(impl_file) = self.files.get(&impl_location.path) {
+                    if let Some(&impl_file_id) = file_ids.get(impl_location.path.as_str()) {
                         let impl_global_pos = GlobalPosition::new(
-                            impl_file.id,
+                            impl_file_id,
                             Position {
                                 line: impl_location.line,
                                 character: impl_location.character,
@@ -558,167 +547,70 @@ impl GraphGenerator {
             }
         }
         
+        for (kind, targets) in [
+            (RelationKind::Inherit, &self.subtypes),
+            (RelationKind::Reference, &self.references),
+        ] {
+            for (target, sources) in targets {
+                let Some(&target_file_id) = file_ids.get(target.path.as_str()) else {
+                    continue;
+                };
+                let target_global_pos = GlobalPosition {
+                    file_id: target_file_id,
+                    line: target.line,
+                    character: target.character,
+                };
+
+                for source in sources {
+                    if let Some(&source_file_id) = file_ids.get(source.path.as_str()) {
+                        let source_global_pos = GlobalPosition {
+                            file_id: source_file_id,
+                            line: source.line,
+                            character: source.character,
+                        };
+                        builder.add_relation(source_global_pos, target_global_pos, kind.clone());
+                    }
+                }
+            }
+        }
+
         builder.build()
     }
     
     pub fn generate_dot_source(&self) -> String {
-        let files = &self.files;
-
-        // TODO: it's better to construct tables before fetching call hierarchy, so that we can skip the filtered out symbols.
-        let mut tables = files
-            .values()
-            .map(|file| {
-                let mut table = self.lang.file_repr(file);
-                if let Some(cells) = self.highlights.get(&file.id) {
-                    table.highlight_cells(cells);
-                }
-
-                (file.id, table)
-            })
-            .collect::<HashMap<_, _>>();
-
-        let mut cell_ids = HashSet::new();
-        tables
-            .iter()
-            .flat_map(|(_, tbl)| tbl.sections.iter().map(|cell| (tbl.id, cell)))
-            .for_each(|(tid, cell)| self.collect_cell_ids(tid, cell, &mut cell_ids));
-        let cell_ids_ref = &cell_ids;
-
-        let updated_files = RefCell::new(HashSet::new());
-        let updated_files_ref = &updated_files;
-
-        let inserted_symbols = RefCell::new(HashSet::new());
-        let inserted_symbols_ref = &inserted_symbols;
-
-        let incoming_calls = self
-            .incoming_calls
-            .iter()
-            .filter_map(|(callee, callers)| {
-                let to = callee.location_id(files)?;
-
-                cell_ids.contains(&to).then_some((to, callers))
-            })
-            .flat_map(|(to, calls)| {
-                calls.into_iter().filter_map(move |call| {
-                    let from = call.from.location_id(files)?;
-
-                    // incoming calls may start from nested functions, which may not be included in file symbols in some lsp server implementations.
-                    // in that case, we add the missing nested symbol to the symbol list.
-                    // another approach would be to modify edges to make them start from the outter functions, which is not so accurate
-
-                    (cell_ids_ref.contains(&from)
-                        || inserted_symbols_ref.try_borrow().map(|b| b.contains(&from)).unwrap_or(false)
-                        || {
-                            let file = files.get(&call.from.uri.path)? as *const FileOutline;
-
-                            let updated = unsafe {
-                                self.try_insert_symbol(
-                                    &call.from,
-                                    file.cast_mut().as_mut().unwrap(),
-                                )
-                            };
-
-                            if updated {
-                                if let Ok(mut updated_files) = updated_files_ref.try_borrow_mut() {
-                                    updated_files.insert(call.from.uri.path.clone());
-                                }
-                                if let Ok(mut inserted_symbols) = inserted_symbols_ref.try_borrow_mut() {
-                                    inserted_symbols.insert(from);
-                                }
-                            }
-                            updated
-                        })
-                    .then_some(Edge {
-                        from,
-                        to,
-                        classes: EnumSet::new(),
-                    })
-                })
-            });
-
-        let outgoing_calls = self
-            .outgoing_calls
-            .iter()
-            .filter_map(|(caller, callees)| {
-                let from = caller.location_id(files)?;
-
-                cell_ids.contains(&from).then_some((from, callees))
-            })
-            .flat_map(|(from, callees)| {
-                callees.into_iter().filter_map(move |call| {
-                    let to = call.to.location_id(files)?;
-
-                    cell_ids_ref.contains(&to).then_some(Edge {
-                        from,
-                        to,
-                        classes: EnumSet::new(),
-                    })
-                })
-            });
-
-        let implementations = self
-            .interfaces
-            .iter()
-            .filter_map(|(interface, implementations)| {
-                let to = interface.location_id(files)?;
-
-                cell_ids.contains(&to).then_some((to, implementations))
-            })
-            .flat_map(|(to, implementations)| {
-                implementations.into_iter().filter_map(move |location| {
-                    let from = location.location_id(files)?;
-
-                    cell_ids_ref.contains(&&from).then_some(Edge {
-                        from,
-                        to,
-                        classes: CssClass::Impl.into(),
-                    })
-                })
-            });
+        if self.granularity != Granularity::Symbol {
+            return self.generate_group_dot_source();
+        }
 
-        let edges = incoming_calls
-            .chain(outgoing_calls)
-            .chain(implementations)
-            .collect::<HashSet<_>>();
+        self.render_dot(&self.generate_symbol_graph())
+    }
 
-        if let Ok(updated) = updated_files.try_borrow() {
-            updated.iter().for_each(|path| {
-                let file = files.get(path).unwrap();
-                let table = tables.get_mut(&file.id).unwrap();
-                *table = self.lang.file_repr(file);
-            });
+    /// Lays the graph out and draws it as SVG, without going through Graphviz.
+    pub fn generate_svg(&self, routing: EdgeRouting) -> String {
+        if self.granularity != Granularity::Symbol {
+            return self.generate_group_svg(routing);
         }
 
-        let subgraphs = self.subgraphs(files.iter().map(|(_, f)| f));
-
-        Dot::generate_dot_source(tables.into_values(), edges.into_iter(), &subgraphs)
+        self.render_svg(&self.generate_symbol_graph(), routing)
     }
 
+    /// Nested subgraphs of the directories of `files`, given as their ids and paths
     fn subgraphs<'a, I>(&'a self, files: I) -> Vec<Subgraph>
     where
-        I: Iterator<Item = &'a FileOutline>,
+        I: Iterator<Item = (u32, &'a Path)>,
     {
         let mut dirs = BTreeMap::new();
-        for f in files {
-            let parent = f.path.parent().unwrap();
+        for (id, path) in files {
+            let parent = path.parent().unwrap();
             dirs.entry(parent)
                 .or_insert(Vec::new())
-                .push(f.path.clone());
+                .push(id);
         }
 
         let mut subgraphs: Vec<Subgraph> = vec![];
 
-        dirs.iter().for_each(|(dir, files)| {
-            let nodes = files
-                .iter()
-                .map(|path| {
-                    self.files
-                        .get(path.to_str().unwrap())
-                        .unwrap()
-                        .id
-                        .to_string()
-                })
-                .collect::<Vec<_>>();
+        dirs.iter().for_each(|(dir, ids)| {
+            let nodes = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
 
             let dir = dir.strip_prefix(&self.root).unwrap_or(dir);
             self.add_subgraph(dir, nodes, &mut subgraphs);
@@ -755,14 +647,45 @@ impl GraphGenerator {
             .for_each(|child| self.collect_cell_ids(table_id, child, ids));
     }
 
-    fn call_item_to_global_position(&self, item: &CallHierarchyItem) -> Option<GlobalPosition> {
-        let file_path = item.uri.path.as_str();
-        let file = self.files.get(file_path)?;
-        Some(GlobalPosition::new(file.id, item.selection_range.start))
+    /// Symbols of each file, along with the nested functions making incoming calls.
+    ///
+    /// Incoming calls may start from nested functions, which may not be included in file symbols
+    /// in some lsp server implementations. In that case, we add the missing nested symbols, rather
+    /// than making the calls start from the outer functions, which would be less accurate.
+    fn symbols_with_callers(&self) -> HashMap<&str, Vec<DocumentSymbol>> {
+        fn collect_positions(file_id: u32, symbols: &[DocumentSymbol], positions: &mut HashSet<(u32, u32, u32)>) {
+            for symbol in symbols {
+                let start = symbol.selection_range.start;
+                positions.insert((file_id, start.line, start.character));
+                collect_positions(file_id, &symbol.children, positions);
+            }
+        }
+
+        let mut symbols = HashMap::new();
+        let mut positions = HashSet::new();
+        for (path, file) in &self.files {
+            symbols.insert(path.as_str(), file.symbols.clone());
+            collect_positions(file.id, &file.symbols, &mut positions);
+        }
+
+        for call in self.incoming_calls.values().flatten() {
+            let path = call.from.uri.path.as_str();
+            let (Some(file), Some(file_symbols)) = (self.files.get(path), symbols.get_mut(path)) else {
+                continue;
+            };
+            let start = call.from.selection_range.start;
+            let position = (file.id, start.line, start.character);
+
+            if !positions.contains(&position) && self.try_insert_symbol(&call.from, file_symbols) {
+                positions.insert(position);
+            }
+        }
+
+        symbols
     }
-    
-    fn try_insert_symbol(&self, item: &CallHierarchyItem, file: &mut FileOutline) -> bool {
-        let mut symbols = &mut file.symbols;
+
+    fn try_insert_symbol(&self, item: &CallHierarchyItem, symbols: &mut Vec<DocumentSymbol>) -> bool {
+        let mut symbols = symbols;
         let mut is_subsymbol = false;
 
         loop {
@@ -820,3 +743,8 @@ impl GraphGenerator {
         }
     }
 }
+
+fn call_item_to_global_position(file_ids: &HashMap<&str, u32>, item: &CallHierarchyItem) -> Option<GlobalPosition> {
+    let file_id = file_ids.get(item.uri.path.as_str())?;
+    Some(GlobalPosition::new(*file_id, item.selection_range.start))
+}
@@ -0,0 +1,271 @@
+//! Collapsed views of the graph, with one node per file or per directory.
+
+use {
+    super::GraphGenerator,
+    crate::{
+        graph::{
+            dot::Dot,
+            mermaid::mermaid_edge,
+            plantuml::plantuml_edge,
+            svg::{EdgeRouting, SvgRenderer},
+            CssClass, Edge, GroupEdge, GroupNode, Renderer, Subgraph, TableNode,
+        },
+        graph_model::{File, GlobalPosition, Granularity, Graph, Relation, RelationKind},
+    },
+    enumset::EnumSet,
+    std::{
+        collections::{BTreeMap, HashMap},
+        path::Path,
+    },
+};
+
+pub(super) fn relation_class(kind: &RelationKind) -> EnumSet<CssClass> {
+    match kind {
+        RelationKind::Call => EnumSet::new(),
+        RelationKind::Impl => CssClass::Impl.into(),
+        RelationKind::Inherit => CssClass::Inherit.into(),
+        RelationKind::Reference => CssClass::Reference.into(),
+    }
+}
+
+fn class_relation(classes: EnumSet<CssClass>) -> RelationKind {
+    if classes.contains(CssClass::Impl) {
+        RelationKind::Impl
+    } else if classes.contains(CssClass::Inherit) {
+        RelationKind::Inherit
+    } else if classes.contains(CssClass::Reference) {
+        RelationKind::Reference
+    } else {
+        RelationKind::Call
+    }
+}
+
+/// Flattens nested subgraphs into the full directory paths and the files directly in them.
+fn flatten_subgraphs(subgraphs: &[Subgraph], parent: &Path, dirs: &mut Vec<(String, Vec<u32>)>) {
+    for subgraph in subgraphs {
+        let dir = parent.join(&subgraph.title);
+        let files = subgraph
+            .nodes
+            .iter()
+            .filter_map(|id| id.parse().ok())
+            .collect();
+        dirs.push((dir.to_string_lossy().into_owned(), files));
+
+        flatten_subgraphs(&subgraph.subgraphs, &dir, dirs);
+    }
+}
+
+impl GraphGenerator {
+    /// Sets the level of detail of `generate_dot_source`, `generate_mermaid_source` and
+    /// `generate_graph`.
+    pub fn set_granularity(&mut self, granularity: Granularity) {
+        self.granularity = granularity;
+    }
+
+    /// Nodes of the collapsed graph, and the node of each file.
+    fn group_nodes(&self) -> (Vec<GroupNode>, HashMap<u32, u32>) {
+        let mut nodes = vec![];
+        let mut node_of_file = HashMap::new();
+
+        match self.granularity {
+            Granularity::Symbol | Granularity::File => {
+                for (path, id) in self.output_file_ids() {
+                    let title = Path::new(path)
+                        .strip_prefix(&self.root)
+                        .unwrap_or(Path::new(path))
+                        .to_string_lossy()
+                        .into_owned();
+                    nodes.push(GroupNode {
+                        id,
+                        title,
+                        path: path.to_string(),
+                    });
+                    node_of_file.insert(id, id);
+                }
+                nodes.sort_by_key(|node| node.id);
+            }
+            Granularity::Directory => {
+                let mut dirs = vec![];
+                let file_ids = self.output_file_ids();
+                let files = self
+                    .files
+                    .iter()
+                    .map(|(path, file)| (file_ids[path.as_str()], file.path.as_path()));
+                flatten_subgraphs(
+                    &self.subgraphs(files),
+                    Path::new(""),
+                    &mut dirs,
+                );
+                dirs.sort();
+
+                for (id, (dir, files)) in (1..).zip(dirs) {
+                    node_of_file.extend(files.into_iter().map(|file| (file, id)));
+                    nodes.push(GroupNode {
+                        id,
+                        title: format!("/{}", dir),
+                        path: Path::new(&self.root)
+                            .join(&dir)
+                            .to_string_lossy()
+                            .into_owned(),
+                    });
+                }
+            }
+        }
+
+        (nodes, node_of_file)
+    }
+
+    /// Aggregates the symbol-level relations into edges between the group nodes. Each call
+    /// counts once per call site, and the relations inside a node are dropped.
+    fn group_edges(&self, node_of_file: &HashMap<u32, u32>) -> Vec<GroupEdge> {
+        let mut edges: BTreeMap<(u32, u32), GroupEdge> = BTreeMap::new();
+
+        for relation in self.generate_symbol_graph().relations() {
+            let (Some(&from), Some(&to)) = (
+                node_of_file.get(&relation.from.file_id),
+                node_of_file.get(&relation.to.file_id),
+            ) else {
+                continue;
+            };
+            if from == to {
+                continue;
+            }
+
+            let edge = edges.entry((from, to)).or_insert(GroupEdge {
+                from,
+                to,
+                classes: relation_class(&relation.kind),
+                count: 0,
+            });
+            // calls take precedence over the other relations between the same nodes
+            if matches!(relation.kind, RelationKind::Call) {
+                edge.classes = EnumSet::new();
+            }
+            edge.count += relation.call_count.max(1);
+        }
+
+        edges.into_values().collect()
+    }
+
+    pub(super) fn generate_group_dot_source(&self) -> String {
+        let (nodes, node_of_file) = self.group_nodes();
+        let mut edges = self.group_edges(&node_of_file);
+        if self.dot_options.highlight_cycles {
+            GroupEdge::mark_cycles(&mut edges);
+        }
+
+        Dot::generate_group_dot_source(&nodes, &edges, &self.dot_options)
+    }
+
+    pub(super) fn generate_group_mermaid_source(&self) -> String {
+        let (nodes, node_of_file) = self.group_nodes();
+        let edges = self.group_edges(&node_of_file);
+
+        let mut mermaid = String::from("flowchart LR\n");
+        for node in &nodes {
+            mermaid.push_str(&format!(
+                "    n{}[\"{}\"]\n",
+                node.id,
+                node.title.replace('"', "#quot;")
+            ));
+        }
+        for edge in &edges {
+            let (from, to) = (format!("n{}", edge.from), format!("n{}", edge.to));
+            mermaid.push_str(&mermaid_edge(
+                &from,
+                &to,
+                edge.classes,
+                Some(edge.count),
+            ));
+        }
+
+        mermaid
+    }
+
+    pub(super) fn generate_group_plantuml_source(&self) -> String {
+        let (nodes, node_of_file) = self.group_nodes();
+        let edges = self.group_edges(&node_of_file);
+
+        let mut uml = String::from("@startuml\n");
+        for node in &nodes {
+            uml.push_str(&format!(
+                "component \"{}\" as n{}\n",
+                node.title.replace('"', "'"),
+                node.id
+            ));
+        }
+        for edge in &edges {
+            let (from, to) = (format!("n{}", edge.from), format!("n{}", edge.to));
+            uml.push_str(&plantuml_edge(&from, &to, edge.classes, Some(edge.count)));
+        }
+        uml.push_str("@enduml\n");
+
+        uml
+    }
+
+    /// Draws the group nodes as tables without cells, so the edges attach to their titles.
+    pub(super) fn generate_group_svg(&self, routing: EdgeRouting) -> String {
+        let (nodes, node_of_file) = self.group_nodes();
+        let mut edges = self.group_edges(&node_of_file);
+        if self.dot_options.highlight_cycles {
+            GroupEdge::mark_cycles(&mut edges);
+        }
+
+        let tables = nodes
+            .into_iter()
+            .map(|node| TableNode {
+                id: node.id,
+                title: node.title,
+                path: Some(node.path),
+                sections: vec![],
+            })
+            .collect();
+        let edges = edges
+            .into_iter()
+            .map(|edge| Edge {
+                from: (edge.from, 0, 0),
+                to: (edge.to, 0, 0),
+                classes: edge.classes,
+                call_sites: vec![],
+            })
+            .collect();
+
+        SvgRenderer {
+            options: &self.dot_options,
+            routing,
+        }
+        .render(tables, edges, &[])
+    }
+
+    pub(super) fn generate_group_graph(&self) -> Graph {
+        let (nodes, node_of_file) = self.group_nodes();
+        let edges = self.group_edges(&node_of_file);
+
+        let position = |id| GlobalPosition {
+            file_id: id,
+            line: 0,
+            character: 0,
+        };
+
+        Graph::new(
+            nodes
+                .into_iter()
+                .map(|node| File {
+                    id: node.id,
+                    path: node.path,
+                    symbols: vec![],
+                })
+                .collect(),
+            edges
+                .into_iter()
+                .map(|edge| Relation {
+                    from: position(edge.from),
+                    to: position(edge.to),
+                    kind: class_relation(edge.classes),
+                    call_sites: vec![],
+                    call_count: edge.count,
+                })
+                .collect(),
+        )
+    }
+}
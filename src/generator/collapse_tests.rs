@@ -0,0 +1,124 @@
+use super::{
+    test_support::{func, incoming},
+    Granularity, GraphGenerator,
+};
+
+/// src/a.rs: f, f2；src/b.rs: g；lib/c.rs: h (文件按路径编号，lib/c.rs 为 1)
+/// f -> g (两处调用)，f -> h，g -> h，f2 -> f (同一文件内)
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let (f, f2, g, h) = (func("f", 0), func("f2", 10), func("g", 0), func("h", 0));
+    generator.add_file("/root/src/a.rs".to_string(), vec![f.clone(), f2.clone()]);
+    generator.add_file("/root/src/b.rs".to_string(), vec![g.clone()]);
+    generator.add_file("/root/lib/c.rs".to_string(), vec![h.clone()]);
+
+    generator.add_incoming_calls(
+        "/root/src/b.rs".to_string(),
+        g.selection_range.start,
+        vec![incoming("/root/src/a.rs", &f, &[1, 2])],
+    );
+    generator.add_incoming_calls(
+        "/root/lib/c.rs".to_string(),
+        h.selection_range.start,
+        vec![
+            incoming("/root/src/a.rs", &f, &[3]),
+            incoming("/root/src/b.rs", &g, &[1]),
+        ],
+    );
+    generator.add_incoming_calls(
+        "/root/src/a.rs".to_string(),
+        f.selection_range.start,
+        vec![incoming("/root/src/a.rs", &f2, &[11])],
+    );
+
+    generator
+}
+
+fn edges(generator: &GraphGenerator) -> Vec<(u32, u32, usize)> {
+    generator
+        .generate_graph()
+        .relations()
+        .iter()
+        .map(|r| (r.from.file_id, r.to.file_id, r.call_count))
+        .collect()
+}
+
+#[test]
+fn file_granularity() {
+    let mut generator = sample_generator();
+    generator.set_granularity(Granularity::File);
+
+    let graph = generator.generate_graph();
+    let files = graph
+        .files()
+        .iter()
+        .map(|f| (f.id, f.path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        files,
+        vec![
+            (1, "/root/lib/c.rs"),
+            (2, "/root/src/a.rs"),
+            (3, "/root/src/b.rs")
+        ],
+        "文件按路径编号"
+    );
+    assert_eq!(
+        edges(&generator),
+        vec![(2, 1, 1), (2, 3, 2), (3, 1, 1)],
+        "同一文件内的调用应该被忽略，调用次数应该被累加"
+    );
+
+    let dot = generator.generate_dot_source();
+    assert!(
+        dot.contains(r#""2" [id="2", label="src/a.rs", href="/root/src/a.rs"];"#),
+        "每个文件应该是一个节点:\n{}",
+        dot
+    );
+    assert!(
+        dot.contains(r#""2" -> "3" [id="2 -> 3", label="2"]"#),
+        "边应该标注调用次数:\n{}",
+        dot
+    );
+    assert!(!dot.contains("<TABLE"), "不应该生成符号表格");
+
+    let mermaid = generator.generate_mermaid_source();
+    assert!(mermaid.contains("    n2[\"src/a.rs\"]\n"), "{}", mermaid);
+    assert!(mermaid.contains("    n2 -->|2| n3\n"), "{}", mermaid);
+    assert!(mermaid.contains("    n3 -->|1| n1\n"), "{}", mermaid);
+}
+
+#[test]
+fn directory_granularity() {
+    let mut generator = sample_generator();
+    generator.set_granularity(Granularity::Directory);
+
+    let graph = generator.generate_graph();
+    let dirs = graph
+        .files()
+        .iter()
+        .map(|f| (f.id, f.path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(dirs, vec![(1, "/root/lib"), (2, "/root/src")]);
+    assert_eq!(
+        edges(&generator),
+        vec![(2, 1, 2)],
+        "目录之间的调用次数应该被累加"
+    );
+
+    let mermaid = generator.generate_mermaid_source();
+    assert!(mermaid.contains("    n2[\"/src\"]\n"), "{}", mermaid);
+    assert!(mermaid.contains("    n2 -->|2| n1\n"), "{}", mermaid);
+}
+
+#[test]
+fn symbol_granularity_by_default() {
+    let mut generator = sample_generator();
+    let symbol_dot = generator.generate_dot_source();
+    assert!(symbol_dot.contains("<TABLE"), "默认应该生成符号级别的图");
+
+    generator.set_granularity(Granularity::File);
+    generator.set_granularity(Granularity::Symbol);
+    assert_eq!(generator.generate_graph().relations().len(), 4);
+}
@@ -0,0 +1,142 @@
+use {
+    super::{
+        test_support::{func, incoming, pos},
+        DotOptions, Granularity, GraphGenerator,
+    },
+    crate::graph_model::CycleNode,
+};
+
+fn symbol(file_id: u32, line: u32) -> CycleNode {
+    CycleNode::Symbol(pos(file_id, line))
+}
+
+/// src/a.rs: f, r；src/b.rs: g；lib/c.rs: h (文件按路径编号，lib/c.rs 为 1)
+/// f -> g -> f (互相递归)，r -> r (自递归)，f -> h，h 不在任何循环中
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let (f, r, g, h) = (func("f", 0), func("r", 10), func("g", 0), func("h", 0));
+    generator.add_file("/root/src/a.rs".to_string(), vec![f.clone(), r.clone()]);
+    generator.add_file("/root/src/b.rs".to_string(), vec![g.clone()]);
+    generator.add_file("/root/lib/c.rs".to_string(), vec![h.clone()]);
+
+    generator.add_incoming_calls(
+        "/root/src/b.rs".to_string(),
+        g.selection_range.start,
+        vec![incoming("/root/src/a.rs", &f, &[1])],
+    );
+    generator.add_incoming_calls(
+        "/root/src/a.rs".to_string(),
+        f.selection_range.start,
+        vec![incoming("/root/src/b.rs", &g, &[1])],
+    );
+    generator.add_incoming_calls(
+        "/root/src/a.rs".to_string(),
+        r.selection_range.start,
+        vec![incoming("/root/src/a.rs", &r, &[11])],
+    );
+    generator.add_incoming_calls(
+        "/root/lib/c.rs".to_string(),
+        h.selection_range.start,
+        vec![incoming("/root/src/a.rs", &f, &[1])],
+    );
+
+    generator
+}
+
+#[test]
+fn symbol_cycles() {
+    let generator = sample_generator();
+
+    let report = generator.find_cycles();
+    assert_eq!(report.granularity, Granularity::Symbol);
+    assert_eq!(
+        report.components,
+        vec![vec![symbol(2, 0), symbol(3, 0)], vec![symbol(2, 10)]],
+        "应该找到互相递归和自递归，h 不应该出现"
+    );
+}
+
+#[test]
+fn file_and_directory_cycles() {
+    let mut generator = sample_generator();
+
+    generator.set_granularity(Granularity::File);
+    let report = generator.find_cycles();
+    assert_eq!(
+        report.components,
+        vec![vec![CycleNode::File(2), CycleNode::File(3)]],
+        "a.rs 和 b.rs 之间存在循环依赖"
+    );
+
+    generator.set_granularity(Granularity::Directory);
+    let report = generator.find_cycles();
+    assert_eq!(
+        report.components,
+        vec![vec![CycleNode::Directory("/root/src".to_string())]],
+        "目录内部的调用构成自环"
+    );
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(
+        json,
+        r#"{"granularity":"directory","components":[["/root/src"]]}"#
+    );
+}
+
+#[test]
+fn no_cycles() {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+    let (f, g) = (func("f", 0), func("g", 5));
+    generator.add_file("/root/a.rs".to_string(), vec![f.clone(), g.clone()]);
+    generator.add_incoming_calls(
+        "/root/a.rs".to_string(),
+        g.selection_range.start,
+        vec![incoming("/root/a.rs", &f, &[1])],
+    );
+
+    assert!(generator.find_cycles().components.is_empty());
+}
+
+#[test]
+fn highlight_cycle_edges() {
+    let mut generator = sample_generator();
+
+    let dot = generator.generate_dot_source();
+    assert!(!dot.contains("cycle"), "默认不应该高亮循环:\n{}", dot);
+
+    generator.set_dot_options(DotOptions {
+        highlight_cycles: true,
+        ..Default::default()
+    });
+    let dot = generator.generate_dot_source();
+    let cycle_edges = dot
+        .lines()
+        .filter(|line| line.contains("->") && line.contains(r#"class="cycle""#))
+        .count();
+    assert_eq!(
+        cycle_edges, 3,
+        "f -> g、g -> f 和 r -> r 应该被高亮:\n{}",
+        dot
+    );
+    assert!(
+        dot.lines()
+            .filter(|line| line.contains(r#"class="cycle""#))
+            .all(|line| line.contains(r##"color="#d1242f""##)),
+        "{}",
+        dot
+    );
+
+    generator.set_granularity(Granularity::File);
+    let dot = generator.generate_dot_source();
+    assert!(
+        dot.contains(r#""2" -> "3""#) && dot.contains(r#""3" -> "2""#),
+        "{}",
+        dot
+    );
+    let cycle_edges = dot
+        .lines()
+        .filter(|line| line.contains(r#"class="cycle""#))
+        .count();
+    assert_eq!(cycle_edges, 2, "文件之间的循环依赖应该被高亮:\n{}", dot);
+}
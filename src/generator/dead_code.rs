@@ -0,0 +1,144 @@
+//! Functions and methods that are never called from the analyzed files.
+
+use {
+    super::{traversal::FUNC_KINDS, GraphGenerator, SymbolLocation},
+    crate::{graph_model::GlobalPosition, lang::Language, lsp_types::DocumentSymbol},
+    std::collections::HashSet,
+};
+
+/// Symbols that are not reported by `GraphGenerator::find_dead_code` even without callers
+#[derive(Debug, Clone)]
+pub struct DeadCodeOptions {
+    /// Names of the functions called by the runtime rather than by other code
+    pub entry_points: Vec<String>,
+    /// Skips the tests and the symbols nested in them, as recognized by the language
+    pub exempt_tests: bool,
+    /// Skips the interfaces and implementations connected by `Impl` edges, and their members
+    pub exempt_impls: bool,
+    /// Skips the symbols visible outside of their package, as recognized by the language
+    pub exempt_exported: bool,
+}
+
+impl Default for DeadCodeOptions {
+    fn default() -> Self {
+        Self {
+            entry_points: vec!["main".to_string()],
+            exempt_tests: true,
+            exempt_impls: true,
+            exempt_exported: true,
+        }
+    }
+}
+
+struct DeadCodeScan<'a> {
+    path: &'a str,
+    file_id: u32,
+    options: &'a DeadCodeOptions,
+    lang: &'a dyn Language,
+    called: &'a HashSet<SymbolLocation>,
+    impls: &'a HashSet<&'a SymbolLocation>,
+}
+
+impl DeadCodeScan<'_> {
+    fn visit(&self, symbols: &[DocumentSymbol], exempt: bool, dead: &mut Vec<GlobalPosition>) {
+        for symbol in symbols {
+            let location =
+                SymbolLocation::new(self.path.to_string(), &symbol.selection_range.start);
+
+            // tests and implementations exempt their members too, unlike entry points and exports
+            let exempt = exempt
+                || (self.options.exempt_tests && self.lang.is_test(self.path, symbol))
+                || (self.options.exempt_impls && self.is_impl(symbol, &location));
+
+            let exported = self.options.exempt_exported && self.lang.is_exported(symbol);
+            let unreferenced = FUNC_KINDS.contains(&symbol.kind)
+                && !exempt
+                && !exported
+                && !self.called.contains(&location)
+                && !self.options.entry_points.contains(&symbol.name);
+            if unreferenced {
+                dead.push(GlobalPosition::new(
+                    self.file_id,
+                    symbol.selection_range.start,
+                ));
+            }
+
+            self.visit(&symbol.children, exempt, dead);
+        }
+    }
+
+    /// Implementation locations may point at the start of either range of the symbol.
+    fn is_impl(&self, symbol: &DocumentSymbol, location: &SymbolLocation) -> bool {
+        self.impls.contains(location)
+            || self.impls.contains(&SymbolLocation::new(
+                self.path.to_string(),
+                &symbol.range.start,
+            ))
+    }
+}
+
+impl GraphGenerator {
+    /// Sets the exemptions of `find_dead_code`.
+    pub fn set_dead_code_options(&mut self, options: DeadCodeOptions) {
+        self.dead_code_options = options;
+    }
+
+    /// Returns the functions and methods that no other symbol of the analyzed files calls,
+    /// except the ones exempted by the dead code options, sorted by position.
+    ///
+    /// The result is only as complete as the collected calls, so these are candidates to
+    /// review rather than code that is safe to delete.
+    pub fn find_dead_code(&self) -> Vec<GlobalPosition> {
+        let called = self.called_symbols();
+        let impls = self
+            .interfaces
+            .iter()
+            .flat_map(|(interface, implementations)| {
+                std::iter::once(interface).chain(implementations)
+            })
+            .collect::<HashSet<_>>();
+
+        let file_ids = self.output_file_ids();
+        let mut dead = vec![];
+        for (path, file) in &self.files {
+            let scan = DeadCodeScan {
+                path,
+                file_id: file_ids[path.as_str()],
+                options: &self.dead_code_options,
+                lang: &*self.lang,
+                called: &called,
+                impls: &impls,
+            };
+            scan.visit(&file.symbols, false, &mut dead);
+        }
+
+        dead.sort();
+        dead
+    }
+
+    /// Symbols called by another symbol, recursive calls are not counted.
+    fn called_symbols(&self) -> HashSet<SymbolLocation> {
+        let incoming = self.incoming_calls.iter().flat_map(|(callee, calls)| {
+            calls.iter().map(move |call| {
+                let caller = SymbolLocation::new(
+                    call.from.uri.path.clone(),
+                    &call.from.selection_range.start,
+                );
+                (caller, callee.clone())
+            })
+        });
+        let outgoing = self.outgoing_calls.iter().flat_map(|(caller, calls)| {
+            calls.iter().map(move |call| {
+                let callee =
+                    SymbolLocation::new(call.to.uri.path.clone(), &call.to.selection_range.start);
+                (caller.clone(), callee)
+            })
+        });
+
+        incoming
+            .chain(outgoing)
+            .filter(|(caller, callee)| caller != callee)
+            .map(|(_, callee)| callee)
+            .collect()
+    }
+}
@@ -0,0 +1,184 @@
+use {
+    super::{
+        test_support::{func, incoming, location, pos, spanning, symbol},
+        DeadCodeOptions, GraphGenerator,
+    },
+    crate::{
+        graph_model::GlobalPosition,
+        lsp_types::{DocumentSymbol, SymbolKind},
+    },
+};
+
+fn position(line: u32) -> GlobalPosition {
+    pos(1, line)
+}
+
+/// main -> f -> g，unused 没有调用者，r 只调用自己，
+/// tests 模块中的 test_f，Shape 接口由 impl 块实现
+fn sample_generator(lang: &str) -> GraphGenerator {
+    let path = "/root/a.rs";
+    let mut generator = GraphGenerator::new("/root".to_string(), lang);
+
+    let (main, f, g, unused, r) = (
+        func("main", 0),
+        func("f", 5),
+        func("g", 10),
+        func("unused", 15),
+        func("r", 20),
+    );
+    let tests = DocumentSymbol {
+        children: vec![func("test_f", 26)],
+        ..spanning(symbol("tests", SymbolKind::Module, 25), 27)
+    };
+    let shape = DocumentSymbol {
+        children: vec![symbol("area", SymbolKind::Method, 31)],
+        ..spanning(symbol("Shape", SymbolKind::Interface, 30), 32)
+    };
+    let impl_block = DocumentSymbol {
+        children: vec![symbol("area", SymbolKind::Method, 36)],
+        ..spanning(symbol("impl Shape for Square", SymbolKind::Object, 35), 37)
+    };
+
+    generator.add_file(
+        path.to_string(),
+        vec![
+            main.clone(),
+            f.clone(),
+            g.clone(),
+            unused,
+            r.clone(),
+            tests,
+            shape.clone(),
+            impl_block.clone(),
+        ],
+    );
+
+    generator.add_incoming_calls(
+        path.to_string(),
+        f.selection_range.start,
+        vec![incoming(path, &main, &[])],
+    );
+    generator.add_incoming_calls(
+        path.to_string(),
+        g.selection_range.start,
+        vec![incoming(path, &f, &[])],
+    );
+    generator.add_incoming_calls(
+        path.to_string(),
+        r.selection_range.start,
+        vec![incoming(path, &r, &[])],
+    );
+    generator.add_interface_implementations(
+        path.to_string(),
+        shape.selection_range.start,
+        vec![location(path, &impl_block)],
+    );
+
+    generator
+}
+
+#[test]
+fn dead_code_with_default_exemptions() {
+    let generator = sample_generator("Rust");
+
+    assert_eq!(
+        generator.find_dead_code(),
+        vec![position(15), position(20)],
+        "只有 unused 和只调用自己的 r 应该被报告"
+    );
+
+    let graph = generator.generate_graph();
+    assert_eq!(graph.unreferenced, vec![position(15), position(20)]);
+    let json = serde_json::to_string(&graph).unwrap();
+    assert!(
+        json.contains(r#""unreferenced":[{"fileId":1,"line":15,"character":3}"#),
+        "{}",
+        json
+    );
+}
+
+#[test]
+fn dead_code_without_exemptions() {
+    let mut generator = sample_generator("Rust");
+    generator.set_dead_code_options(DeadCodeOptions {
+        entry_points: vec![],
+        exempt_tests: false,
+        exempt_impls: false,
+        exempt_exported: false,
+    });
+
+    assert_eq!(
+        generator.find_dead_code(),
+        vec![
+            position(0),
+            position(15),
+            position(20),
+            position(26),
+            position(31),
+            position(36)
+        ],
+        "main、测试函数和接口方法都应该被报告"
+    );
+}
+
+#[test]
+fn dead_code_exported_symbols() {
+    let path = "/root/a.go";
+    let mut generator = GraphGenerator::new("/root".to_string(), "Go");
+    generator.add_file(
+        path.to_string(),
+        vec![
+            func("Exported", 0),
+            func("unexported", 5),
+            func("(*T).Method", 10),
+            func("(*T).method", 15),
+            func("TestExported", 20),
+        ],
+    );
+
+    assert_eq!(
+        generator.find_dead_code(),
+        vec![position(5), position(15)],
+        "导出的函数和方法不应该被报告"
+    );
+
+    // Go 的测试文件不会被添加，因此 TestExported 不是测试
+    generator.set_dead_code_options(DeadCodeOptions {
+        exempt_exported: false,
+        ..Default::default()
+    });
+    assert_eq!(
+        generator.find_dead_code(),
+        vec![
+            position(0),
+            position(5),
+            position(10),
+            position(15),
+            position(20)
+        ]
+    );
+}
+
+#[test]
+fn go_test_files() {
+    let mut generator = GraphGenerator::new("/root".to_string(), "Go");
+    let (helper, unused) = (func("helper", 0), func("unused", 5));
+    let test = func("TestHelper", 0);
+
+    assert!(
+        !generator.add_file("/root/a_test.go".to_string(), vec![test.clone()]),
+        "测试文件不应该被添加"
+    );
+    assert!(generator.add_file("/root/a.go".to_string(), vec![helper.clone(), unused]));
+    generator.add_incoming_calls(
+        "/root/a.go".to_string(),
+        helper.selection_range.start,
+        vec![incoming("/root/a_test.go", &test, &[1])],
+    );
+
+    assert_eq!(
+        generator.find_dead_code(),
+        vec![position(5)],
+        "只被测试调用的函数不应该被报告"
+    );
+}
@@ -0,0 +1,255 @@
+use {
+    super::{
+        test_support::{func, incoming, symbol},
+        GraphGenerator,
+    },
+    crate::{
+        graph_model::{DiffRelation, DiffSymbol},
+        lsp_types::{DocumentSymbol, SymbolKind},
+    },
+};
+
+const A: &str = "a.rs";
+const B: &str = "b.rs";
+
+/// `root` 下的 a.rs 中的符号为 `a`，b.rs 中只有 f；`calls` 为 (被调用者所在文件, 被调用者, a.rs 中的调用者)
+fn generator(
+    root: &str,
+    a: Vec<DocumentSymbol>,
+    calls: &[(&str, &DocumentSymbol, Vec<&DocumentSymbol>)],
+) -> GraphGenerator {
+    let mut generator = GraphGenerator::new(root.to_string(), "");
+    generator.add_file(format!("{}/{}", root, A), a);
+    generator.add_file(format!("{}/{}", root, B), vec![func("f", 0)]);
+
+    for (callee_path, callee, callers) in calls {
+        generator.add_incoming_calls(
+            format!("{}/{}", root, callee_path),
+            callee.selection_range.start,
+            callers
+                .iter()
+                .map(|caller| {
+                    let line = caller.selection_range.start.line;
+                    incoming(&format!("{}/{}", root, A), caller, &[line])
+                })
+                .collect(),
+        );
+    }
+
+    generator
+}
+
+/// 旧版本：main -> helper，main -> old_fn，helper -> f，S::run -> f
+/// 新版本：a.rs 开头加入了 new_fn，其他符号下移 3 行，old_fn 被删除
+///         main -> helper，main -> new_fn，helper -> f，new_fn -> f
+///
+/// 两个版本分别位于 `old_root` 和 `new_root` 下
+fn sample_generators(old_root: &str, new_root: &str) -> (GraphGenerator, GraphGenerator) {
+    let f = func("f", 0);
+
+    let (main, helper, old_fn, run) = (
+        func("main", 0),
+        func("helper", 5),
+        func("old_fn", 10),
+        symbol("run", SymbolKind::Method, 21),
+    );
+    let old = generator(
+        old_root,
+        vec![
+            main.clone(),
+            helper.clone(),
+            old_fn.clone(),
+            DocumentSymbol {
+                children: vec![run.clone()],
+                ..symbol("S", SymbolKind::Struct, 20)
+            },
+        ],
+        &[
+            (A, &helper, vec![&main]),
+            (A, &old_fn, vec![&main]),
+            (B, &f, vec![&helper, &run]),
+        ],
+    );
+
+    let (new_fn, main, helper, run) = (
+        func("new_fn", 0),
+        func("main", 3),
+        func("helper", 8),
+        symbol("run", SymbolKind::Method, 24),
+    );
+    let new = generator(
+        new_root,
+        vec![
+            new_fn.clone(),
+            main.clone(),
+            helper.clone(),
+            DocumentSymbol {
+                children: vec![run.clone()],
+                ..symbol("S", SymbolKind::Struct, 23)
+            },
+        ],
+        &[
+            (A, &helper, vec![&main]),
+            (A, &new_fn, vec![&main]),
+            (B, &f, vec![&helper, &new_fn]),
+        ],
+    );
+
+    (old, new)
+}
+
+fn names(symbols: &[DiffSymbol]) -> Vec<&str> {
+    symbols.iter().map(|s| s.qualified_name.as_str()).collect()
+}
+
+fn ends(relations: &[DiffRelation]) -> Vec<(&str, &str)> {
+    relations
+        .iter()
+        .map(|r| (r.from.qualified_name.as_str(), r.to.qualified_name.as_str()))
+        .collect()
+}
+
+#[test]
+fn symbols_and_relations() {
+    let (old, new) = sample_generators("/root", "/root");
+    let diff = old.generate_graph().diff(&new.generate_graph());
+
+    assert_eq!(names(&diff.added_symbols), vec!["new_fn"]);
+    assert_eq!(names(&diff.removed_symbols), vec!["old_fn"]);
+    // 按路径和限定名排序
+    assert_eq!(
+        names(&diff.unchanged_symbols),
+        vec!["S", "S::run", "helper", "main", "f"]
+    );
+
+    // 符号按名称而不是位置匹配
+    let main = &diff.unchanged_symbols[3];
+    assert_eq!(main.old_position.map(|p| p.line), Some(0));
+    assert_eq!(main.new_position.map(|p| p.line), Some(3));
+
+    assert_eq!(
+        ends(&diff.added_relations),
+        vec![("main", "new_fn"), ("new_fn", "f")]
+    );
+    assert_eq!(
+        ends(&diff.removed_relations),
+        vec![("S::run", "f"), ("main", "old_fn")]
+    );
+    assert_eq!(
+        ends(&diff.unchanged_relations),
+        vec![("helper", "f"), ("main", "helper")]
+    );
+
+    let json = serde_json::to_value(&diff).unwrap();
+    assert_eq!(json["addedSymbols"][0]["qualifiedName"], "new_fn");
+    assert_eq!(json["addedSymbols"][0]["path"], A);
+    assert!(json["addedSymbols"][0]["oldPosition"].is_null());
+    assert_eq!(
+        json["removedRelations"][0]["from"]["qualifiedName"],
+        "S::run"
+    );
+
+    let same = new.generate_graph().diff(&new.generate_graph());
+    assert!(same.added_relations.is_empty() && same.removed_relations.is_empty());
+    assert!(same.added_symbols.is_empty() && same.removed_symbols.is_empty());
+}
+
+#[test]
+fn different_roots() {
+    // 例如基础分支和PR分别检出在不同的目录中
+    let (old, new) = sample_generators("/checkouts/base", "/checkouts/pr");
+    let diff = old.generate_graph().diff(&new.generate_graph());
+
+    assert_eq!(names(&diff.added_symbols), vec!["new_fn"]);
+    assert_eq!(names(&diff.removed_symbols), vec!["old_fn"]);
+    assert_eq!(diff.unchanged_symbols.len(), 5);
+    assert_eq!(diff.unchanged_symbols[0].path, A, "路径应该相对于根目录");
+    assert_eq!(
+        ends(&diff.added_relations),
+        vec![("main", "new_fn"), ("new_fn", "f")]
+    );
+    assert_eq!(diff.unchanged_relations.len(), 2);
+}
+
+#[test]
+fn colored_edges() {
+    let (old, new) = sample_generators("/root", "/root");
+    let graph = new.generate_graph();
+    let diff = old.generate_graph().diff(&graph);
+
+    let dot = new.render_diff_dot(&graph, &diff);
+    let edge = |id: &str| {
+        dot.lines()
+            .find(|line| line.contains(&format!(r#"id="{}""#, id)))
+            .unwrap_or_else(|| panic!("缺少边 {}:\n{}", id, dot))
+            .to_string()
+    };
+    for id in ["1:3_3 -> 1:0_3", "1:0_3 -> 2:0_3"] {
+        let edge = edge(id);
+        assert!(
+            edge.contains(r##"color="#1a7f37""##) && edge.contains(r#"class="added""#),
+            "新增的边应该是绿色的: {}",
+            edge
+        );
+    }
+    // 被删除的边以红色画出
+    let removed = edge("1:24_3 -> 2:0_3");
+    assert!(
+        removed.contains(r##"color="#cf222e""##) && removed.contains(r#"class="removed""#),
+        "被删除的边应该是红色的: {}",
+        removed
+    );
+    assert!(!edge("1:8_3 -> 2:0_3").contains("color"));
+    // 被删除的符号画在所在文件的最后一个符号之后，以便画出它的边
+    assert!(dot.contains(r#"<TD PORT="25_3" ID="1:25_3"  STYLE="ROUNDED" href="12">old_fn</TD>"#));
+    assert!(edge("1:3_3 -> 1:25_3").contains(r#"class="removed""#));
+    assert_eq!(
+        dot.lines().filter(|line| line.contains("datafrom")).count(),
+        6
+    );
+
+    let mermaid = new.render_diff_mermaid(&graph, &diff);
+    assert!(mermaid.contains("    1_24_3 --> 2_0_3\n"), "{}", mermaid);
+    assert!(
+        mermaid.contains("    linkStyle 0,1 stroke:#1a7f37\n"),
+        "{}",
+        mermaid
+    );
+    assert!(
+        mermaid.contains("    linkStyle 3,5 stroke:#cf222e\n"),
+        "{}",
+        mermaid
+    );
+
+    // 普通的渲染不着色
+    assert!(!new.generate_mermaid_source().contains("linkStyle"));
+}
+
+#[test]
+fn removed_files() {
+    let (old, _) = sample_generators("/root", "/root");
+    let (main, helper) = (func("main", 0), func("helper", 5));
+    let mut new = GraphGenerator::new("/root".to_string(), "");
+    new.add_file(format!("/root/{}", A), vec![main.clone(), helper.clone()]);
+    let graph = new.generate_graph();
+    let diff = old.generate_graph().diff(&graph);
+
+    // b.rs 整个被删除，f 画在新的文件中，S::run 和 old_fn 画在 a.rs 的末尾
+    let dot = new.render_diff_dot(&graph, &diff);
+    assert!(dot.contains("b.rs"), "{}", dot);
+    for id in [
+        "1:0_3 -> 1:5_3",
+        "1:0_3 -> 1:7_3",
+        "1:5_3 -> 2:0_3",
+        "1:6_3 -> 2:0_3",
+    ] {
+        assert!(
+            dot.lines()
+                .any(|line| line.contains(&format!(r#"id="{}""#, id))
+                    && line.contains(r#"class="removed""#)),
+            "缺少被删除的边 {}:\n{}",
+            id,
+            dot
+        );
+    }
+}
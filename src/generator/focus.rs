@@ -0,0 +1,276 @@
+use {
+    super::{FileOutline, GraphGenerator, SymbolLocation},
+    crate::{
+        graph_model::GlobalPosition,
+        lsp_types::{DocumentSymbol, Position},
+    },
+    std::collections::{BTreeMap, HashMap, HashSet, VecDeque},
+};
+
+type Adjacency = HashMap<SymbolLocation, Vec<SymbolLocation>>;
+
+impl GraphGenerator {
+    fn location_of(&self, position: GlobalPosition) -> Option<SymbolLocation> {
+        self.output_file_ids()
+            .into_iter()
+            .find(|(_, id)| *id == position.file_id)
+            .map(|(path, _)| SymbolLocation {
+                path: path.to_string(),
+                line: position.line,
+                character: position.character,
+            })
+    }
+
+    /// Call edges from both the incoming and outgoing call maps, as caller -> callees and callee -> callers.
+    fn call_adjacency(&self) -> (Adjacency, Adjacency) {
+        let mut callees: HashMap<_, Vec<_>> = HashMap::new();
+        let mut callers: HashMap<_, Vec<_>> = HashMap::new();
+
+        let incoming = self.incoming_calls.iter().flat_map(|(callee, calls)| {
+            calls.iter().map(move |call| {
+                let caller = SymbolLocation::new(
+                    call.from.uri.path.clone(),
+                    &call.from.selection_range.start,
+                );
+                (caller, callee.clone())
+            })
+        });
+        let outgoing = self.outgoing_calls.iter().flat_map(|(caller, calls)| {
+            calls.iter().map(move |call| {
+                let callee =
+                    SymbolLocation::new(call.to.uri.path.clone(), &call.to.selection_range.start);
+                (caller.clone(), callee)
+            })
+        });
+
+        for (caller, callee) in incoming.chain(outgoing) {
+            callees
+                .entry(caller.clone())
+                .or_default()
+                .push(callee.clone());
+            callers.entry(callee).or_default().push(caller);
+        }
+
+        (callees, callers)
+    }
+
+    /// Returns a generator restricted to the symbols within `max_in_depth` caller hops and
+    /// `max_out_depth` callee hops of `root`, or `None` if `root` is not in any added file.
+    ///
+    /// File ids are kept, and `root` is highlighted, so the DOT, Mermaid and `Graph` outputs of
+    /// the returned generator are consistent with the full graph.
+    pub fn generate_focused_graph(
+        &self,
+        root: GlobalPosition,
+        max_in_depth: u32,
+        max_out_depth: u32,
+    ) -> Option<GraphGenerator> {
+        let root = self.location_of(root)?;
+        let (callees, callers) = self.call_adjacency();
+
+        let mut kept = HashSet::from([root.clone()]);
+        for (adjacency, max_depth) in [(&callers, max_in_depth), (&callees, max_out_depth)] {
+            let mut visited = HashSet::from([root.clone()]);
+            let mut queue = VecDeque::from([(root.clone(), 0)]);
+
+            while let Some((location, depth)) = queue.pop_front() {
+                if depth >= max_depth {
+                    continue;
+                }
+
+                for next in adjacency.get(&location).into_iter().flatten() {
+                    if visited.insert(next.clone()) {
+                        queue.push_back((next.clone(), depth + 1));
+                    }
+                }
+            }
+
+            kept.extend(visited);
+        }
+
+        let mut focused = self.restrict(&kept, |caller, callee| {
+            kept.contains(caller) && kept.contains(callee)
+        });
+        focused.highlight(
+            root.path.clone(),
+            Position {
+                line: root.line,
+                character: root.character,
+            },
+        );
+
+        Some(focused)
+    }
+
+    /// Returns a generator restricted to the call chains from `from` to `to` found by
+    /// `Graph::find_paths`, or `None` if either symbol is not in any added file.
+    ///
+    /// Only the calls along the chains are kept, and both ends are highlighted.
+    pub fn generate_path_graph(
+        &self,
+        from: GlobalPosition,
+        to: GlobalPosition,
+        max_paths: usize,
+        max_len: usize,
+    ) -> Option<GraphGenerator> {
+        let ends = [self.location_of(from)?, self.location_of(to)?];
+        let paths = self.find_paths(from, to, max_paths, max_len);
+
+        let mut kept = HashSet::from(ends.clone());
+        let mut calls = HashSet::new();
+        for path in &paths {
+            let path = path
+                .iter()
+                .filter_map(|position| self.location_of(*position))
+                .collect::<Vec<_>>();
+            calls.extend(
+                path.windows(2)
+                    .map(|call| (call[0].clone(), call[1].clone())),
+            );
+            kept.extend(path);
+        }
+
+        let mut restricted = self.restrict(&kept, |caller, callee| {
+            calls.contains(&(caller.clone(), callee.clone()))
+        });
+        restricted.interfaces.clear();
+        restricted.subtypes.clear();
+        restricted.references.clear();
+
+        for end in ends {
+            restricted.highlight(
+                end.path,
+                Position {
+                    line: end.line,
+                    character: end.character,
+                },
+            );
+        }
+
+        Some(restricted)
+    }
+
+    /// Returns a generator with the symbols in `kept`, and the calls for which `keep_call`
+    /// returns true when given the caller and the callee.
+    fn restrict<F>(&self, kept: &HashSet<SymbolLocation>, keep_call: F) -> GraphGenerator
+    where
+        F: Fn(&SymbolLocation, &SymbolLocation) -> bool,
+    {
+        let mut restricted = GraphGenerator::new(self.root.clone(), &self.lang_name);
+        restricted.next_file_id = self.next_file_id;
+        restricted.dot_options = self.dot_options;
+        restricted.granularity = self.granularity;
+        restricted.dead_code_options = self.dead_code_options.clone();
+
+        let file_ids = self.output_file_ids();
+        let mut kept_file_ids = HashMap::new();
+
+        for (path, file) in &self.files {
+            let symbols = Self::retain_symbols(&file.symbols, path, kept);
+            if symbols.is_empty() {
+                continue;
+            }
+
+            restricted.files.insert(
+                path.clone(),
+                FileOutline {
+                    id: file.id,
+                    path: file.path.clone(),
+                    symbols,
+                },
+            );
+
+            kept_file_ids.insert(path.clone(), file_ids[path.as_str()]);
+
+            if let Some(cells) = self.highlights.get(&file.id) {
+                restricted.highlights.insert(file.id, cells.clone());
+            }
+        }
+        restricted.kept_file_ids = Some(kept_file_ids);
+
+        restricted.incoming_calls = self
+            .incoming_calls
+            .iter()
+            .map(|(callee, calls)| {
+                let calls = calls
+                    .iter()
+                    .filter(|call| {
+                        let caller = SymbolLocation::new(
+                            call.from.uri.path.clone(),
+                            &call.from.selection_range.start,
+                        );
+                        keep_call(&caller, callee)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (callee.clone(), calls)
+            })
+            .filter(|(_, calls)| !calls.is_empty())
+            .collect();
+
+        restricted.outgoing_calls = self
+            .outgoing_calls
+            .iter()
+            .map(|(caller, calls)| {
+                let calls = calls
+                    .iter()
+                    .filter(|call| {
+                        let callee = SymbolLocation::new(
+                            call.to.uri.path.clone(),
+                            &call.to.selection_range.start,
+                        );
+                        keep_call(caller, &callee)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (caller.clone(), calls)
+            })
+            .filter(|(_, calls)| !calls.is_empty())
+            .collect();
+
+        let retain_targets = |targets: &BTreeMap<SymbolLocation, Vec<SymbolLocation>>| {
+            targets
+                .iter()
+                .filter(|(target, _)| kept.contains(*target))
+                .map(|(target, sources)| {
+                    let sources = sources
+                        .iter()
+                        .filter(|location| kept.contains(*location))
+                        .cloned()
+                        .collect();
+                    (target.clone(), sources)
+                })
+                .collect()
+        };
+        restricted.interfaces = retain_targets(&self.interfaces);
+        restricted.subtypes = retain_targets(&self.subtypes);
+        restricted.references = retain_targets(&self.references);
+
+        restricted
+    }
+
+    /// Keeps the symbols in `kept`, along with their ancestors.
+    fn retain_symbols(
+        symbols: &[DocumentSymbol],
+        path: &str,
+        kept: &HashSet<SymbolLocation>,
+    ) -> Vec<DocumentSymbol> {
+        symbols
+            .iter()
+            .filter_map(|symbol| {
+                let children = Self::retain_symbols(&symbol.children, path, kept);
+                let location = SymbolLocation::new(path.to_string(), &symbol.selection_range.start);
+
+                (kept.contains(&location) || !children.is_empty()).then(|| DocumentSymbol {
+                    name: symbol.name.clone(),
+                    detail: symbol.detail.clone(),
+                    kind: symbol.kind,
+                    tags: symbol.tags.clone(),
+                    range: symbol.range,
+                    selection_range: symbol.selection_range,
+                    children,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,126 @@
+use {
+    super::{
+        test_support::{func, incoming, outgoing, pos, spanning},
+        GraphGenerator,
+    },
+    crate::lsp_types::DocumentSymbol,
+};
+
+/// 有函数体的函数
+fn function(name: &str, line: u32) -> DocumentSymbol {
+    spanning(func(name, line), line + 3)
+}
+
+/// main -> f1 -> f2 -> f3 -> f4，其中 f3 -> f4 只记录在 outgoing calls 中
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let (main, f1) = (function("main", 0), function("f1", 5));
+    let (f2, f3, f4) = (function("f2", 0), function("f3", 5), function("f4", 10));
+
+    generator.add_file("/root/a.rs".to_string(), vec![main.clone(), f1.clone()]);
+    generator.add_file(
+        "/root/b.rs".to_string(),
+        vec![f2.clone(), f3.clone(), f4.clone()],
+    );
+
+    let calls = [
+        ("/root/a.rs", &main, "/root/a.rs", &f1),
+        ("/root/a.rs", &f1, "/root/b.rs", &f2),
+        ("/root/b.rs", &f2, "/root/b.rs", &f3),
+    ];
+    for (caller_path, caller, callee_path, callee) in calls {
+        generator.add_incoming_calls(
+            callee_path.to_string(),
+            callee.selection_range.start,
+            vec![incoming(caller_path, caller, &[])],
+        );
+    }
+
+    generator.add_outgoing_calls(
+        "/root/b.rs".to_string(),
+        f3.selection_range.start,
+        vec![outgoing("/root/b.rs", &f4, &[])],
+    );
+
+    generator
+}
+
+fn symbol_names(generator: &GraphGenerator) -> Vec<String> {
+    let mut names = generator
+        .files
+        .values()
+        .flat_map(|file| file.symbols.iter().map(|s| s.name.clone()))
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+fn focused_graph_keeps_neighborhood() {
+    let generator = sample_generator();
+    let b_id = generator.files["/root/b.rs"].id;
+    let root = pos(b_id, 0);
+
+    let focused = generator.generate_focused_graph(root, 1, 1).unwrap();
+    assert_eq!(
+        symbol_names(&focused),
+        vec!["f1", "f2", "f3"],
+        "只应该保留一跳以内的函数"
+    );
+    assert_eq!(focused.files["/root/b.rs"].id, b_id, "文件ID应该保持不变");
+    assert!(
+        focused.highlights[&b_id].contains(&(0, 3)),
+        "中心函数应该被高亮"
+    );
+
+    let graph = focused.generate_graph();
+    assert_eq!(
+        graph.relations().len(),
+        2,
+        "应该只有 f1 -> f2 和 f2 -> f3 两条关系"
+    );
+
+    let dot = focused.generate_dot_source();
+    assert!(!dot.contains("main"), "dot源码不应该包含范围外的函数");
+    assert!(
+        dot.contains(r#"1:"5_3" -> 2:"0_3""#),
+        "dot源码应该包含 f1 -> f2:\n{}",
+        dot
+    );
+
+    let mermaid = focused.generate_mermaid_source();
+    assert!(
+        !mermaid.contains("main"),
+        "mermaid源码不应该包含范围外的函数"
+    );
+}
+
+#[test]
+fn focused_graph_depth_per_direction() {
+    let generator = sample_generator();
+    let root = pos(generator.files["/root/b.rs"].id, 0);
+
+    let callers = generator.generate_focused_graph(root, 2, 0).unwrap();
+    assert_eq!(symbol_names(&callers), vec!["f1", "f2", "main"]);
+
+    // outgoing calls 中记录的边也应该被跟踪
+    let callees = generator.generate_focused_graph(root, 0, 2).unwrap();
+    assert_eq!(symbol_names(&callees), vec!["f2", "f3", "f4"]);
+    assert!(
+        !callees.files.contains_key("/root/a.rs"),
+        "没有保留符号的文件应该被去掉"
+    );
+    assert_eq!(
+        callees.generate_graph().files()[0].id,
+        root.file_id,
+        "输出中的文件ID应该与完整的图一致"
+    );
+}
+
+#[test]
+fn focused_graph_unknown_root() {
+    let generator = sample_generator();
+    let root = pos(42, 0);
+    assert!(generator.generate_focused_graph(root, 1, 1).is_none());
+}
@@ -411,6 +411,398 @@ fn test_performance() {
     assert!(!mermaid.is_empty(), "生成的mermaid源码不应为空");
 }
 
+// 辅助函数：创建两个文件，other.rs 中的 caller 调用 test_file.rs 中的 callee，
+// test_file.rs 中的 Iface 由 other.rs 中的 Impl 实现
+fn create_two_file_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("test_root".to_string(), "rust");
+
+    let symbol = |name: &str, kind: SymbolKind, line: u32| {
+        let range = Range {
+            start: Position { line, character: 0 },
+            end: Position { line, character: 10 },
+        };
+        DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind,
+            tags: None,
+            range,
+            selection_range: range,
+            children: vec![],
+        }
+    };
+
+    generator.add_file("test_file.rs".to_string(), vec![
+        symbol("callee", SymbolKind::Function, 0),
+        symbol("Iface", SymbolKind::Interface, 5),
+    ]);
+    generator.add_file("other.rs".to_string(), vec![
+        symbol("caller", SymbolKind::Function, 0),
+        symbol("Impl", SymbolKind::Struct, 5),
+    ]);
+
+    let item = |path: &str, symbol: DocumentSymbol| CallHierarchyItem {
+        name: symbol.name,
+        kind: symbol.kind,
+        tags: None,
+        detail: None,
+        uri: Uri { path: path.to_string() },
+        range: symbol.range,
+        selection_range: symbol.selection_range,
+        data: None,
+    };
+
+    let origin = Position { line: 0, character: 0 };
+    generator.add_incoming_calls("test_file.rs".to_string(), origin, vec![CallHierarchyIncomingCall {
+        from: item("other.rs", symbol("caller", SymbolKind::Function, 0)),
+        from_ranges: vec![],
+    }]);
+    generator.add_outgoing_calls("other.rs".to_string(), origin, vec![CallHierarchyOutgoingCall {
+        to: item("test_file.rs", symbol("callee", SymbolKind::Function, 0)),
+        from_ranges: vec![],
+    }]);
+    generator.add_interface_implementations("test_file.rs".to_string(), Position { line: 5, character: 0 }, vec![Location {
+        uri: Uri { path: "other.rs".to_string() },
+        range: symbol("Impl", SymbolKind::Struct, 5).range,
+    }]);
+    generator.highlight("test_file.rs".to_string(), origin);
+    generator.highlight("other.rs".to_string(), origin);
+
+    generator
+}
+
+#[test]
+fn test_remove_file() {
+    let mut generator = create_two_file_generator();
+    assert_eq!(generator.generate_graph().relations().len(), 2, "应该有调用关系和实现关系");
+
+    assert!(generator.remove_file("test_file.rs"), "已存在的文件应该被删除");
+    assert!(!generator.remove_file("test_file.rs"), "不存在的文件不能被删除");
+
+    assert!(!generator.files.contains_key("test_file.rs"));
+    assert!(!generator.highlights.contains_key(&1), "被删除文件的高亮应该被清除");
+    assert!(generator.highlights.contains_key(&2), "其他文件的高亮应该保留");
+
+    // 以被删除文件为起点或终点的调用和实现都应该被清除
+    assert!(generator.incoming_calls.is_empty());
+    assert!(generator.interfaces.is_empty());
+    assert!(generator.outgoing_calls.values().all(|calls| calls.is_empty()));
+    assert!(generator.generate_graph().relations().is_empty());
+
+    // 文件ID不会被重用
+    generator.add_file("test_file.rs".to_string(), vec![]);
+    assert_eq!(generator.files["test_file.rs"].id, 3);
+    assert_eq!(generator.files["other.rs"].id, 2);
+}
+
+#[test]
+fn test_update_file() {
+    let mut generator = create_two_file_generator();
+
+    let range = Range {
+        start: Position { line: 2, character: 0 },
+        end: Position { line: 2, character: 10 },
+    };
+    let symbols = vec![DocumentSymbol {
+        name: "moved_callee".to_string(),
+        detail: None,
+        kind: SymbolKind::Function,
+        tags: None,
+        range,
+        selection_range: range,
+        children: vec![],
+    }];
+
+    assert!(generator.update_file("test_file.rs".to_string(), symbols));
+    assert_eq!(generator.files["test_file.rs"].id, 1, "更新后的文件应该保留原来的ID");
+    assert_eq!(generator.files["test_file.rs"].symbols[0].name, "moved_callee");
+    assert!(!generator.highlights.contains_key(&1), "更新文件的高亮应该被清除");
+    assert!(generator.generate_graph().relations().is_empty(), "旧的调用关系应该被清除");
+
+    // 更新后重新添加调用关系
+    let position = Position { line: 2, character: 0 };
+    generator.add_incoming_calls("test_file.rs".to_string(), position, vec![CallHierarchyIncomingCall {
+        from: CallHierarchyItem {
+            name: "caller".to_string(),
+            kind: SymbolKind::Function,
+            tags: None,
+            detail: None,
+            uri: Uri { path: "other.rs".to_string() },
+            range: Range::default(),
+            selection_range: Range::default(),
+            data: None,
+        },
+        from_ranges: vec![],
+    }]);
+    let dot = generator.generate_dot_source();
+    assert!(dot.contains(r#"1:"0_0" -> 2:"2_0""#), "应该包含新的调用关系:\n{}", dot);
+
+    // 不存在的文件会被直接添加
+    assert!(generator.update_file("new_file.rs".to_string(), vec![]));
+    assert_eq!(generator.files["new_file.rs"].id, 3);
+}
+
+#[test]
+fn test_call_sites() {
+    let mut generator = create_two_file_generator();
+
+    let site = |line: u32| Range {
+        start: Position { line, character: 4 },
+        end: Position { line, character: 10 },
+    };
+
+    // incoming calls 和 outgoing calls 报告了部分相同的调用位置
+    let mut incoming = generator.incoming_calls.values().next().unwrap().clone();
+    incoming[0].from_ranges = vec![site(2), site(1)];
+    generator.add_incoming_calls("test_file.rs".to_string(), Position { line: 0, character: 0 }, incoming);
+
+    let mut outgoing = generator.outgoing_calls.values().next().unwrap().clone();
+    outgoing[0].from_ranges = vec![site(1), site(3)];
+    generator.add_outgoing_calls("other.rs".to_string(), Position { line: 0, character: 0 }, outgoing);
+
+    let graph = generator.generate_graph();
+    let call = graph.relations().iter()
+        .find(|r| matches!(r.kind, crate::graph_model::RelationKind::Call))
+        .expect("应该包含调用关系");
+    assert_eq!(call.call_count, 3, "相同的调用位置只应该计算一次");
+
+    let dot = generator.generate_dot_source();
+    assert!(dot.contains(r#"datacallsites="1:4 2:4 3:4""#), "dot源码应该包含排序后的调用位置:\n{}", dot);
+
+    let mermaid = generator.generate_mermaid_source();
+    assert!(mermaid.contains("1_0_0 -->|3| 2_0_0"), "mermaid源码应该标注调用次数:\n{}", mermaid);
+}
+
+#[test]
+fn test_relation_kinds_between_same_symbols() {
+    use crate::graph_model::RelationKind;
+
+    let mut generator = create_two_file_generator();
+
+    // Impl 同时调用了 Iface，实现关系和调用关系应该分别保留
+    generator.add_outgoing_calls("other.rs".to_string(), Position { line: 5, character: 0 }, vec![CallHierarchyOutgoingCall {
+        to: CallHierarchyItem {
+            name: "Iface".to_string(),
+            kind: SymbolKind::Interface,
+            tags: None,
+            detail: None,
+            uri: Uri { path: "test_file.rs".to_string() },
+            range: Range::default(),
+            selection_range: Range { start: Position { line: 5, character: 0 }, end: Position { line: 5, character: 10 } },
+            data: None,
+        },
+        from_ranges: vec![Range { start: Position { line: 6, character: 4 }, end: Position { line: 6, character: 9 } }],
+    }]);
+
+    let graph = generator.generate_graph();
+    let between = graph.relations().iter()
+        .filter(|r| r.from.file_id == 1 && r.from.line == 5 && r.to.file_id == 2 && r.to.line == 5)
+        .collect::<Vec<_>>();
+    assert_eq!(between.len(), 2, "同一对符号之间不同种类的关系都应该保留");
+    assert!(between.iter().any(|r| matches!(r.kind, RelationKind::Impl) && r.call_sites.is_empty()), "调用位置不应该合并到实现关系中");
+    assert!(between.iter().any(|r| matches!(r.kind, RelationKind::Call) && r.call_count == 1));
+
+    // 没有调用位置的调用至少计为一次
+    let call = graph.relations().iter()
+        .find(|r| matches!(r.kind, RelationKind::Call) && r.from.line == 0)
+        .expect("应该包含调用关系");
+    assert!(call.call_sites.is_empty());
+    assert_eq!(call.call_count, 1);
+}
+
+#[test]
+fn test_dot_edge_weights() {
+    use crate::graph::dot::{DotOptions, EdgeWeighting};
+
+    let mut generator = create_two_file_generator();
+
+    let mut incoming = generator.incoming_calls.values().next().unwrap().clone();
+    incoming[0].from_ranges = (1..=4).map(|line| Range {
+        start: Position { line, character: 4 },
+        end: Position { line, character: 10 },
+    }).collect();
+    generator.add_incoming_calls("test_file.rs".to_string(), Position { line: 0, character: 0 }, incoming);
+
+    let call_edge = |dot: &str| dot.lines()
+        .find(|line| line.contains(r#"1:"0_0" -> 2:"0_0""#))
+        .expect("应该包含调用边")
+        .to_string();
+    let impl_edge = |dot: &str| dot.lines()
+        .find(|line| line.contains("class=\"impl\""))
+        .expect("应该包含实现边")
+        .to_string();
+
+    // 默认不设置权重
+    let dot = generator.generate_dot_source();
+    assert!(!call_edge(&dot).contains("penwidth"), "默认不应该设置边的粗细");
+    assert!(!call_edge(&dot).contains("label"), "默认不应该标注调用次数");
+
+    generator.set_dot_options(DotOptions { edge_weighting: EdgeWeighting::Linear, call_count_labels: true, ..Default::default() });
+    let dot = generator.generate_dot_source();
+    let edge = call_edge(&dot);
+    assert!(edge.contains(r#"penwidth="4.00""#) && edge.contains(r#"weight="4""#), "线性权重应该等于调用次数: {}", edge);
+    assert!(edge.contains(r#"label="4""#), "应该标注调用次数: {}", edge);
+    assert!(!impl_edge(&dot).contains("penwidth"), "只有一次调用的边不应该设置权重");
+
+    generator.set_dot_options(DotOptions { edge_weighting: EdgeWeighting::Logarithmic, ..Default::default() });
+    let edge = call_edge(&generator.generate_dot_source());
+    assert!(edge.contains(r#"penwidth="3.00""#) && edge.contains(r#"weight="3""#), "对数权重应该是 1 + log2(调用次数): {}", edge);
+    assert!(!edge.contains("label"));
+}
+
+#[test]
+fn test_inheritance() {
+    use crate::{graph_model::RelationKind, lsp_types::TypeHierarchyItem};
+
+    let mut generator = GraphGenerator::new("test_root".to_string(), "rust");
+
+    let class = |name: &str, line: u32| {
+        let range = Range {
+            start: Position { line, character: 6 },
+            end: Position { line, character: 6 + name.len() as u32 },
+        };
+        DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind: SymbolKind::Class,
+            tags: None,
+            range,
+            selection_range: range,
+            children: vec![],
+        }
+    };
+    let item = |path: &str, symbol: &DocumentSymbol| TypeHierarchyItem {
+        name: symbol.name.clone(),
+        kind: symbol.kind,
+        tags: None,
+        detail: None,
+        uri: Uri { path: path.to_string() },
+        range: symbol.range,
+        selection_range: symbol.selection_range,
+        data: None,
+    };
+
+    let (animal, dog, cat) = (class("Animal", 0), class("Dog", 0), class("Cat", 5));
+    generator.add_file("animal.ts".to_string(), vec![animal.clone()]);
+    generator.add_file("pets.ts".to_string(), vec![dog.clone(), cat.clone()]);
+
+    // 同一条继承关系可能同时出现在 supertypes 和 subtypes 的结果中
+    generator.add_supertypes("pets.ts".to_string(), dog.selection_range.start, vec![item("animal.ts", &animal)]);
+    generator.add_subtypes("animal.ts".to_string(), animal.selection_range.start, vec![item("pets.ts", &dog), item("pets.ts", &cat)]);
+
+    let graph = generator.generate_graph();
+    let inherits = graph.relations().iter()
+        .filter(|r| matches!(r.kind, RelationKind::Inherit))
+        .count();
+    assert_eq!(inherits, 2, "应该有两条继承关系");
+
+    let dot = generator.generate_dot_source();
+    let edge = dot.lines()
+        .find(|line| line.contains(r#"2:"0_6" -> 1:"0_6""#))
+        .expect("dot源码应该包含从子类指向父类的边");
+    assert!(edge.contains(r#"arrowhead="empty""#) && edge.contains(r#"class="inherit""#), "继承边应该使用不同的箭头和样式: {}", edge);
+
+    let mermaid = generator.generate_mermaid_source();
+    assert!(mermaid.contains("2_5_6 ==> 1_0_6"), "mermaid源码应该包含继承边:\n{}", mermaid);
+
+    assert!(generator.remove_file("animal.ts"));
+    assert!(generator.subtypes.is_empty(), "删除文件后继承关系也应该被删除");
+}
+
+#[test]
+fn test_references() {
+    use crate::graph_model::RelationKind;
+
+    let mut generator = GraphGenerator::new("test_root".to_string(), "rust");
+
+    let symbol = |name: &str, kind: SymbolKind, start: u32, end: u32, children: Vec<DocumentSymbol>| DocumentSymbol {
+        name: name.to_string(),
+        detail: None,
+        kind,
+        tags: None,
+        range: Range {
+            start: Position { line: start, character: 0 },
+            end: Position { line: end, character: 1 },
+        },
+        selection_range: Range {
+            start: Position { line: start, character: 4 },
+            end: Position { line: start, character: 4 + name.len() as u32 },
+        },
+        children,
+    };
+
+    generator.add_file("types.rs".to_string(), vec![symbol("Config", SymbolKind::Struct, 0, 3, vec![])]);
+    generator.add_file("main.rs".to_string(), vec![
+        symbol("load", SymbolKind::Function, 0, 5, vec![]),
+        symbol("App", SymbolKind::Struct, 10, 20, vec![
+            symbol("new", SymbolKind::Method, 12, 15, vec![]),
+        ]),
+    ]);
+
+    let location = |path: &str, line: u32| Location {
+        uri: Uri { path: path.to_string() },
+        range: Range {
+            start: Position { line, character: 8 },
+            end: Position { line, character: 14 },
+        },
+    };
+
+    generator.add_references("types.rs".to_string(), Position { line: 0, character: 4 }, vec![
+        // 声明本身
+        location("types.rs", 0),
+        // load 中的两次引用
+        location("main.rs", 1),
+        location("main.rs", 2),
+        // App::new 中的引用
+        location("main.rs", 13),
+        // 不在任何符号内
+        location("main.rs", 7),
+        // 未添加的文件
+        location("other.rs", 1),
+    ]);
+
+    let graph = generator.generate_graph();
+    let mut references = graph.relations().iter()
+        .filter(|r| matches!(r.kind, RelationKind::Reference))
+        .map(|r| (r.from.file_id, r.from.line, r.to.file_id, r.to.line))
+        .collect::<Vec<_>>();
+    references.sort();
+    assert_eq!(references, vec![(1, 0, 2, 0), (1, 12, 2, 0)], "引用应该解析到最内层的符号");
+
+    let dot = generator.generate_dot_source();
+    let edge = dot.lines()
+        .find(|line| line.contains(r#"1:"12_4" -> 2:"0_4""#))
+        .expect("dot源码应该包含引用边");
+    assert!(edge.contains(r#"style="dotted""#) && edge.contains(r#"class="reference""#), "引用边应该使用单独的样式: {}", edge);
+
+    let mermaid = generator.generate_mermaid_source();
+    assert!(mermaid.contains("1_0_4 -.-> 2_0_4"), "mermaid源码应该包含引用边:\n{}", mermaid);
+
+    // App::new 同时调用了 Config，合并后的边按调用关系绘制
+    let new = symbol("new", SymbolKind::Method, 12, 15, vec![]);
+    generator.add_incoming_calls("types.rs".to_string(), Position { line: 0, character: 4 }, vec![CallHierarchyIncomingCall {
+        from: CallHierarchyItem {
+            name: new.name.clone(),
+            kind: new.kind,
+            tags: None,
+            detail: None,
+            uri: Uri { path: "main.rs".to_string() },
+            range: new.range,
+            selection_range: new.selection_range,
+            data: None,
+        },
+        from_ranges: vec![location("main.rs", 14).range],
+    }]);
+
+    let dot = generator.generate_dot_source();
+    let edge = dot.lines()
+        .find(|line| line.contains(r#"1:"12_4" -> 2:"0_4""#))
+        .expect("dot源码应该包含调用边");
+    assert!(!edge.contains("dotted") && !edge.contains("reference"), "调用关系应该优先于引用关系: {}", edge);
+    assert!(generator.generate_mermaid_source().contains("1_12_4 --> 2_0_4"));
+    assert_eq!(generator.generate_graph().relations().len(), 3, "图模型中两种关系都应该保留");
+}
+
 // 辅助函数：创建测试用的incoming call
 fn create_test_incoming_call() -> CallHierarchyIncomingCall {
     let from_range = Range {
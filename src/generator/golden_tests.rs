@@ -0,0 +1,167 @@
+use {
+    super::{
+        test_support::{incoming, location, outgoing, site, spanning, symbol},
+        GraphGenerator,
+    },
+    crate::{
+        graph::svg::EdgeRouting,
+        graph_model::Granularity,
+        lsp_types::{DocumentSymbol, Location, SymbolKind, Uri},
+    },
+    std::{fs, path::Path},
+};
+
+const MAIN: &str = "/root/src/main.rs";
+const UTIL: &str = "/root/src/util.rs";
+const SHAPE: &str = "/root/lib/shape.rs";
+
+/// 有3行函数体的符号
+fn block(name: &str, kind: SymbolKind, line: u32) -> DocumentSymbol {
+    spanning(symbol(name, kind, line), line + 3)
+}
+
+/// src/main.rs: main, run；src/util.rs: parse, log；lib/shape.rs: Shape, Circle
+/// main -> run -> parse -> log，main -> log，Circle 实现了 Shape，run 引用了 Shape
+///
+/// 文件以及各种关系按 `order` 给出的文件顺序添加
+fn sample_generator(order: [usize; 3]) -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "Rust");
+
+    let (main, run) = (
+        block("main", SymbolKind::Function, 0),
+        block("run", SymbolKind::Function, 10),
+    );
+    let (parse, log) = (
+        block("parse", SymbolKind::Function, 0),
+        block("log", SymbolKind::Function, 10),
+    );
+    let (shape, circle) = (
+        block("Shape", SymbolKind::Interface, 0),
+        block("Circle", SymbolKind::Struct, 10),
+    );
+
+    let files = [
+        (MAIN, vec![main.clone(), run.clone()]),
+        (UTIL, vec![parse.clone(), log.clone()]),
+        (SHAPE, vec![shape.clone(), circle.clone()]),
+    ];
+    for i in order {
+        generator.add_file(files[i].0.to_string(), files[i].1.clone());
+    }
+
+    for i in order {
+        match i {
+            0 => {
+                generator.add_outgoing_calls(
+                    MAIN.to_string(),
+                    main.selection_range.start,
+                    vec![outgoing(UTIL, &log, &[2]), outgoing(MAIN, &run, &[1])],
+                );
+                generator.add_outgoing_calls(
+                    MAIN.to_string(),
+                    run.selection_range.start,
+                    vec![outgoing(UTIL, &parse, &[12, 11])],
+                );
+            }
+            1 => {
+                generator.add_incoming_calls(
+                    UTIL.to_string(),
+                    log.selection_range.start,
+                    vec![incoming(UTIL, &parse, &[1]), incoming(MAIN, &main, &[2])],
+                );
+            }
+            _ => {
+                generator.add_interface_implementations(
+                    SHAPE.to_string(),
+                    shape.selection_range.start,
+                    vec![location(SHAPE, &circle)],
+                );
+                generator.add_references(
+                    SHAPE.to_string(),
+                    shape.selection_range.start,
+                    vec![Location {
+                        uri: Uri::from_file_path(MAIN),
+                        range: site(11),
+                    }],
+                );
+            }
+        }
+    }
+
+    generator.highlight(UTIL.to_string(), parse.selection_range.start);
+
+    generator
+}
+
+/// 所有输出格式，以及对应的golden文件名
+fn outputs(generator: &mut GraphGenerator) -> Vec<(&'static str, String)> {
+    let mut outputs = vec![
+        ("sample.dot", generator.generate_dot_source()),
+        ("sample.mmd", generator.generate_mermaid_source()),
+        ("sample.puml", generator.generate_plantuml_source()),
+        ("sample.svg", generator.generate_svg(EdgeRouting::Spline)),
+        ("sample.json", generator.to_snapshot()),
+    ];
+    generator.set_granularity(Granularity::File);
+    outputs.push(("sample_files.dot", generator.generate_dot_source()));
+    outputs
+}
+
+/// 与 `src/generator/golden` 下的文件比较。设置 `UPDATE_GOLDEN=1` 时改为重新生成这些文件。
+fn assert_golden(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/generator/golden")
+        .join(name);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected =
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("无法读取 {}: {}", path.display(), e));
+    assert!(
+        expected == actual,
+        "{} 与golden文件不一致，可以设置 UPDATE_GOLDEN=1 重新生成:\n{}",
+        name,
+        actual
+    );
+}
+
+#[test]
+fn golden_outputs() {
+    for (name, output) in outputs(&mut sample_generator([0, 1, 2])) {
+        assert_golden(name, &output);
+    }
+}
+
+#[test]
+fn independent_of_insertion_order() {
+    let expected = outputs(&mut sample_generator([0, 1, 2]));
+
+    for order in [[0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]] {
+        let mut generator = sample_generator(order);
+        let graph = generator.generate_graph();
+        assert_eq!(
+            (graph.files()[0].id, graph.files()[0].path.as_str()),
+            (1, SHAPE),
+            "文件应该按路径编号: {:?}",
+            order
+        );
+
+        for ((name, expected), (_, actual)) in expected.iter().zip(outputs(&mut generator)) {
+            // 快照保存的是生成器的状态，其中的文件ID是添加文件时分配的
+            if *name == "sample.json" {
+                continue;
+            }
+            assert!(
+                *expected == actual,
+                "添加顺序 {:?} 生成的 {} 不同:\n{}\n\n{}",
+                order,
+                name,
+                expected,
+                actual
+            );
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use {
+    super::test_support::{func, pos, symbol},
+    crate::{
+        graph_model::{Graph, GraphBuilder, Relation, RelationKind, SymbolKind},
+        lsp_types::{self, DocumentSymbol},
+    },
+};
+
+/// 文件1: f, S { m, n }；文件2: g
+/// f -> g，m -> f，m -> n，g -> f，以及 S -> g 的实现关系
+fn sample_graph() -> Graph {
+    let mut builder = GraphBuilder::new();
+    builder.add_file_with_id(
+        1,
+        "/root/a.rs".to_string(),
+        vec![
+            func("f", 0),
+            DocumentSymbol {
+                children: vec![
+                    symbol("m", lsp_types::SymbolKind::Method, 6),
+                    symbol("n", lsp_types::SymbolKind::Method, 7),
+                ],
+                ..symbol("S", lsp_types::SymbolKind::Struct, 5)
+            },
+        ],
+    );
+    builder.add_file_with_id(2, "/root/b.rs".to_string(), vec![func("g", 0)]);
+
+    builder.add_call_relation(pos(1, 0), pos(2, 0), &[]);
+    builder.add_call_relation(pos(1, 6), pos(1, 0), &[]);
+    builder.add_call_relation(pos(1, 6), pos(1, 7), &[]);
+    builder.add_call_relation(pos(2, 0), pos(1, 0), &[]);
+    builder.add_relation(pos(1, 5), pos(2, 0), RelationKind::Impl);
+    builder.build()
+}
+
+fn ends(relations: Vec<&Relation>) -> Vec<(u32, u32, u32, u32)> {
+    relations
+        .into_iter()
+        .map(|r| (r.from.file_id, r.from.line, r.to.file_id, r.to.line))
+        .collect()
+}
+
+#[test]
+fn lookup_files_and_symbols() {
+    let graph = sample_graph();
+
+    assert_eq!(graph.get_file(2).unwrap().path, "/root/b.rs");
+    assert!(graph.get_file(3).is_none());
+
+    assert_eq!(
+        graph.get_symbol(pos(1, 7)).unwrap().name,
+        "n",
+        "应该能找到嵌套的符号"
+    );
+    assert_eq!(graph.get_symbol(pos(2, 0)).unwrap().name, "g");
+    assert!(graph.get_symbol(pos(1, 1)).is_none());
+}
+
+#[test]
+fn lookup_relations() {
+    let graph = sample_graph();
+
+    assert_eq!(
+        ends(graph.outgoing_relations(pos(1, 6))),
+        vec![(1, 6, 1, 0), (1, 6, 1, 7)]
+    );
+    assert_eq!(
+        ends(graph.incoming_relations(pos(1, 0))),
+        vec![(1, 6, 1, 0), (2, 0, 1, 0)]
+    );
+    assert!(graph.incoming_relations(pos(1, 6)).is_empty());
+
+    // 与线性扫描的结果和顺序一致
+    for position in [pos(1, 0), pos(1, 5), pos(1, 6), pos(2, 0)] {
+        let scanned = graph
+            .relations()
+            .iter()
+            .filter(|r| r.from == position || r.to == position)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ends(graph.get_symbol_relations(position)),
+            ends(scanned),
+            "{:?}",
+            position
+        );
+    }
+}
+
+#[test]
+fn search_by_kind_uses_index() {
+    let graph = sample_graph();
+
+    let methods = graph
+        .search_by_symbol_kind(SymbolKind::Method)
+        .into_iter()
+        .map(|r| (r.symbol_name, r.file_path))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        methods,
+        vec![
+            ("m".to_string(), "/root/a.rs".to_string()),
+            ("n".to_string(), "/root/a.rs".to_string())
+        ]
+    );
+    assert!(graph.search_by_symbol_kind(SymbolKind::Enum).is_empty());
+}
+
+#[test]
+fn reindex_after_changes() {
+    let mut graph = sample_graph();
+    assert_eq!(graph.outgoing_relations(pos(2, 0)).len(), 1);
+
+    // relations_mut 会丢弃索引，下次查询时重建
+    graph.relations_mut().retain(|r| r.from != pos(2, 0));
+    assert!(graph.outgoing_relations(pos(2, 0)).is_empty());
+    assert_eq!(graph.incoming_relations(pos(1, 0)).len(), 1);
+
+    // 直接修改字段之后需要调用 reindex
+    graph.relations.clear();
+    graph.reindex();
+    assert!(graph.incoming_relations(pos(1, 0)).is_empty());
+}
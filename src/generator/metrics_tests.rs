@@ -0,0 +1,126 @@
+use super::{
+    test_support::{func, incoming, pos},
+    DotOptions, GraphGenerator, NodeMetric,
+};
+
+const X: &str = "/root/x.rs";
+const Y: &str = "/root/y.rs";
+
+/// x.rs: main -> a, b -> hub；y.rs: hub -> c, d，c -> c (自递归)，d <-> e (循环)
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let (main, a, b, hub) = (
+        func("main", 0),
+        func("a", 5),
+        func("b", 10),
+        func("hub", 15),
+    );
+    let (c, d, e) = (func("c", 0), func("d", 5), func("e", 10));
+    generator.add_file(
+        X.to_string(),
+        vec![main.clone(), a.clone(), b.clone(), hub.clone()],
+    );
+    generator.add_file(Y.to_string(), vec![c.clone(), d.clone(), e.clone()]);
+
+    for (callee_path, callee, callers) in [
+        (X, &a, vec![(X, &main)]),
+        (X, &b, vec![(X, &main)]),
+        (X, &hub, vec![(X, &a), (X, &b)]),
+        (Y, &c, vec![(X, &hub), (Y, &c)]),
+        (Y, &d, vec![(X, &hub), (Y, &e)]),
+        (Y, &e, vec![(Y, &d)]),
+    ] {
+        generator.add_incoming_calls(
+            callee_path.to_string(),
+            callee.selection_range.start,
+            callers
+                .into_iter()
+                .map(|(path, caller)| incoming(path, caller, &[]))
+                .collect(),
+        );
+    }
+
+    generator
+}
+
+#[test]
+fn symbol_metrics() {
+    let metrics = sample_generator().compute_metrics();
+    let of = |file_id, line| {
+        metrics
+            .symbols
+            .iter()
+            .find(|m| m.position == pos(file_id, line))
+            .unwrap()
+    };
+
+    assert_eq!(metrics.symbols.len(), 7);
+
+    let hub = of(1, 15);
+    assert_eq!((hub.fan_in, hub.fan_out), (2, 2));
+    assert_eq!(hub.depth, Some(2));
+    assert_eq!(
+        hub.betweenness, 9.0,
+        "main、a、b 到 c、d、e 的最短路径都经过 hub"
+    );
+
+    assert_eq!(of(1, 5).betweenness, 2.0, "main 出发的最短路径有一半经过 a");
+
+    let c = of(2, 0);
+    assert_eq!((c.fan_in, c.fan_out), (1, 0), "递归调用不应该被计入");
+    assert_eq!(c.depth, Some(3));
+
+    assert_eq!(of(1, 0).depth, Some(0), "没有调用者的函数是入口");
+    assert_eq!(of(2, 5).depth, Some(3));
+    assert_eq!(of(2, 10).depth, Some(3), "循环中的函数深度相同");
+}
+
+#[test]
+fn file_metrics() {
+    let metrics = sample_generator().compute_metrics();
+
+    let files = metrics
+        .files
+        .iter()
+        .map(|f| (f.file_id, f.symbols, f.fan_in, f.fan_out, f.max_depth))
+        .collect::<Vec<_>>();
+    assert_eq!(files, vec![(1, 4, 0, 2, Some(2)), (2, 3, 2, 0, Some(3))]);
+    assert_eq!(metrics.files[0].max_betweenness, 9.0);
+
+    let json = serde_json::to_value(&metrics).unwrap();
+    assert_eq!(json["files"][1]["fanIn"], 2);
+    assert_eq!(json["symbols"][0]["depth"], 0);
+}
+
+#[test]
+fn color_nodes_by_metric() {
+    let mut generator = sample_generator();
+    assert!(
+        !generator.generate_dot_source().contains("BGCOLOR=\"#"),
+        "默认不应该着色"
+    );
+
+    generator.set_dot_options(DotOptions {
+        node_metric: NodeMetric::FanIn,
+        ..Default::default()
+    });
+    let dot = generator.generate_dot_source();
+
+    let cell = |id: &str| {
+        dot.lines()
+            .find(|line| line.contains(&format!(r#"ID="{}""#, id)))
+            .unwrap()
+            .to_string()
+    };
+    assert!(
+        cell("1:15_3").contains(r##"BGCOLOR="#cb181d""##),
+        "hub 的调用者最多:\n{}",
+        dot
+    );
+    assert!(
+        cell("1:0_3").contains(r##"BGCOLOR="#fff5f0""##),
+        "main 没有调用者:\n{}",
+        dot
+    );
+}
@@ -0,0 +1,192 @@
+use {
+    super::{
+        test_support::{func, incoming, pos},
+        GraphGenerator,
+    },
+    crate::{
+        graph_model::GlobalPosition,
+        lsp_types::{CallHierarchyIncomingCall, DocumentSymbol},
+    },
+    std::collections::BTreeSet,
+};
+
+const PATH: &str = "/root/a.rs";
+
+fn call(caller: &DocumentSymbol) -> CallHierarchyIncomingCall {
+    incoming(PATH, caller, &[])
+}
+
+/// a(0) -> b(5) -> d(15)，a -> c(10) -> e(20) -> d，d -> a (回边)，x(25) -> b
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let [a, b, c, d, e, x] = [
+        ("a", 0),
+        ("b", 5),
+        ("c", 10),
+        ("d", 15),
+        ("e", 20),
+        ("x", 25),
+    ]
+    .map(|(n, l)| func(n, l));
+    generator.add_file(
+        PATH.to_string(),
+        vec![
+            a.clone(),
+            b.clone(),
+            c.clone(),
+            d.clone(),
+            e.clone(),
+            x.clone(),
+        ],
+    );
+
+    for (callee, callers) in [
+        (&b, vec![&a, &x]),
+        (&c, vec![&a]),
+        (&d, vec![&b, &e]),
+        (&e, vec![&c]),
+        (&a, vec![&d]),
+    ] {
+        generator.add_incoming_calls(
+            PATH.to_string(),
+            callee.selection_range.start,
+            callers.into_iter().map(call).collect(),
+        );
+    }
+
+    generator
+}
+
+fn position(line: u32) -> GlobalPosition {
+    pos(1, line)
+}
+
+fn lines(paths: Vec<Vec<GlobalPosition>>) -> Vec<Vec<u32>> {
+    paths
+        .into_iter()
+        .map(|path| path.into_iter().map(|p| p.line).collect())
+        .collect()
+}
+
+#[test]
+fn find_paths_shortest_first() {
+    let generator = sample_generator();
+
+    assert_eq!(
+        lines(generator.find_paths(position(0), position(15), 10, 10)),
+        vec![vec![0, 5, 15], vec![0, 10, 20, 15]],
+        "路径应该按长度排序"
+    );
+    assert_eq!(
+        lines(generator.find_paths(position(0), position(15), 1, 10)),
+        vec![vec![0, 5, 15]],
+        "应该最多返回 max_paths 条路径"
+    );
+    assert_eq!(
+        lines(generator.find_paths(position(0), position(15), 10, 2)),
+        vec![vec![0, 5, 15]],
+        "超过 max_len 的路径应该被忽略"
+    );
+    assert_eq!(
+        lines(generator.find_paths(position(15), position(5), 10, 10)),
+        vec![vec![15, 0, 5]]
+    );
+    assert!(
+        generator
+            .find_paths(position(5), position(25), 10, 10)
+            .is_empty(),
+        "没有调用链时应该返回空"
+    );
+    assert_eq!(
+        lines(generator.find_paths(position(0), position(0), 10, 10)),
+        vec![vec![0]],
+        "起点和终点相同时只有一条空调用链，即使它在循环中"
+    );
+    assert!(generator
+        .find_paths(position(0), position(0), 0, 10)
+        .is_empty());
+}
+
+#[test]
+fn find_paths_with_exponentially_many_chains() {
+    // 30 层菱形：h(3i) -> l(3i+1)、r(3i+2) -> h(3i+3)，共有 2^30 条等长的调用链
+    const LAYERS: u32 = 30;
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+    let funcs = (0..=LAYERS * 3)
+        .map(|line| func(&format!("f{}", line), line))
+        .collect::<Vec<_>>();
+    generator.add_file(PATH.to_string(), funcs.clone());
+
+    for layer in 0..LAYERS {
+        let [hub, left, right, next] = [0, 1, 2, 3].map(|i| &funcs[(layer * 3 + i) as usize]);
+        for side in [left, right] {
+            generator.add_incoming_calls(
+                PATH.to_string(),
+                side.selection_range.start,
+                vec![call(hub)],
+            );
+        }
+        generator.add_incoming_calls(
+            PATH.to_string(),
+            next.selection_range.start,
+            vec![call(left), call(right)],
+        );
+    }
+
+    let paths = lines(generator.find_paths(position(0), position(LAYERS * 3), 3, 100));
+    assert_eq!(paths.len(), 3, "应该在找到 max_paths 条路径后立即停止");
+    assert!(paths
+        .iter()
+        .all(|path| path.len() == 2 * LAYERS as usize + 1));
+    assert_eq!(&paths[0][..4], &[0, 1, 3, 4]);
+    assert!(paths[0] < paths[1] && paths[1] < paths[2]);
+}
+
+#[test]
+fn path_graph_keeps_only_the_paths() {
+    let generator = sample_generator();
+
+    let paths = generator
+        .generate_path_graph(position(0), position(15), 10, 10)
+        .expect("两端都在已添加的文件中");
+    let graph = paths.generate_graph();
+
+    let relations = graph
+        .relations()
+        .iter()
+        .map(|r| (r.from.line, r.to.line))
+        .collect::<BTreeSet<_>>();
+    assert_eq!(
+        relations,
+        BTreeSet::from([(0, 5), (5, 15), (0, 10), (10, 20), (20, 15)]),
+        "只应该保留路径上的调用，回边和 x 的调用应该被去掉"
+    );
+
+    let names = graph.files()[0]
+        .symbols
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+    assert_eq!(paths.highlights[&1], [(0, 3), (15, 3)].into());
+
+    let mermaid = paths.generate_mermaid_source();
+    assert!(mermaid.contains("1_0_3"), "{}", mermaid);
+    assert!(!mermaid.contains("1_25_3"), "x 不在路径上:\n{}", mermaid);
+
+    let short = generator
+        .generate_path_graph(position(0), position(15), 10, 2)
+        .unwrap()
+        .generate_graph();
+    assert_eq!(short.relations().len(), 2);
+
+    let missing = GlobalPosition {
+        file_id: 9,
+        line: 0,
+        character: 0,
+    };
+    assert!(generator
+        .generate_path_graph(position(0), missing, 10, 10)
+        .is_none());
+}
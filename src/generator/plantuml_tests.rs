@@ -0,0 +1,178 @@
+use {
+    super::{
+        test_support::{func, location, outgoing, symbol, type_item},
+        GraphGenerator,
+    },
+    crate::{
+        graph::plantuml::PlantUmlRenderer,
+        graph_model::Granularity,
+        lsp_types::{DocumentSymbol, SymbolKind},
+    },
+};
+
+const SHAPES: &str = "/root/src/shapes.rs";
+const HTTP: &str = "/root/src/net/http.rs";
+const MAIN: &str = "/root/main.rs";
+
+/// src/shapes.rs：接口 Shape（方法 area），Circle 实现 Shape，Circle 有方法 new、area 以及嵌套的枚举 Unit，
+///               Base 为 Circle 的父类
+/// src/net/http.rs：get
+/// main.rs：main 调用两次 Circle::new、一次 Circle::area 和 get；Circle::new 调用 Circle::area
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let shape = DocumentSymbol {
+        children: vec![symbol("area", SymbolKind::Method, 1)],
+        ..symbol("Shape", SymbolKind::Interface, 0)
+    };
+    let new = symbol("new", SymbolKind::Method, 11);
+    let area = symbol("area", SymbolKind::Method, 12);
+    let unit = DocumentSymbol {
+        children: vec![symbol("Cm", SymbolKind::EnumMember, 14)],
+        ..symbol("Unit", SymbolKind::Enum, 13)
+    };
+    let circle = DocumentSymbol {
+        children: vec![new.clone(), area.clone(), unit],
+        ..symbol("Circle", SymbolKind::Class, 10)
+    };
+    let base = symbol("Base", SymbolKind::Class, 20);
+    let get = func("get", 0);
+    let main = func("main", 0);
+
+    generator.add_file(MAIN.to_string(), vec![main.clone()]);
+    generator.add_file(HTTP.to_string(), vec![get.clone()]);
+    generator.add_file(
+        SHAPES.to_string(),
+        vec![shape.clone(), circle.clone(), base.clone()],
+    );
+
+    generator.add_outgoing_calls(
+        MAIN.to_string(),
+        main.selection_range.start,
+        vec![
+            outgoing(SHAPES, &new, &[1, 2]),
+            outgoing(SHAPES, &area, &[3]),
+            outgoing(HTTP, &get, &[4]),
+        ],
+    );
+    generator.add_outgoing_calls(
+        SHAPES.to_string(),
+        new.selection_range.start,
+        vec![outgoing(SHAPES, &area, &[11])],
+    );
+    generator.add_interface_implementations(
+        SHAPES.to_string(),
+        shape.selection_range.start,
+        vec![location(SHAPES, &circle)],
+    );
+    generator.add_subtypes(
+        SHAPES.to_string(),
+        base.selection_range.start,
+        vec![type_item(SHAPES, &circle)],
+    );
+
+    generator
+}
+
+#[test]
+fn classes_and_packages() {
+    let uml = sample_generator().generate_plantuml_source();
+
+    assert!(uml.starts_with("@startuml\n") && uml.ends_with("@enduml\n"));
+
+    // 目录嵌套为package，根目录下的文件不在任何目录package中
+    let src = uml.find(r#"package "src" as"#).expect(&uml);
+    let net = uml.find(r#"  package "net" as"#).expect(&uml);
+    let http = uml.find(r#"    package "http.rs" as"#).expect(&uml);
+    assert!(src < net && net < http, "{}", uml);
+    assert!(
+        uml.contains("\npackage \"main.rs\" as f1 <<Rectangle>> {\n"),
+        "{}",
+        uml
+    );
+
+    // 类型的方法作为成员，嵌套的类型与其并列
+    assert!(
+        uml.contains("    interface \"Shape\" as s3_0_3 {\n      area()\n    }\n"),
+        "{}",
+        uml
+    );
+    assert!(
+        uml.contains(
+            "    class \"Circle\" as s3_10_3 {\n      new()\n      area()\n    }\n    enum \"Unit\" as s3_13_3\n"
+        ),
+        "{}",
+        uml
+    );
+    assert!(
+        uml.contains("class \"get\" as s2_0_3 <<function>>\n"),
+        "{}",
+        uml
+    );
+}
+
+#[test]
+fn arrows() {
+    let uml = sample_generator().generate_plantuml_source();
+    let edges = uml
+        .lines()
+        .filter(|line| line.contains(" -") || line.contains(" ."))
+        .collect::<Vec<_>>();
+
+    // 到同一个类的成员的调用合并为一条边，类内部的调用不画
+    assert_eq!(
+        edges,
+        vec![
+            "s1_0_3 ..> s2_0_3",
+            "s1_0_3 ..> s3_10_3 : 3",
+            "s3_10_3 ..|> s3_0_3",
+            "s3_10_3 --|> s3_20_3",
+        ],
+        "{}",
+        uml
+    );
+}
+
+#[test]
+fn diff_colors_and_symbol_ids() {
+    let generator = sample_generator();
+    let graph = generator.generate_graph();
+    let old = {
+        let mut old = GraphGenerator::new("/root".to_string(), "");
+        old.add_file(HTTP.to_string(), vec![func("get", 0)]);
+        old.add_file(MAIN.to_string(), vec![func("main", 0)]);
+        old.generate_graph()
+    };
+    let diff = old.diff(&graph);
+
+    let uml = generator.render_diff(&graph, &diff, &PlantUmlRenderer { symbol_ids: false });
+    assert!(uml.contains("s1_0_3 .[#1a7f37].> s2_0_3\n"), "{}", uml);
+    assert!(uml.contains("s3_10_3 .[#1a7f37].|> s3_0_3\n"), "{}", uml);
+
+    let uml = generator.render(&graph, &PlantUmlRenderer { symbol_ids: true });
+    let main = graph.files()[0].symbols[0].id.as_str();
+    assert!(
+        uml.contains(&format!("class \"main\" as s{} <<function>>\n", main)),
+        "{}",
+        uml
+    );
+    assert!(!uml.contains("s1_0_3"), "{}", uml);
+}
+
+#[test]
+fn components() {
+    let mut generator = sample_generator();
+    generator.set_granularity(Granularity::File);
+    let uml = generator.generate_plantuml_source();
+
+    assert_eq!(
+        uml,
+        "@startuml\n\
+         component \"main.rs\" as n1\n\
+         component \"src/net/http.rs\" as n2\n\
+         component \"src/shapes.rs\" as n3\n\
+         n1 ..> n2 : 1\n\
+         n1 ..> n3 : 3\n\
+         @enduml\n"
+    );
+}
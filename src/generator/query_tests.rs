@@ -0,0 +1,170 @@
+use {
+    super::test_support::{func, pos, symbol},
+    crate::{
+        graph_model::{Graph, GraphBuilder, Query, QueryError},
+        lsp_types::{DocumentSymbol, SymbolKind},
+    },
+};
+
+fn method(name: &str, line: u32) -> DocumentSymbol {
+    symbol(name, SymbolKind::Method, line)
+}
+
+/// 文件1 src/net/socket.rs: Conn { send_packet, recv, flush }, connect
+/// 文件2 src/app.rs: main, run, test_flush
+/// main -> run -> recv -> send_packet，test_flush -> flush -> send_packet，run -> connect
+fn sample_graph() -> Graph {
+    let mut builder = GraphBuilder::new();
+    builder.add_file_with_id(
+        1,
+        "/root/src/net/socket.rs".to_string(),
+        vec![
+            DocumentSymbol {
+                children: vec![
+                    method("send_packet", 1),
+                    method("recv", 2),
+                    method("flush", 3),
+                ],
+                ..symbol("Conn", SymbolKind::Struct, 0)
+            },
+            func("connect", 10),
+        ],
+    );
+    builder.add_file_with_id(
+        2,
+        "/root/src/app.rs".to_string(),
+        vec![func("main", 0), func("run", 5), func("test_flush", 10)],
+    );
+
+    builder.add_call_relation(pos(2, 0), pos(2, 5), &[]);
+    builder.add_call_relation(pos(2, 5), pos(1, 2), &[]);
+    builder.add_call_relation(pos(1, 2), pos(1, 1), &[]);
+    builder.add_call_relation(pos(2, 10), pos(1, 3), &[]);
+    builder.add_call_relation(pos(1, 3), pos(1, 1), &[]);
+    builder.add_call_relation(pos(2, 5), pos(1, 10), &[]);
+    builder.build()
+}
+
+fn names(graph: &Graph) -> Vec<String> {
+    fn collect(symbols: &[crate::graph_model::Symbol], names: &mut Vec<String>) {
+        for symbol in symbols {
+            names.push(symbol.name.clone());
+            collect(&symbol.children, names);
+        }
+    }
+
+    let mut names = vec![];
+    for file in graph.files() {
+        collect(&file.symbols, &mut names);
+    }
+    names
+}
+
+fn relations(graph: &Graph) -> Vec<(u32, u32, u32, u32)> {
+    graph
+        .relations()
+        .iter()
+        .map(|r| (r.from.file_id, r.from.line, r.to.file_id, r.to.line))
+        .collect()
+}
+
+#[test]
+fn filter_by_query() {
+    let graph = sample_graph();
+
+    let filtered = graph
+        .query("kind:method file:src/net/** calls:send_* -callers:test_*")
+        .unwrap();
+    assert_eq!(names(&filtered), vec!["Conn", "recv"], "Conn 作为容器保留");
+    assert!(filtered.relations().is_empty(), "send_packet 不在结果中");
+
+    let filtered = graph.query("file:socket.rs").unwrap();
+    assert_eq!(filtered.files().len(), 1);
+    assert_eq!(
+        relations(&filtered),
+        vec![(1, 2, 1, 1), (1, 3, 1, 1)],
+        "只保留两端都在结果中的关系"
+    );
+
+    let filtered = graph.query("callers:run").unwrap();
+    assert_eq!(names(&filtered), vec!["Conn", "recv", "connect"]);
+
+    let filtered = graph.query("-kind:method,struct").unwrap();
+    assert_eq!(
+        names(&filtered),
+        vec!["connect", "main", "run", "test_flush"]
+    );
+    assert_eq!(relations(&filtered), vec![(2, 0, 2, 5), (2, 5, 1, 10)]);
+
+    assert!(graph.query("name:nothing*").unwrap().files().is_empty());
+    assert_eq!(
+        names(&graph.query("").unwrap()).len(),
+        8,
+        "空查询保留整个图"
+    );
+}
+
+#[test]
+fn globs() {
+    let graph = sample_graph();
+
+    assert_eq!(names(&graph.query("r??").unwrap()), vec!["run"]);
+    assert_eq!(names(&graph.query("file:src/*.rs").unwrap()).len(), 3);
+    assert_eq!(
+        names(&graph.query("file:src/**/*.rs").unwrap()).len(),
+        8,
+        "** 可以匹配多层目录"
+    );
+    assert_eq!(
+        names(&graph.query("file:net/*").unwrap()).len(),
+        5,
+        "file 可以匹配路径的后缀"
+    );
+    assert!(graph.query("file:ket.rs").unwrap().files().is_empty());
+}
+
+#[test]
+fn parse_and_display() {
+    let query = "  kind:Method,function   -callers:test_*  send* ";
+    let parsed = query.parse::<Query>().unwrap();
+    assert_eq!(
+        parsed.to_string(),
+        "kind:method,function -callers:test_* name:send*"
+    );
+    assert_eq!(
+        Query::parse(&parsed.to_string()).unwrap().to_string(),
+        parsed.to_string(),
+        "规范化的查询应该能被解析回来"
+    );
+    assert!(Query::parse(" ").unwrap().is_empty());
+
+    // 不是已知的键或者键后面还有冒号时，整个词都是名称
+    for (query, normalized) in [
+        ("Circle::new", "name:Circle::new"),
+        ("name:Circle::new", "name:Circle::new"),
+        ("size:3", "name:size:3"),
+        ("calls::new", "name:calls::new"),
+        ("::new", "::new"),
+    ] {
+        let parsed = Query::parse(query).unwrap();
+        assert_eq!(parsed.to_string(), normalized);
+        assert_eq!(
+            Query::parse(normalized).unwrap().to_string(),
+            normalized,
+            "{}",
+            query
+        );
+    }
+    assert_eq!(
+        Query::parse("kind:method,widget").unwrap_err(),
+        QueryError::UnknownKind("widget".to_string())
+    );
+    assert_eq!(
+        Query::parse("calls:").unwrap_err(),
+        QueryError::EmptyValue("calls:".to_string())
+    );
+    assert_eq!(
+        Query::parse("-").unwrap_err(),
+        QueryError::EmptyValue("-".to_string())
+    );
+}
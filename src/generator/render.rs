@@ -0,0 +1,335 @@
+//! Rendering of symbol-level graphs: every output format goes from a `Graph` to the tables and
+//! edges of its symbols, then to a `Renderer`.
+
+use {
+    super::{collapse::relation_class, FileOutline, GraphGenerator},
+    crate::{
+        graph::{
+            dot::{DotRenderer, NodeMetric},
+            mermaid::MermaidRenderer,
+            plantuml::PlantUmlRenderer,
+            svg::{EdgeRouting, SvgRenderer},
+            CssClass, Edge, Renderer, TableNode,
+        },
+        graph_model::{
+            relative_path, DiffSymbol, File, GlobalPosition, Graph, GraphDiff, Relation, Symbol,
+            SymbolId,
+        },
+        lsp_types::{DocumentSymbol, Position, Range},
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        path::{Path, PathBuf},
+    },
+};
+
+impl GraphGenerator {
+    /// Renders `graph`, such as a subgraph of `generate_graph`, with `renderer`. Highlights
+    /// apply to the files with the same paths.
+    pub fn render(&self, graph: &Graph, renderer: &dyn Renderer) -> String {
+        self.render_marked(graph, renderer, |_| {})
+    }
+
+    /// Renders `graph`, the newer graph of `diff`, with the added edges colored green and the
+    /// removed ones red. The symbols removed along with their relations are drawn as cells
+    /// after the last symbol of their files, in files of their own if the files were removed.
+    pub fn render_diff(&self, graph: &Graph, diff: &GraphDiff, renderer: &dyn Renderer) -> String {
+        let cells =
+            |position: GlobalPosition| (position.file_id, position.line, position.character);
+
+        let mut files = graph.files().to_vec();
+        let mut placeholders = HashMap::new();
+        let mut position_of = |symbol: &DiffSymbol| {
+            symbol.new_position.unwrap_or_else(|| {
+                *placeholders
+                    .entry(symbol.old_position)
+                    .or_insert_with(|| placeholder(&mut files, graph.root(), symbol))
+            })
+        };
+
+        let mut ends = graph
+            .relations()
+            .iter()
+            .map(|relation| (relation.from, relation.to))
+            .collect::<HashSet<_>>();
+        let mut relations = graph.relations().to_vec();
+        let mut removed = HashSet::new();
+        for relation in &diff.removed_relations {
+            let (from, to) = (position_of(&relation.from), position_of(&relation.to));
+            // the symbols may still be related in another way
+            if !ends.insert((from, to)) {
+                continue;
+            }
+            relations.push(Relation {
+                from,
+                to,
+                kind: relation.kind.clone(),
+                call_sites: vec![],
+                call_count: 0,
+            });
+            removed.insert((cells(from), cells(to)));
+        }
+
+        let added = diff
+            .added_relations
+            .iter()
+            .filter_map(|relation| {
+                Some((
+                    cells(relation.from.new_position?),
+                    cells(relation.to.new_position?),
+                ))
+            })
+            .collect::<HashSet<_>>();
+
+        let graph = Graph::new(files, relations);
+        self.render_marked(&graph, renderer, |edges| {
+            for edge in edges {
+                if added.contains(&(edge.from, edge.to)) {
+                    edge.classes.insert(CssClass::Added);
+                } else if removed.contains(&(edge.from, edge.to)) {
+                    edge.classes.insert(CssClass::Removed);
+                }
+            }
+        })
+    }
+
+    /// Renders `graph` after `mark` has added classes to its edges.
+    fn render_marked<F>(&self, graph: &Graph, renderer: &dyn Renderer, mark: F) -> String
+    where
+        F: FnOnce(&mut [Edge]),
+    {
+        let files = outlines(graph);
+
+        let metric_colors = match self.dot_options.node_metric {
+            NodeMetric::Off => HashMap::new(),
+            _ => self.metric_colors(graph),
+        };
+        let tables = self.tables(&files, &metric_colors, &symbol_ids(graph));
+
+        let mut edges = self.edges(graph, &tables);
+        mark(&mut edges);
+        if self.dot_options.highlight_cycles {
+            Edge::mark_cycles(&mut edges);
+        }
+
+        let files = files.iter().map(|file| (file.id, file.path.as_path()));
+        renderer.render(tables, edges, &self.subgraphs(files))
+    }
+
+    pub fn render_dot(&self, graph: &Graph) -> String {
+        self.render(
+            graph,
+            &DotRenderer {
+                options: &self.dot_options,
+            },
+        )
+    }
+
+    pub fn render_mermaid(&self, graph: &Graph) -> String {
+        self.render(
+            graph,
+            &MermaidRenderer {
+                root: &self.root,
+                symbol_ids: self.dot_options.symbol_ids,
+            },
+        )
+    }
+
+    pub fn render_plantuml(&self, graph: &Graph) -> String {
+        self.render(
+            graph,
+            &PlantUmlRenderer {
+                symbol_ids: self.dot_options.symbol_ids,
+            },
+        )
+    }
+
+    pub fn render_diff_dot(&self, graph: &Graph, diff: &GraphDiff) -> String {
+        self.render_diff(
+            graph,
+            diff,
+            &DotRenderer {
+                options: &self.dot_options,
+            },
+        )
+    }
+
+    pub fn render_diff_mermaid(&self, graph: &Graph, diff: &GraphDiff) -> String {
+        self.render_diff(
+            graph,
+            diff,
+            &MermaidRenderer {
+                root: &self.root,
+                symbol_ids: self.dot_options.symbol_ids,
+            },
+        )
+    }
+
+    pub fn render_svg(&self, graph: &Graph, routing: EdgeRouting) -> String {
+        self.render(
+            graph,
+            &SvgRenderer {
+                options: &self.dot_options,
+                routing,
+            },
+        )
+    }
+
+    fn tables(
+        &self,
+        files: &[FileOutline],
+        metric_colors: &HashMap<u32, HashMap<(u32, u32), String>>,
+        symbol_ids: &HashMap<u32, HashMap<(u32, u32), SymbolId>>,
+    ) -> Vec<TableNode> {
+        files
+            .iter()
+            .map(|file| {
+                let mut table = self.lang.file_repr(file);
+                let highlights = file
+                    .path
+                    .to_str()
+                    .and_then(|path| self.files.get(path))
+                    .and_then(|outline| self.highlights.get(&outline.id));
+                if let Some(cells) = highlights {
+                    table.highlight_cells(cells);
+                }
+                if let Some(colors) = metric_colors.get(&file.id) {
+                    table.color_cells(colors);
+                }
+                if let Some(ids) = symbol_ids.get(&file.id) {
+                    table.set_symbol_ids(ids);
+                }
+                table
+            })
+            .collect()
+    }
+
+    /// Edges of the relations between symbols shown in `tables`
+    fn edges(&self, graph: &Graph, tables: &[TableNode]) -> Vec<Edge> {
+        let mut cell_ids = HashSet::new();
+        for table in tables {
+            for cell in &table.sections {
+                self.collect_cell_ids(table.id, cell, &mut cell_ids);
+            }
+        }
+
+        Edge::merge(graph.relations().iter().filter_map(|relation| {
+            let from = (
+                relation.from.file_id,
+                relation.from.line,
+                relation.from.character,
+            );
+            let to = (relation.to.file_id, relation.to.line, relation.to.character);
+            if !cell_ids.contains(&from) || !cell_ids.contains(&to) {
+                return None;
+            }
+
+            Some(Edge {
+                from,
+                to,
+                classes: relation_class(&relation.kind),
+                call_sites: relation.call_sites.iter().map(Range::from).collect(),
+            })
+        }))
+    }
+}
+
+/// Ids of the symbols of `graph` by file id and start of the symbols
+fn symbol_ids(graph: &Graph) -> HashMap<u32, HashMap<(u32, u32), SymbolId>> {
+    fn collect(symbols: &[Symbol], ids: &mut HashMap<(u32, u32), SymbolId>) {
+        for symbol in symbols {
+            let position = symbol.global_position;
+            ids.insert((position.line, position.character), symbol.id.clone());
+            collect(&symbol.children, ids);
+        }
+    }
+
+    graph
+        .files()
+        .iter()
+        .map(|file| {
+            let mut ids = HashMap::new();
+            collect(&file.symbols, &mut ids);
+            (file.id, ids)
+        })
+        .collect()
+}
+
+/// Adds to `files` a symbol standing for `symbol`, removed from them, after the last symbol of
+/// its file or in a new file, and returns its position.
+fn placeholder(files: &mut Vec<File>, root: &str, symbol: &DiffSymbol) -> GlobalPosition {
+    let index = match files
+        .iter()
+        .position(|file| relative_path(root, &file.path) == symbol.path)
+    {
+        Some(index) => index,
+        None => {
+            let id = files.iter().map(|file| file.id).max().unwrap_or(0) + 1;
+            files.push(File {
+                id,
+                path: Path::new(root)
+                    .join(&symbol.path)
+                    .to_string_lossy()
+                    .into_owned(),
+                symbols: vec![],
+            });
+            files.len() - 1
+        }
+    };
+
+    let file = &mut files[index];
+    let start = Position {
+        line: last_line(&file.symbols).map_or(0, |line| line + 1),
+        character: symbol.old_position.map_or(0, |position| position.character),
+    };
+    let global_position = GlobalPosition::new(file.id, start);
+    file.symbols.push(Symbol {
+        name: symbol.qualified_name.clone(),
+        kind: symbol.kind,
+        range: Range { start, end: start }.into(),
+        children: vec![],
+        global_position,
+        id: SymbolId::from_key(&format!("{}#{}", symbol.path, symbol.qualified_name)),
+    });
+    global_position
+}
+
+/// Last line of `symbols` and their children
+fn last_line(symbols: &[Symbol]) -> Option<u32> {
+    symbols
+        .iter()
+        .flat_map(|symbol| [Some(symbol.range.end.line), last_line(&symbol.children)])
+        .flatten()
+        .max()
+}
+
+/// Files of `graph` as the outlines the languages turn into tables
+fn outlines(graph: &Graph) -> Vec<FileOutline> {
+    graph
+        .files()
+        .iter()
+        .map(|file| FileOutline {
+            id: file.id,
+            path: PathBuf::from(&file.path),
+            symbols: file.symbols.iter().map(document_symbol).collect(),
+        })
+        .collect()
+}
+
+fn document_symbol(symbol: &Symbol) -> DocumentSymbol {
+    // the graph model only keeps the start of the selection range, which identifies the cells
+    let start = Position {
+        line: symbol.global_position.line,
+        character: symbol.global_position.character,
+    };
+
+    DocumentSymbol {
+        name: symbol.name.clone(),
+        detail: None,
+        kind: symbol.kind.into(),
+        tags: None,
+        range: Range::from(&symbol.range),
+        selection_range: Range { start, end: start },
+        children: symbol.children.iter().map(document_symbol).collect(),
+    }
+}
@@ -0,0 +1,84 @@
+use {
+    super::{
+        test_support::{func, incoming, location, spanning, symbol},
+        GraphGenerator,
+    },
+    crate::{
+        graph::{Edge, Renderer, Subgraph, TableNode},
+        lsp_types::SymbolKind,
+    },
+};
+
+const A: &str = "/root/a.rs";
+const B: &str = "/root/b.rs";
+
+/// a.rs: outer (其中有文档符号中缺失的嵌套函数 inner), Trait, S；b.rs: g
+/// inner -> g，S 实现了 Trait
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let trait_ = spanning(symbol("Trait", SymbolKind::Interface, 20), 23);
+    let s = spanning(symbol("S", SymbolKind::Struct, 30), 33);
+    let g = spanning(func("g", 0), 3);
+    generator.add_file(
+        A.to_string(),
+        vec![spanning(func("outer", 0), 10), trait_.clone(), s.clone()],
+    );
+    generator.add_file(B.to_string(), vec![g.clone()]);
+
+    let inner = spanning(func("inner", 2), 4);
+    generator.add_incoming_calls(
+        B.to_string(),
+        g.selection_range.start,
+        vec![incoming(A, &inner, &[3])],
+    );
+    generator.add_interface_implementations(
+        A.to_string(),
+        trait_.selection_range.start,
+        vec![location(A, &s)],
+    );
+
+    generator
+}
+
+/// 只输出边的渲染器
+struct EdgeList;
+
+impl Renderer for EdgeList {
+    fn render(&self, _tables: Vec<TableNode>, edges: Vec<Edge>, _subgraphs: &[Subgraph]) -> String {
+        let mut edges = edges
+            .iter()
+            .map(|e| format!("{:?}->{:?}", e.from, e.to))
+            .collect::<Vec<_>>();
+        edges.sort();
+        edges.join(" ")
+    }
+}
+
+#[test]
+fn same_edges_in_every_format() {
+    let generator = sample_generator();
+
+    let graph = generator.generate_graph();
+    let inner = &graph.files()[0].symbols[0].children;
+    assert_eq!(inner.len(), 1, "缺失的嵌套函数应该被加入图中");
+    assert_eq!(inner[0].name, "inner");
+
+    let dot = generator.generate_dot_source();
+    assert!(dot.contains(r#"1:"2_3" -> 2:"0_3""#), "{}", dot);
+    assert!(dot.contains(r#"1:"30_3" -> 1:"20_3""#), "{}", dot);
+
+    let mermaid = generator.generate_mermaid_source();
+    assert!(mermaid.contains("1_2_3 --> 2_0_3"), "{}", mermaid);
+    assert!(mermaid.contains("1_30_3 --> 1_20_3"), "{}", mermaid);
+}
+
+#[test]
+fn custom_renderer() {
+    let generator = sample_generator();
+
+    assert_eq!(
+        generator.render(&generator.generate_graph(), &EdgeList),
+        "(1, 2, 3)->(2, 0, 3) (1, 30, 3)->(1, 20, 3)"
+    );
+}
@@ -0,0 +1,150 @@
+use {
+    super::test_support::{func, symbol},
+    crate::{
+        graph_model::{FileMatchType, Graph, GraphBuilder, MatchType, SearchError, SearchMode},
+        lsp_types::{DocumentSymbol, SymbolKind},
+    },
+};
+
+/// graph/misc.rs: gsr_helper, gasser
+/// src/graph.rs: Graph { get_symbol_relations, search_files }, getSymbolRelations, parse_graph
+fn sample_graph() -> Graph {
+    let mut builder = GraphBuilder::new();
+    builder.add_file_with_id(
+        2,
+        "/root/graph/misc.rs".to_string(),
+        vec![func("gsr_helper", 0), func("gasser", 1)],
+    );
+    builder.add_file_with_id(
+        1,
+        "/root/src/graph.rs".to_string(),
+        vec![
+            DocumentSymbol {
+                children: vec![
+                    symbol("get_symbol_relations", SymbolKind::Method, 1),
+                    symbol("search_files", SymbolKind::Method, 2),
+                ],
+                ..symbol("Graph", SymbolKind::Struct, 0)
+            },
+            func("getSymbolRelations", 5),
+            func("parse_graph", 6),
+        ],
+    );
+    builder.build()
+}
+
+fn names(graph: &Graph, query: &str, mode: SearchMode, case_sensitive: bool) -> Vec<String> {
+    graph
+        .search_symbols_with_mode(query, mode, case_sensitive)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.symbol_name)
+        .collect()
+}
+
+#[test]
+fn search_modes() {
+    let graph = sample_graph();
+
+    assert_eq!(
+        names(&graph, "graph", SearchMode::Substring, false),
+        vec!["Graph", "parse_graph"],
+        "完全匹配应该排在前面"
+    );
+    assert_eq!(
+        names(&graph, "graph", SearchMode::Substring, true),
+        vec!["parse_graph"]
+    );
+    assert_eq!(
+        graph.search_symbols("graph", false).len(),
+        2,
+        "search_symbols 应该使用子串匹配"
+    );
+
+    assert_eq!(
+        names(&graph, "get", SearchMode::Prefix, false),
+        vec!["getSymbolRelations", "get_symbol_relations"]
+    );
+
+    assert_eq!(
+        names(&graph, "^(parse|search)_", SearchMode::Regex, true),
+        vec!["parse_graph", "search_files"]
+    );
+    assert!(matches!(
+        graph.search_symbols_with_mode("(", SearchMode::Regex, true),
+        Err(SearchError::InvalidRegex(_))
+    ));
+
+    let fuzzy = names(&graph, "gsr", SearchMode::Fuzzy, false);
+    assert_eq!(
+        fuzzy.last().map(String::as_str),
+        Some("gasser"),
+        "不在词首的匹配应该排在后面: {:?}",
+        fuzzy
+    );
+    assert_eq!(fuzzy.len(), 4, "{:?}", fuzzy);
+
+    assert_eq!(
+        names(&graph, "getSymRel", SearchMode::WordPrefix, false),
+        vec!["getSymbolRelations", "get_symbol_relations"]
+    );
+    assert_eq!(
+        names(&graph, "get_sym_rel", SearchMode::WordPrefix, true),
+        vec!["get_symbol_relations"],
+        "区分大小写时 Sym 不应该匹配 sym"
+    );
+    assert!(names(&graph, "gsrx", SearchMode::WordPrefix, false).is_empty());
+}
+
+#[test]
+fn search_by_container_path() {
+    let graph = sample_graph();
+
+    let results = graph
+        .search_symbols_with_mode("graph::search", SearchMode::Substring, false)
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].qualified_name, "Graph::search_files");
+    assert!(matches!(results[0].match_type, MatchType::QualifiedName));
+
+    let results = graph
+        .search_symbols_with_mode("src/graph.rs::Graph::g", SearchMode::Fuzzy, true)
+        .unwrap();
+    assert_eq!(results[0].symbol_name, "get_symbol_relations");
+    assert!(matches!(results[0].match_type, MatchType::FilePath));
+    assert!(
+        results.iter().all(|r| r.file_id == 1),
+        "其他文件的符号不应该匹配"
+    );
+
+    let methods = graph.search_by_symbol_kind(crate::graph_model::SymbolKind::Method);
+    assert_eq!(methods[0].qualified_name, "Graph::get_symbol_relations");
+}
+
+#[test]
+fn search_files_by_name_first() {
+    let graph = sample_graph();
+
+    let results = graph
+        .search_files("graph", false)
+        .into_iter()
+        .map(|r| (r.file_path, r.match_type))
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert_eq!(
+        results[0].0, "/root/src/graph.rs",
+        "文件名匹配的应该排在前面"
+    );
+    assert!(matches!(results[0].1, FileMatchType::Name));
+    assert!(matches!(results[1].1, FileMatchType::Path));
+
+    let results = graph.search_files("/root/", false);
+    assert_eq!(results.len(), 2);
+    assert!(results
+        .iter()
+        .all(|r| matches!(r.match_type, FileMatchType::Path)));
+
+    let results = graph.search_files("misc", false);
+    assert_eq!(results[0].file_path, "/root/graph/misc.rs");
+    assert!(matches!(results[0].match_type, FileMatchType::Name));
+}
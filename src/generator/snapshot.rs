@@ -0,0 +1,219 @@
+//! JSON snapshots of the generator state, so that a crawled call graph can be re-rendered or
+//! shared without querying the language server again.
+
+use {
+    super::{FileOutline, GraphGenerator, SymbolLocation},
+    crate::lsp_types::{
+        CallHierarchyIncomingCall, CallHierarchyOutgoingCall, DocumentSymbol, SymbolKind,
+    },
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    std::{collections::HashMap, fmt, path::PathBuf},
+};
+
+/// Schema version of the snapshots written by [`GraphGenerator::to_snapshot`], bumped on every
+/// schema change.
+///
+/// - 1: files, calls, interface implementations and highlights
+/// - 2: adds `subtypes` and `references`
+/// - 3: symbol kinds are the LSP numbers, whichever numbering the build uses
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+/// Oldest schema version [`GraphGenerator::from_snapshot`] can read. The fields added since
+/// are empty when missing.
+const OLDEST_SNAPSHOT_VERSION: u32 = 1;
+
+/// Oldest schema version whose symbol kinds are the LSP numbers. Older snapshots are read with
+/// the numbering of this build.
+const LSP_KINDS_VERSION: u32 = 3;
+
+/// What to add to the symbol kinds of this build to get the LSP numbers, which start from 1.
+/// The VS Code API numbers the kinds from 0.
+pub(super) const LSP_KIND_OFFSET: i64 = 1 - SymbolKind::File as i64;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Json(serde_json::Error),
+    /// The snapshot was written with a schema version that cannot be read.
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Json(e) => write!(f, "invalid snapshot: {}", e),
+            SnapshotError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported snapshot version {}, expected {} to {}",
+                version, OLDEST_SNAPSHOT_VERSION, SNAPSHOT_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        SnapshotError::Json(e)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileSnapshot {
+    id: u32,
+    path: String,
+    symbols: Vec<DocumentSymbol>,
+}
+
+/// Maps are stored as sorted entry lists, since JSON object keys can only be strings.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    version: u32,
+    root: String,
+    lang: String,
+    /// Id of the next file added after restoring, so that the ids of removed files are not reused
+    next_file_id: u32,
+    files: Vec<FileSnapshot>,
+    incoming_calls: Vec<(SymbolLocation, Vec<CallHierarchyIncomingCall>)>,
+    outgoing_calls: Vec<(SymbolLocation, Vec<CallHierarchyOutgoingCall>)>,
+    interfaces: Vec<(SymbolLocation, Vec<SymbolLocation>)>,
+    #[serde(default)]
+    subtypes: Vec<(SymbolLocation, Vec<SymbolLocation>)>,
+    #[serde(default)]
+    references: Vec<(SymbolLocation, Vec<SymbolLocation>)>,
+    highlights: Vec<(u32, Vec<(u32, u32)>)>,
+}
+
+#[derive(Deserialize)]
+struct SnapshotHeader {
+    version: u32,
+}
+
+/// Adds `offset` to the kinds of the symbols and call hierarchy items in `value`.
+pub(super) fn shift_kinds(value: &mut Value, offset: i64) {
+    match value {
+        Value::Object(object) => {
+            if object.contains_key("name") {
+                if let Some(kind) = object.get("kind").and_then(Value::as_i64) {
+                    object.insert("kind".to_string(), (kind + offset).into());
+                }
+            }
+            for value in object.values_mut() {
+                shift_kinds(value, offset);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                shift_kinds(value, offset);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn sorted_entries<'a, K, V, I>(map: I) -> Vec<(K, V)>
+where
+    K: Ord + Clone + 'a,
+    V: Clone + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+{
+    let mut entries = map
+        .into_iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries
+}
+
+impl GraphGenerator {
+    /// Serializes the whole generator state into a JSON snapshot.
+    pub fn to_snapshot(&self) -> String {
+        let mut files = self
+            .files
+            .iter()
+            .map(|(path, file)| FileSnapshot {
+                id: file.id,
+                path: path.clone(),
+                symbols: file.symbols.clone(),
+            })
+            .collect::<Vec<_>>();
+        files.sort_by_key(|file| file.id);
+
+        let highlights = self
+            .highlights
+            .iter()
+            .map(|(id, cells)| {
+                let mut cells = cells.iter().copied().collect::<Vec<_>>();
+                cells.sort();
+                (*id, cells)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let snapshot = Snapshot {
+            version: SNAPSHOT_VERSION,
+            root: self.root.clone(),
+            lang: self.lang_name.clone(),
+            next_file_id: self.next_file_id,
+            files,
+            incoming_calls: sorted_entries(&self.incoming_calls),
+            outgoing_calls: sorted_entries(&self.outgoing_calls),
+            interfaces: sorted_entries(&self.interfaces),
+            subtypes: sorted_entries(&self.subtypes),
+            references: sorted_entries(&self.references),
+            highlights: sorted_entries(&highlights),
+        };
+
+        if LSP_KIND_OFFSET == 0 {
+            return serde_json::to_string(&snapshot)
+                .expect("snapshot serialization should not fail");
+        }
+
+        let mut value =
+            serde_json::to_value(&snapshot).expect("snapshot serialization should not fail");
+        shift_kinds(&mut value, LSP_KIND_OFFSET);
+        value.to_string()
+    }
+
+    /// Restores a generator from a snapshot written by [`GraphGenerator::to_snapshot`].
+    pub fn from_snapshot(json: &str) -> Result<GraphGenerator, SnapshotError> {
+        let mut value = serde_json::from_str::<Value>(json)?;
+        let header = SnapshotHeader::deserialize(&value)?;
+        if !(OLDEST_SNAPSHOT_VERSION..=SNAPSHOT_VERSION).contains(&header.version) {
+            return Err(SnapshotError::UnsupportedVersion(header.version));
+        }
+
+        if header.version >= LSP_KINDS_VERSION && LSP_KIND_OFFSET != 0 {
+            shift_kinds(&mut value, -LSP_KIND_OFFSET);
+        }
+        let snapshot = serde_json::from_value::<Snapshot>(value)?;
+
+        let mut generator = GraphGenerator::new(snapshot.root, &snapshot.lang);
+        generator.next_file_id = snapshot.next_file_id;
+        generator.files = snapshot
+            .files
+            .into_iter()
+            .map(|file| {
+                let outline = FileOutline {
+                    id: file.id,
+                    path: PathBuf::from(&file.path),
+                    symbols: file.symbols,
+                };
+                (file.path, outline)
+            })
+            .collect();
+        generator.incoming_calls = snapshot.incoming_calls.into_iter().collect();
+        generator.outgoing_calls = snapshot.outgoing_calls.into_iter().collect();
+        generator.interfaces = snapshot.interfaces.into_iter().collect();
+        generator.subtypes = snapshot.subtypes.into_iter().collect();
+        generator.references = snapshot.references.into_iter().collect();
+        generator.highlights = snapshot
+            .highlights
+            .into_iter()
+            .map(|(id, cells)| (id, cells.into_iter().collect()))
+            .collect();
+
+        Ok(generator)
+    }
+}
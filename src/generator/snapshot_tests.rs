@@ -0,0 +1,172 @@
+use {
+    super::{
+        snapshot::{shift_kinds, LSP_KIND_OFFSET},
+        test_support::{func, incoming, location, outgoing, spanning, symbol},
+        GraphGenerator, SnapshotError, SNAPSHOT_VERSION,
+    },
+    crate::lsp_types::SymbolKind,
+};
+
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "Go");
+
+    let main = spanning(func("main", 0), 2);
+    let run = spanning(func("run", 3), 5);
+    let runner = spanning(symbol("Runner", SymbolKind::Interface, 0), 2);
+    let task = spanning(symbol("Task", SymbolKind::Struct, 3), 5);
+
+    generator.add_file("/root/main.go".to_string(), vec![main.clone(), run.clone()]);
+    generator.add_file(
+        "/root/task.go".to_string(),
+        vec![runner.clone(), task.clone()],
+    );
+
+    generator.add_incoming_calls(
+        "/root/main.go".to_string(),
+        run.selection_range.start,
+        vec![incoming("/root/main.go", &main, &[])],
+    );
+    generator.add_outgoing_calls(
+        "/root/main.go".to_string(),
+        main.selection_range.start,
+        vec![outgoing("/root/main.go", &run, &[])],
+    );
+    generator.add_interface_implementations(
+        "/root/task.go".to_string(),
+        runner.selection_range.start,
+        vec![location("/root/task.go", &task)],
+    );
+    generator.highlight("/root/main.go".to_string(), run.selection_range.start);
+
+    generator
+}
+
+/// dot源码中的边（集群的顺序不固定，因此只比较边）
+fn dot_edges(source: &str) -> Vec<&str> {
+    let mut edges = source
+        .lines()
+        .filter(|line| line.contains("->"))
+        .collect::<Vec<_>>();
+    edges.sort();
+    edges
+}
+
+#[test]
+fn snapshot_round_trip() {
+    let generator = sample_generator();
+    let snapshot = generator.to_snapshot();
+
+    let restored = GraphGenerator::from_snapshot(&snapshot).expect("快照应该能够被恢复");
+
+    assert_eq!(
+        restored.to_snapshot(),
+        snapshot,
+        "恢复后的状态应该与原状态一致"
+    );
+    assert_eq!(
+        dot_edges(&restored.generate_dot_source()),
+        dot_edges(&generator.generate_dot_source()),
+        "恢复后生成的dot源码应该包含相同的边"
+    );
+    assert_eq!(
+        restored.generate_graph().relations().len(),
+        generator.generate_graph().relations().len()
+    );
+
+    // 语言设置也应该被恢复
+    assert!(restored.should_filter_out_file("/root/main_test.go"));
+
+    // 新添加的文件不应该与已有的文件ID冲突
+    let mut restored = restored;
+    restored.add_file("/root/util.go".to_string(), vec![]);
+    assert_eq!(restored.files["/root/util.go"].id, 3);
+}
+
+#[test]
+fn snapshot_is_versioned() {
+    let snapshot = sample_generator().to_snapshot();
+    let value = serde_json::from_str::<serde_json::Value>(&snapshot).unwrap();
+    assert_eq!(value["version"], SNAPSHOT_VERSION);
+
+    let newer = snapshot.replacen(
+        &format!(r#""version":{}"#, SNAPSHOT_VERSION),
+        &format!(r#""version":{}"#, SNAPSHOT_VERSION + 1),
+        1,
+    );
+    assert!(matches!(
+        GraphGenerator::from_snapshot(&newer),
+        Err(SnapshotError::UnsupportedVersion(v)) if v == SNAPSHOT_VERSION + 1
+    ));
+}
+
+#[test]
+fn version_1_snapshot() {
+    let generator = sample_generator();
+    let mut value = serde_json::from_str::<serde_json::Value>(&generator.to_snapshot()).unwrap();
+
+    // 版本1的快照没有 subtypes 和 references，符号类型使用编译时的编号
+    value["version"] = 1.into();
+    shift_kinds(&mut value, -LSP_KIND_OFFSET);
+    let object = value.as_object_mut().unwrap();
+    object.remove("subtypes");
+    object.remove("references");
+
+    let restored =
+        GraphGenerator::from_snapshot(&value.to_string()).expect("应该能读取版本1的快照");
+    assert_eq!(
+        dot_edges(&restored.generate_dot_source()),
+        dot_edges(&generator.generate_dot_source())
+    );
+
+    let value = serde_json::from_str::<serde_json::Value>(&restored.to_snapshot()).unwrap();
+    assert_eq!(value["version"], SNAPSHOT_VERSION, "重新保存时使用当前版本");
+
+    assert!(matches!(
+        GraphGenerator::from_snapshot(&generator.to_snapshot().replacen(
+            &format!(r#""version":{}"#, SNAPSHOT_VERSION),
+            r#""version":0"#,
+            1,
+        )),
+        Err(SnapshotError::UnsupportedVersion(0))
+    ));
+}
+
+#[test]
+fn invalid_snapshot() {
+    assert!(matches!(
+        GraphGenerator::from_snapshot("{}"),
+        Err(SnapshotError::Json(_))
+    ));
+    assert!(matches!(
+        GraphGenerator::from_snapshot(r#"{"version":1}"#),
+        Err(SnapshotError::Json(_))
+    ));
+}
+
+#[test]
+fn symbol_kinds_are_lsp_numbers() {
+    // LSP 中 Function 为 12，Struct 为 23，Method 为 6，与编译时的特性无关
+    let json = r#"{"version":3,"root":"/root","lang":"Go","nextFileId":2,
+        "files":[{"id":1,"path":"/root/main.go","symbols":[
+            {"name":"main","kind":12,"range":{"start":{"line":0,"character":0},"end":{"line":2,"character":1}},"selectionRange":{"start":{"line":0,"character":5},"end":{"line":0,"character":9}},"children":[]},
+            {"name":"T","kind":23,"range":{"start":{"line":3,"character":0},"end":{"line":5,"character":1}},"selectionRange":{"start":{"line":3,"character":5},"end":{"line":3,"character":6}},"children":[]}]}],
+        "incomingCalls":[[{"path":"/root/main.go","line":3,"character":5},[{"from":
+            {"name":"run","kind":6,"uri":"file:///root/main.go","range":{"start":{"line":0,"character":0},"end":{"line":2,"character":1}},"selectionRange":{"start":{"line":0,"character":5},"end":{"line":0,"character":9}}},
+            "fromRanges":[]}]]],
+        "outgoingCalls":[],"interfaces":[],"subtypes":[],"references":[],"highlights":[]}"#;
+
+    let generator = GraphGenerator::from_snapshot(json).expect("快照应该能够被恢复");
+    let symbols = &generator.files["/root/main.go"].symbols;
+    assert_eq!(symbols[0].kind, SymbolKind::Function);
+    assert_eq!(symbols[1].kind, SymbolKind::Struct);
+    assert_eq!(
+        generator.incoming_calls.values().next().unwrap()[0]
+            .from
+            .kind,
+        SymbolKind::Method
+    );
+
+    let restored = serde_json::from_str::<serde_json::Value>(&generator.to_snapshot()).unwrap();
+    let expected = serde_json::from_str::<serde_json::Value>(json).unwrap();
+    assert_eq!(restored, expected, "重新保存的快照应该与原快照一致");
+}
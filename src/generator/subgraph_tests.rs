@@ -0,0 +1,117 @@
+use {
+    super::{
+        test_support::{func, incoming, pos, symbol},
+        GraphGenerator,
+    },
+    crate::{
+        graph_model::Graph,
+        lsp_types::{DocumentSymbol, SymbolKind},
+    },
+    std::collections::HashSet,
+};
+
+const A: &str = "/root/a.rs";
+const B: &str = "/root/b.rs";
+
+/// a.rs: main, S { m, n }；b.rs: f, g
+/// main -> m -> f，n -> g，f -> g
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let (m, n) = (
+        symbol("m", SymbolKind::Method, 6),
+        symbol("n", SymbolKind::Method, 7),
+    );
+    let main = func("main", 0);
+    let s = DocumentSymbol {
+        children: vec![m.clone(), n.clone()],
+        ..symbol("S", SymbolKind::Struct, 5)
+    };
+    let (f, g) = (func("f", 0), func("g", 5));
+    generator.add_file(A.to_string(), vec![main.clone(), s]);
+    generator.add_file(B.to_string(), vec![f.clone(), g.clone()]);
+
+    for (callee_path, callee, callers) in [
+        (A, &m, vec![(A, &main)]),
+        (B, &f, vec![(A, &m)]),
+        (B, &g, vec![(A, &n), (B, &f)]),
+    ] {
+        generator.add_incoming_calls(
+            callee_path.to_string(),
+            callee.selection_range.start,
+            callers
+                .into_iter()
+                .map(|(path, caller)| incoming(path, caller, &[caller.range.start.line]))
+                .collect(),
+        );
+    }
+
+    generator
+}
+
+fn relations(graph: &Graph) -> HashSet<(u32, u32, u32, u32)> {
+    graph
+        .relations()
+        .iter()
+        .map(|r| (r.from.file_id, r.from.line, r.to.file_id, r.to.line))
+        .collect()
+}
+
+#[test]
+fn induced_subgraph() {
+    let graph = sample_generator().generate_graph();
+
+    let sub = graph.induced(&HashSet::from([pos(1, 6), pos(2, 0), pos(2, 5)]));
+    assert_eq!(
+        sub.files().iter().map(|f| f.id).collect::<Vec<_>>(),
+        vec![1, 2],
+        "文件 id 应该保持不变"
+    );
+
+    let a = &sub.files()[0];
+    assert_eq!(a.symbols.len(), 1, "main 应该被去掉");
+    assert_eq!(a.symbols[0].name, "S", "保留子符号的父符号");
+    assert_eq!(a.symbols[0].children.len(), 1);
+    assert_eq!(a.symbols[0].children[0].name, "m");
+
+    assert_eq!(
+        relations(&sub),
+        HashSet::from([(1, 6, 2, 0), (2, 0, 2, 5)]),
+        "只保留两端都被保留的关系"
+    );
+    assert_eq!(sub.get_symbol(pos(1, 6)).unwrap().name, "m");
+    assert!(sub.get_symbol(pos(1, 0)).is_none());
+
+    let b_only = graph.subgraph(|file, _| file.id == 2);
+    assert_eq!(b_only.files().len(), 1);
+    assert_eq!(b_only.files()[0].id, 2);
+    assert_eq!(relations(&b_only), HashSet::from([(2, 0, 2, 5)]));
+
+    assert!(graph.induced(&HashSet::new()).files().is_empty());
+}
+
+#[test]
+fn render_any_graph() {
+    let generator = sample_generator();
+    let graph = generator.generate_graph();
+
+    let dot = generator.render_dot(&graph);
+    for edge in [
+        r#"1:"0_3" -> 1:"6_3""#,
+        r#"1:"6_3" -> 2:"0_3""#,
+        r#"1:"7_3" -> 2:"5_3""#,
+        r#"2:"0_3" -> 2:"5_3""#,
+    ] {
+        assert!(dot.contains(edge), "缺少 {}:\n{}", edge, dot);
+    }
+
+    let sub = graph.induced(&HashSet::from([pos(1, 7), pos(2, 5)]));
+    let dot = generator.render_dot(&sub);
+    assert!(dot.contains(r#"1:"7_3" -> 2:"5_3""#), "{}", dot);
+    assert!(!dot.contains("main"), "{}", dot);
+    assert!(!dot.contains(r#"2:"0_3""#), "f 不在子图中:\n{}", dot);
+
+    let mermaid = generator.render_mermaid(&sub);
+    assert!(mermaid.contains("1_7_3 --> 2_5_3"), "{}", mermaid);
+    assert!(!mermaid.contains("1_6_3"), "m 不在子图中:\n{}", mermaid);
+}
@@ -0,0 +1,158 @@
+use {
+    super::{
+        test_support::{func, incoming},
+        GraphGenerator,
+    },
+    crate::{
+        graph::{
+            layout::{layout, LayoutEdge, LayoutNode, Point, Port, Spacing},
+            svg::EdgeRouting,
+        },
+        graph_model::Granularity,
+    },
+};
+
+const A: &str = "/root/src/a.rs";
+const B: &str = "/root/lib/b.rs";
+
+const SPACING: Spacing = Spacing {
+    rank: 100.0,
+    node: 20.0,
+    edge: 10.0,
+    cluster_top: 30.0,
+    cluster_bottom: 10.0,
+};
+
+fn node(height: f64) -> LayoutNode {
+    LayoutNode {
+        width: 50.0,
+        height,
+        clusters: vec![],
+    }
+}
+
+fn edge(from: usize, to: usize, from_y: f64, to_y: f64) -> LayoutEdge {
+    let port = |y| Port {
+        y,
+        left: 0.0,
+        right: 50.0,
+    };
+    LayoutEdge {
+        from,
+        to,
+        from_port: port(from_y),
+        to_port: port(to_y),
+    }
+}
+
+/// src/a.rs: main, helper；lib/b.rs: f
+/// main -> f -> helper，main -> helper
+fn sample_generator() -> GraphGenerator {
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let (main, helper, f) = (func("main", 0), func("helper", 5), func("f", 0));
+    generator.add_file(A.to_string(), vec![main.clone(), helper.clone()]);
+    generator.add_file(B.to_string(), vec![f.clone()]);
+
+    generator.add_incoming_calls(
+        B.to_string(),
+        f.selection_range.start,
+        vec![incoming(A, &main, &[0])],
+    );
+    generator.add_incoming_calls(
+        A.to_string(),
+        helper.selection_range.start,
+        vec![incoming(B, &f, &[0]), incoming(A, &main, &[0])],
+    );
+
+    generator
+}
+
+#[test]
+fn layered_layout() {
+    // 0 -> 1 -> 2 -> 0 是一个环，0 -> 3 与 1 在同一层
+    let nodes = vec![node(60.0), node(40.0), node(40.0), node(80.0)];
+    let edges = vec![
+        edge(0, 1, 10.0, 20.0),
+        edge(1, 2, 20.0, 20.0),
+        edge(2, 0, 30.0, 50.0),
+        edge(0, 3, 40.0, 40.0),
+    ];
+    let result = layout(&nodes, &edges, &SPACING);
+    let at = |i: usize| result.nodes[i];
+
+    assert!(at(0).x < at(1).x && at(1).x < at(2).x, "{:?}", result.nodes);
+    assert_eq!(at(1).x, at(3).x, "1 和 3 应该在同一层");
+    let (upper, lower) = match at(1).y < at(3).y {
+        true => ((1, 40.0), (3, 80.0)),
+        false => ((3, 80.0), (1, 40.0)),
+    };
+    assert!(
+        at(upper.0).y + upper.1 + SPACING.node <= at(lower.0).y + 1e-6,
+        "同一层的节点不能重叠: {:?}",
+        result.nodes
+    );
+
+    // 边从源端口出发，到达目标端口，被反转的边 2 -> 0 从 2 的左侧出发，到达 0 的右侧
+    for (i, (from, to)) in [
+        ((0, 50.0, 10.0), (1, 0.0, 20.0)),
+        ((1, 50.0, 20.0), (2, 0.0, 20.0)),
+        ((2, 0.0, 30.0), (0, 50.0, 50.0)),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let points = &result.edges[i];
+        let port = |(node, x, y): (usize, f64, f64)| Point {
+            x: at(node).x + x,
+            y: at(node).y + y,
+        };
+        assert_eq!(points.first(), Some(&port(from)), "边 {}: {:?}", i, points);
+        assert_eq!(points.last(), Some(&port(to)), "边 {}: {:?}", i, points);
+    }
+
+    // 跨越两层的边 2 -> 0 在中间一层经过一个虚拟节点
+    assert_eq!(result.edges[2].len(), 4);
+}
+
+#[test]
+fn svg_structure() {
+    let generator = sample_generator();
+    let svg = generator.generate_svg(EdgeRouting::Spline);
+
+    assert!(svg.starts_with("<svg "), "{}", svg);
+    assert!(svg.contains(r#"<g id="graph0" class="graph""#), "{}", svg);
+    assert_eq!(svg.matches("<g ").count(), svg.matches("</g>").count());
+
+    for element in [
+        r#"<g id="2" class="node" data-path="/root/src/a.rs">"#,
+        r#"<g id="1" class="node" data-path="/root/lib/b.rs">"#,
+        r#"<g id="2:0_3" class="cell function clickable" data-kind="12">"#,
+        r#"<g id="cluster_src" class="cluster">"#,
+        r#"<g id="cluster_lib" class="cluster">"#,
+        r#"<g id="2:0_3 -> 1:0_3" class="edge" data-from="2:0_3" data-to="1:0_3" data-callsites="0:8">"#,
+        r#"<g id="1:0_3 -> 2:5_3" class="edge""#,
+        r#"<g id="2:0_3 -> 2:5_3" class="edge""#,
+    ] {
+        assert!(svg.contains(element), "缺少 {}:\n{}", element, svg);
+    }
+
+    let orthogonal = generator.generate_svg(EdgeRouting::Orthogonal);
+    assert!(svg.contains(" C"), "{}", svg);
+    assert!(!orthogonal.contains(" C"), "{}", orthogonal);
+
+    let mut generator = generator;
+    generator.set_granularity(Granularity::File);
+    let svg = generator.generate_svg(EdgeRouting::Spline);
+    assert!(!svg.contains(r#"class="cell"#), "{}", svg);
+    assert!(
+        svg.contains(r#"<g id="1:0_0 -> 2:0_0" class="edge""#),
+        "{}",
+        svg
+    );
+    assert!(
+        svg.contains(r#"<g id="2:0_0 -> 1:0_0" class="edge""#),
+        "{}",
+        svg
+    );
+}
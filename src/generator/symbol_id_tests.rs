@@ -0,0 +1,163 @@
+use {
+    super::{
+        test_support::{func, incoming, symbol},
+        GraphGenerator,
+    },
+    crate::{
+        graph::{dot::DotOptions, svg::EdgeRouting},
+        graph_model::{Graph, Symbol, SymbolId},
+        lsp_types::{DocumentSymbol, SymbolKind},
+    },
+};
+
+fn method(detail: Option<&str>, name: &str, line: u32) -> DocumentSymbol {
+    DocumentSymbol {
+        detail: detail.map(str::to_string),
+        ..symbol(name, SymbolKind::Method, line)
+    }
+}
+
+/// `root`/src/a.rs：main 调用 S::new，S 有构造函数 new、两个细节不同的方法 new，以及两个完全相同的方法 dup。
+/// 所有符号都从第 `offset` 行开始。
+fn sample_generator(root: &str, offset: u32) -> GraphGenerator {
+    let path = format!("{}/src/a.rs", root);
+    let mut generator = GraphGenerator::new(root.to_string(), "");
+
+    let main = func("main", offset);
+    let new = symbol("new", SymbolKind::Constructor, offset + 11);
+    let s = DocumentSymbol {
+        children: vec![
+            new.clone(),
+            method(Some("(x: u32)"), "new", offset + 12),
+            method(Some("(x: &str)"), "new", offset + 13),
+            method(None, "dup", offset + 14),
+            method(None, "dup", offset + 15),
+        ],
+        ..symbol("S", SymbolKind::Struct, offset + 10)
+    };
+    generator.add_file(path.clone(), vec![main.clone(), s]);
+
+    generator.add_incoming_calls(
+        path.clone(),
+        new.selection_range.start,
+        vec![incoming(&path, &main, &[offset])],
+    );
+
+    generator
+}
+
+/// 按文件中的顺序列出所有符号的行号和id
+fn ids(graph: &Graph) -> Vec<(u32, SymbolId)> {
+    fn collect(symbols: &[Symbol], ids: &mut Vec<(u32, SymbolId)>) {
+        for symbol in symbols {
+            ids.push((symbol.global_position.line, symbol.id.clone()));
+            collect(&symbol.children, ids);
+        }
+    }
+
+    let mut ids = vec![];
+    for file in graph.files() {
+        collect(&file.symbols, &mut ids);
+    }
+    ids
+}
+
+#[test]
+fn stable_when_lines_shift() {
+    let before = ids(&sample_generator("/root", 0).generate_graph());
+    let after = ids(&sample_generator("/root", 3).generate_graph());
+    assert_eq!(before.len(), 7);
+
+    for ((old_line, old_id), (new_line, new_id)) in before.iter().zip(&after) {
+        assert_eq!(old_line + 3, *new_line);
+        assert_eq!(old_id, new_id, "符号下移后id不应改变");
+    }
+
+    // id只依赖相对于根目录的路径
+    let moved = ids(&sample_generator("/elsewhere", 0).generate_graph());
+    assert_eq!(before, moved);
+}
+
+#[test]
+fn overloads_are_distinct() {
+    let ids = ids(&sample_generator("/root", 0).generate_graph());
+
+    let mut distinct = ids.iter().map(|(_, id)| id.clone()).collect::<Vec<_>>();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(
+        distinct.len(),
+        ids.len(),
+        "同名的符号应该有不同的id: {:?}",
+        ids
+    );
+
+    for (_, id) in &ids {
+        assert_eq!(id.as_str().len(), 16);
+        assert!(id.as_str().chars().all(|c| c.is_ascii_hexdigit()));
+    }
+}
+
+#[test]
+fn lookups() {
+    let graph = sample_generator("/root", 0).generate_graph();
+
+    for (_, id) in ids(&graph) {
+        let position = graph.symbol_position(&id).expect("缺少符号");
+        assert_eq!(graph.symbol_id(position), Some(&id));
+    }
+    assert_eq!(
+        graph.symbol_position(&SymbolId::from("missing".to_string())),
+        None
+    );
+
+    let json = serde_json::to_value(&graph).unwrap();
+    let main = &json["files"][0]["symbols"][0];
+    assert_eq!(main["name"], "main");
+    assert_eq!(main["id"], ids(&graph)[0].1.as_str());
+}
+
+#[test]
+fn rendered_ids() {
+    let mut generator = sample_generator("/root", 0);
+    let graph = generator.generate_graph();
+    let ids = ids(&graph);
+    let (main, new) = (ids[0].1.as_str(), ids[2].1.as_str());
+
+    // 默认使用位置作为id，webview依赖这些位置定位符号
+    let dot = generator.generate_dot_source();
+    assert!(dot.contains(r#"ID="1:0_3""#), "{}", dot);
+    assert!(dot.contains(r#"id="1:0_3 -> 1:11_3""#), "{}", dot);
+    assert!(!dot.contains(main));
+    assert!(generator
+        .generate_mermaid_source()
+        .contains("    1_0_3 --> 1_11_3\n"));
+
+    generator.set_dot_options(DotOptions {
+        symbol_ids: true,
+        ..Default::default()
+    });
+
+    let dot = generator.generate_dot_source();
+    assert!(dot.contains(&format!(r#"ID="{}""#, main)), "{}", dot);
+    assert!(
+        dot.contains(&format!(r#"id="{} -> {}""#, main, new)),
+        "{}",
+        dot
+    );
+    assert!(!dot.contains(r#"ID="1:0_3""#));
+
+    let mermaid = generator.generate_mermaid_source();
+    assert!(
+        mermaid.contains(&format!("    {} --> {}\n", main, new)),
+        "{}",
+        mermaid
+    );
+
+    let svg = generator.generate_svg(EdgeRouting::Spline);
+    assert!(
+        svg.contains(&format!(r#"<g id="{} -> {}""#, main, new)),
+        "{}",
+        svg
+    );
+}
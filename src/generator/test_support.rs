@@ -0,0 +1,141 @@
+//! Fixtures shared by the generator tests. Symbols start at `COLUMN` on their line, and the
+//! tests customize them with the struct update syntax, e.g.
+//! `DocumentSymbol { children, ..symbol("S", SymbolKind::Struct, 10) }`.
+
+use crate::{
+    graph_model::GlobalPosition,
+    lsp_types::{
+        CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, DocumentSymbol,
+        Location, Position, Range, SymbolKind, TypeHierarchyItem, Uri,
+    },
+};
+
+/// 符号名称的起始列
+pub(super) const COLUMN: u32 = 3;
+
+/// 范围只包含名称的符号
+pub(super) fn symbol(name: &str, kind: SymbolKind, line: u32) -> DocumentSymbol {
+    let range = Range {
+        start: Position {
+            line,
+            character: COLUMN,
+        },
+        end: Position {
+            line,
+            character: COLUMN + name.len() as u32,
+        },
+    };
+    DocumentSymbol {
+        name: name.to_string(),
+        detail: None,
+        kind,
+        tags: None,
+        range,
+        selection_range: range,
+        children: vec![],
+    }
+}
+
+pub(super) fn func(name: &str, line: u32) -> DocumentSymbol {
+    symbol(name, SymbolKind::Function, line)
+}
+
+/// 范围从符号所在行的行首到 `end_line` 行，像有函数体的符号一样
+pub(super) fn spanning(symbol: DocumentSymbol, end_line: u32) -> DocumentSymbol {
+    let start = symbol.selection_range.start.line;
+    DocumentSymbol {
+        range: Range {
+            start: Position {
+                line: start,
+                character: 0,
+            },
+            end: Position {
+                line: end_line,
+                character: 1,
+            },
+        },
+        ..symbol
+    }
+}
+
+/// 第 `line` 行的调用位置
+pub(super) fn site(line: u32) -> Range {
+    Range {
+        start: Position { line, character: 8 },
+        end: Position {
+            line,
+            character: 12,
+        },
+    }
+}
+
+fn sites(lines: &[u32]) -> Vec<Range> {
+    lines.iter().map(|&line| site(line)).collect()
+}
+
+pub(super) fn item(path: &str, symbol: &DocumentSymbol) -> CallHierarchyItem {
+    CallHierarchyItem {
+        name: symbol.name.clone(),
+        kind: symbol.kind,
+        tags: None,
+        detail: None,
+        uri: Uri::from_file_path(path),
+        range: symbol.range,
+        selection_range: symbol.selection_range,
+        data: None,
+    }
+}
+
+/// `path` 中的 `caller` 在 `lines` 各行调用了被查询的符号
+pub(super) fn incoming(
+    path: &str,
+    caller: &DocumentSymbol,
+    lines: &[u32],
+) -> CallHierarchyIncomingCall {
+    CallHierarchyIncomingCall {
+        from: item(path, caller),
+        from_ranges: sites(lines),
+    }
+}
+
+/// 被查询的符号在 `lines` 各行调用了 `path` 中的 `callee`
+pub(super) fn outgoing(
+    path: &str,
+    callee: &DocumentSymbol,
+    lines: &[u32],
+) -> CallHierarchyOutgoingCall {
+    CallHierarchyOutgoingCall {
+        to: item(path, callee),
+        from_ranges: sites(lines),
+    }
+}
+
+/// 实现查询等请求返回的位置
+pub(super) fn location(path: &str, symbol: &DocumentSymbol) -> Location {
+    Location {
+        uri: Uri::from_file_path(path),
+        range: symbol.selection_range,
+    }
+}
+
+pub(super) fn type_item(path: &str, symbol: &DocumentSymbol) -> TypeHierarchyItem {
+    TypeHierarchyItem {
+        name: symbol.name.clone(),
+        kind: symbol.kind,
+        tags: None,
+        detail: None,
+        uri: Uri::from_file_path(path),
+        range: symbol.range,
+        selection_range: symbol.selection_range,
+        data: None,
+    }
+}
+
+/// 文件 `file_id` 中第 `line` 行的符号的位置
+pub(super) fn pos(file_id: u32, line: u32) -> GlobalPosition {
+    GlobalPosition {
+        file_id,
+        line,
+        character: COLUMN,
+    }
+}
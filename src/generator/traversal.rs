@@ -0,0 +1,414 @@
+//! Call hierarchy traversal, ported from the VS Code extension so that every frontend crawls
+//! the language server the same way.
+
+use {
+    super::GraphGenerator,
+    crate::lsp_types::{
+        CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, DocumentSymbol,
+        Location, Position, Range, SymbolKind,
+    },
+    enumset::{EnumSet, EnumSetType},
+    std::collections::{HashMap, HashSet, VecDeque},
+};
+
+/// Provider of the language server requests needed to crawl a call hierarchy.
+pub trait CallHierarchySource {
+    type Error;
+
+    fn document_symbols(&mut self, path: &str) -> Result<Vec<DocumentSymbol>, Self::Error>;
+
+    fn prepare(
+        &mut self,
+        path: &str,
+        position: Position,
+    ) -> Result<Vec<CallHierarchyItem>, Self::Error>;
+
+    fn incoming_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyIncomingCall>, Self::Error>;
+
+    fn outgoing_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyOutgoingCall>, Self::Error>;
+
+    fn implementations(
+        &mut self,
+        path: &str,
+        position: Position,
+    ) -> Result<Vec<Location>, Self::Error>;
+}
+
+#[derive(EnumSetType, Debug)]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+type FileFilter = Box<dyn Fn(&str) -> bool>;
+
+pub struct TraversalOptions {
+    /// Directions to follow from the visited functions, only the callers by default as in the
+    /// "Generate Call Graph" command of the VS Code extension. Its "Generate Function Call
+    /// Graph" command follows both.
+    pub directions: EnumSet<Direction>,
+    /// Maximum number of hops from the starting functions, unlimited if `None`.
+    pub max_depth: Option<u32>,
+    /// Returns true for files that should not be visited, e.g. the ones matched by `.gitignore`.
+    pub ignore: Option<FileFilter>,
+}
+
+impl Default for TraversalOptions {
+    fn default() -> Self {
+        Self {
+            directions: Direction::Incoming.into(),
+            max_depth: None,
+            ignore: None,
+        }
+    }
+}
+
+pub(super) const FUNC_KINDS: [SymbolKind; 3] = [
+    SymbolKind::Function,
+    SymbolKind::Method,
+    SymbolKind::Constructor,
+];
+
+/// Visited functions of each file, keyed by the start of their selection ranges.
+type FuncMap = HashMap<String, HashSet<(u32, u32)>>;
+
+struct VisitedFile {
+    skip: bool,
+    funcs: HashMap<(u32, u32), (Range, EnumSet<Direction>)>,
+}
+
+impl VisitedFile {
+    fn new(skip: bool) -> Self {
+        Self {
+            skip,
+            funcs: HashMap::new(),
+        }
+    }
+
+    fn visit_func(&mut self, range: Range, direction: Direction) {
+        self.funcs
+            .entry((range.start.line, range.start.character))
+            .or_insert((range, EnumSet::new()))
+            .1
+            .insert(direction);
+    }
+
+    fn has_visited_func(&self, position: Position, direction: Direction) -> bool {
+        self.funcs
+            .get(&(position.line, position.character))
+            .is_some_and(|(_, directions)| directions.contains(direction))
+    }
+
+    fn sorted_funcs(&self) -> Vec<Range> {
+        let mut funcs = self
+            .funcs
+            .values()
+            .map(|(range, _)| *range)
+            .filter(|range| range.start != range.end)
+            .collect::<Vec<_>>();
+        funcs.sort_by_key(|range| range.start);
+        funcs
+    }
+}
+
+fn range_contains(outer: &Range, inner: &Range) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+/// Keeps only the symbols enclosing the given function ranges, which should be sorted.
+fn filter_symbols(
+    mut symbols: Vec<DocumentSymbol>,
+    funcs: &[Range],
+    i: &mut usize,
+) -> Vec<DocumentSymbol> {
+    symbols.sort_by_key(|symbol| symbol.selection_range.start);
+
+    symbols
+        .into_iter()
+        .filter_map(|mut symbol| {
+            if *i >= funcs.len() || !range_contains(&symbol.range, &funcs[*i]) {
+                return None;
+            }
+
+            if symbol.selection_range == funcs[*i] {
+                *i += 1;
+                if *i == funcs.len() || !range_contains(&symbol.range, &funcs[*i]) {
+                    symbol.children = vec![];
+                    return Some(symbol);
+                }
+            }
+
+            if !symbol.children.is_empty() {
+                symbol.children = filter_symbols(symbol.children, funcs, i);
+            }
+
+            Some(symbol)
+        })
+        .collect()
+}
+
+impl GraphGenerator {
+    fn is_ignored(&self, path: &str, options: &TraversalOptions) -> bool {
+        options.ignore.as_ref().is_some_and(|ignore| ignore(path))
+            || self.should_filter_out_file(path)
+    }
+
+    /// Collects the symbols of `files` and the calls between them, the counterpart of
+    /// "Generate Call Graph" in the VS Code extension.
+    ///
+    /// Failed requests are skipped, and their errors are returned.
+    pub fn crawl_files<S: CallHierarchySource>(
+        &mut self,
+        source: &mut S,
+        files: &[String],
+        options: &TraversalOptions,
+    ) -> Vec<S::Error> {
+        let mut errors = vec![];
+
+        let mut files = files
+            .iter()
+            .filter(|path| !self.is_ignored(path, options))
+            .collect::<Vec<_>>();
+        files.sort_by_key(|path| std::cmp::Reverse(path.split('/').count()));
+
+        let mut func_map = files
+            .iter()
+            .map(|path| (path.to_string(), HashSet::new()))
+            .collect::<FuncMap>();
+
+        for path in files {
+            let symbols = match source.document_symbols(path) {
+                Ok(symbols) => symbols,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
+
+            if !self.add_file(path.clone(), symbols.clone()) {
+                continue;
+            }
+
+            let mut symbols = symbols;
+            while !symbols.is_empty() {
+                for symbol in &symbols {
+                    let start = symbol.selection_range.start;
+
+                    if FUNC_KINDS.contains(&symbol.kind) {
+                        if func_map[path].contains(&(start.line, start.character)) {
+                            continue;
+                        }
+
+                        match source.prepare(path, start) {
+                            Ok(items) => items.into_iter().for_each(|item| {
+                                self.resolve_calls_in_files(
+                                    source,
+                                    item,
+                                    &mut func_map,
+                                    options,
+                                    &mut errors,
+                                )
+                            }),
+                            Err(e) => errors.push(e),
+                        }
+                    } else if symbol.kind == SymbolKind::Interface {
+                        match source.implementations(path, start) {
+                            Ok(locations) if !locations.is_empty() => {
+                                self.add_interface_implementations(path.clone(), start, locations)
+                            }
+                            Ok(_) => {}
+                            Err(e) => errors.push(e),
+                        }
+                    }
+                }
+
+                symbols = symbols
+                    .into_iter()
+                    .flat_map(|symbol| symbol.children)
+                    .collect();
+            }
+        }
+
+        errors
+    }
+
+    fn resolve_calls_in_files<S: CallHierarchySource>(
+        &mut self,
+        source: &mut S,
+        item: CallHierarchyItem,
+        func_map: &mut FuncMap,
+        options: &TraversalOptions,
+        errors: &mut Vec<S::Error>,
+    ) {
+        let mut queue = VecDeque::from([(item, 0)]);
+
+        while let Some((item, depth)) = queue.pop_front() {
+            let start = item.selection_range.start;
+            let visited = func_map
+                .get_mut(&item.uri.path)
+                .is_some_and(|funcs| !funcs.insert((start.line, start.character)));
+            if visited {
+                continue;
+            }
+
+            if options.directions.contains(Direction::Outgoing) {
+                match source.outgoing_calls(&item) {
+                    Ok(calls) => self.add_outgoing_calls(item.uri.path.clone(), start, calls),
+                    Err(e) => errors.push(e),
+                }
+            }
+
+            if !options.directions.contains(Direction::Incoming) {
+                continue;
+            }
+
+            let calls = match source.incoming_calls(&item) {
+                Ok(calls) => calls,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
+
+            if options.max_depth.is_none_or(|max| depth < max) {
+                // only callers inside the given files are followed
+                queue.extend(
+                    calls
+                        .iter()
+                        .filter(|call| {
+                            let start = call.from.selection_range.start;
+                            func_map.get(&call.from.uri.path).is_some_and(|funcs| {
+                                !funcs.contains(&(start.line, start.character))
+                            })
+                        })
+                        .map(|call| (call.from.clone(), depth + 1)),
+                );
+            }
+
+            self.add_incoming_calls(item.uri.path, start, calls);
+        }
+    }
+
+    /// Collects the call hierarchy of the function at `position`, the counterpart of
+    /// "Generate Function Call Graph" in the VS Code extension.
+    ///
+    /// Only the symbols enclosing the visited functions are added, and the starting
+    /// functions are highlighted. Failed requests are skipped, and their errors are returned.
+    pub fn crawl_function<S: CallHierarchySource>(
+        &mut self,
+        source: &mut S,
+        path: &str,
+        position: Position,
+        options: &TraversalOptions,
+    ) -> Vec<S::Error> {
+        let mut errors = vec![];
+
+        let items = match source.prepare(path, position) {
+            Ok(items) => items,
+            Err(e) => return vec![e],
+        };
+
+        let mut files = HashMap::new();
+        for item in &items {
+            files
+                .entry(item.uri.path.clone())
+                .or_insert(VisitedFile::new(false));
+        }
+
+        for direction in options.directions {
+            let mut queue = items
+                .iter()
+                .map(|item| (item.clone(), 0))
+                .collect::<VecDeque<_>>();
+
+            while let Some((item, depth)) = queue.pop_front() {
+                let file = files.get_mut(&item.uri.path).unwrap();
+                if file.has_visited_func(item.selection_range.start, direction) {
+                    continue;
+                }
+                file.visit_func(item.selection_range, direction);
+
+                if options.max_depth.is_some_and(|max| depth >= max) {
+                    continue;
+                }
+
+                let next = match direction {
+                    Direction::Incoming => source.incoming_calls(&item).map(|calls| {
+                        let next = calls.iter().map(|call| call.from.clone()).collect();
+                        self.add_incoming_calls(
+                            item.uri.path.clone(),
+                            item.selection_range.start,
+                            calls,
+                        );
+                        next
+                    }),
+                    Direction::Outgoing => source.outgoing_calls(&item).map(|calls| {
+                        let next = calls
+                            .iter()
+                            .map(|call| call.to.clone())
+                            .filter(|to| to.uri.path.starts_with(&self.root))
+                            .collect::<Vec<_>>();
+                        self.add_outgoing_calls(
+                            item.uri.path.clone(),
+                            item.selection_range.start,
+                            calls,
+                        );
+                        next
+                    }),
+                };
+
+                let next = match next {
+                    Ok(next) => next,
+                    Err(e) => {
+                        errors.push(e);
+                        continue;
+                    }
+                };
+
+                for next_item in next {
+                    let file = files.entry(next_item.uri.path.clone()).or_insert_with(|| {
+                        VisitedFile::new(self.is_ignored(&next_item.uri.path, options))
+                    });
+
+                    if !file.skip
+                        && !file.has_visited_func(next_item.selection_range.start, direction)
+                    {
+                        queue.push_back((next_item, depth + 1));
+                    }
+                }
+            }
+        }
+
+        let mut paths = files
+            .iter()
+            .filter(|(_, file)| !file.skip)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            let symbols = match source.document_symbols(&path) {
+                Ok(symbols) => symbols,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
+
+            let funcs = files[&path].sorted_funcs();
+            self.add_file(path, filter_symbols(symbols, &funcs, &mut 0));
+        }
+
+        for item in items {
+            self.highlight(item.uri.path, item.selection_range.start);
+        }
+
+        errors
+    }
+}
@@ -0,0 +1,363 @@
+use {
+    super::{
+        test_support::{func, item, location, spanning},
+        CallHierarchySource, Direction, GraphGenerator, TraversalOptions,
+    },
+    crate::lsp_types::{
+        CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, DocumentSymbol,
+        Location, Position, SymbolKind,
+    },
+    std::collections::{HashMap, HashSet},
+};
+
+/// 内存中的调用层次数据源，记录每个请求的次数
+#[derive(Default)]
+struct MockSource {
+    symbols: HashMap<String, Vec<DocumentSymbol>>,
+    items: Vec<CallHierarchyItem>,
+    calls: Vec<(usize, usize)>,
+    implementations: HashMap<(String, u32), Vec<Location>>,
+    requests: HashMap<String, usize>,
+}
+
+impl MockSource {
+    fn add_func(&mut self, path: &str, name: &str, line: u32) -> usize {
+        let symbol = spanning(func(name, line), line + 3);
+        self.items.push(item(path, &symbol));
+        self.symbols
+            .entry(path.to_string())
+            .or_default()
+            .push(symbol);
+
+        self.items.len() - 1
+    }
+
+    fn record(&mut self, request: String) {
+        *self.requests.entry(request).or_default() += 1;
+    }
+
+    fn index_of(&self, item: &CallHierarchyItem) -> usize {
+        self.items
+            .iter()
+            .position(|i| {
+                i.uri == item.uri && i.selection_range.start == item.selection_range.start
+            })
+            .unwrap()
+    }
+}
+
+impl CallHierarchySource for MockSource {
+    type Error = String;
+
+    fn document_symbols(&mut self, path: &str) -> Result<Vec<DocumentSymbol>, String> {
+        self.record(format!("symbols {}", path));
+        self.symbols
+            .get(path)
+            .cloned()
+            .ok_or(format!("no such file: {}", path))
+    }
+
+    fn prepare(
+        &mut self,
+        path: &str,
+        position: Position,
+    ) -> Result<Vec<CallHierarchyItem>, String> {
+        Ok(self
+            .items
+            .iter()
+            .filter(|item| item.uri.path == path && item.selection_range.start == position)
+            .cloned()
+            .collect())
+    }
+
+    fn incoming_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyIncomingCall>, String> {
+        self.record(format!("incoming {}", item.name));
+        let to = self.index_of(item);
+        Ok(self
+            .calls
+            .iter()
+            .filter(|(_, callee)| *callee == to)
+            .map(|(caller, _)| CallHierarchyIncomingCall {
+                from: self.items[*caller].clone(),
+                from_ranges: vec![],
+            })
+            .collect())
+    }
+
+    fn outgoing_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyOutgoingCall>, String> {
+        self.record(format!("outgoing {}", item.name));
+        let from = self.index_of(item);
+        Ok(self
+            .calls
+            .iter()
+            .filter(|(caller, _)| *caller == from)
+            .map(|(_, callee)| CallHierarchyOutgoingCall {
+                to: self.items[*callee].clone(),
+                from_ranges: vec![],
+            })
+            .collect())
+    }
+
+    fn implementations(&mut self, path: &str, position: Position) -> Result<Vec<Location>, String> {
+        Ok(self
+            .implementations
+            .get(&(path.to_string(), position.line))
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// main -> f1 -> f2 -> f3 -> f1 (循环)，f1 -> g (被忽略的文件)，f2 -> ext (根目录之外)
+fn sample_source() -> MockSource {
+    let mut source = MockSource::default();
+
+    let main = source.add_func("/root/a.rs", "main", 0);
+    let f1 = source.add_func("/root/a.rs", "f1", 5);
+    let f2 = source.add_func("/root/b.rs", "f2", 0);
+    let f3 = source.add_func("/root/b.rs", "f3", 5);
+    source.add_func("/root/b.rs", "unused", 10);
+    let g = source.add_func("/root/ignored/c.rs", "g", 0);
+    let ext = source.add_func("/other/ext.rs", "ext", 0);
+
+    source.calls = vec![(main, f1), (f1, f2), (f2, f3), (f3, f1), (f1, g), (f2, ext)];
+    source
+}
+
+/// 从生成器的调用表中收集所有调用边（用函数名表示）
+fn call_edges(generator: &GraphGenerator, source: &MockSource) -> HashSet<(String, String)> {
+    let name_of = |path: &str, line: u32| {
+        source
+            .items
+            .iter()
+            .find(|item| item.uri.path == path && item.selection_range.start.line == line)
+            .unwrap()
+            .name
+            .clone()
+    };
+
+    let incoming = generator.incoming_calls.iter().flat_map(|(callee, calls)| {
+        calls
+            .iter()
+            .map(move |call| (call.from.name.clone(), name_of(&callee.path, callee.line)))
+    });
+    let outgoing = generator.outgoing_calls.iter().flat_map(|(caller, calls)| {
+        calls
+            .iter()
+            .map(move |call| (name_of(&caller.path, caller.line), call.to.name.clone()))
+    });
+
+    incoming.chain(outgoing).collect()
+}
+
+fn edge(from: &str, to: &str) -> (String, String) {
+    (from.to_string(), to.to_string())
+}
+
+fn ignore_dir() -> TraversalOptions {
+    TraversalOptions {
+        ignore: Some(Box::new(|path: &str| path.contains("/ignored/"))),
+        ..Default::default()
+    }
+}
+
+/// 与 VS Code 扩展的 "Generate Function Call Graph" 一样沿两个方向遍历
+fn both_directions() -> TraversalOptions {
+    TraversalOptions {
+        directions: Direction::Incoming | Direction::Outgoing,
+        ..ignore_dir()
+    }
+}
+
+#[test]
+fn crawl_function_in_both_directions() {
+    let mut source = sample_source();
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let errors = generator.crawl_function(
+        &mut source,
+        "/root/a.rs",
+        Position {
+            line: 5,
+            character: 3,
+        },
+        &both_directions(),
+    );
+    assert!(errors.is_empty(), "不应该有错误: {:?}", errors);
+
+    let edges = call_edges(&generator, &source);
+    for e in [
+        edge("main", "f1"),
+        edge("f1", "f2"),
+        edge("f2", "f3"),
+        edge("f3", "f1"),
+    ] {
+        assert!(edges.contains(&e), "应该包含调用边 {:?}", e);
+    }
+
+    // 被忽略的文件和根目录之外的文件不应该被访问
+    assert!(generator.files.contains_key("/root/a.rs"));
+    assert!(generator.files.contains_key("/root/b.rs"));
+    assert!(
+        !generator.files.contains_key("/root/ignored/c.rs"),
+        "被忽略的文件不应该被添加"
+    );
+    assert!(
+        !generator.files.contains_key("/other/ext.rs"),
+        "根目录之外的文件不应该被添加"
+    );
+    assert!(source.requests.contains_key("outgoing f1"));
+    assert!(!source.requests.contains_key("outgoing g"));
+    assert!(!source.requests.contains_key("outgoing ext"));
+
+    // 只保留访问过的函数
+    let b_symbols = &generator.files["/root/b.rs"].symbols;
+    assert_eq!(
+        b_symbols
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["f2", "f3"],
+        "未访问的函数应该被过滤掉"
+    );
+
+    // 起始函数应该被高亮
+    let a_id = generator.files["/root/a.rs"].id;
+    assert!(generator.highlights[&a_id].contains(&(5, 3)));
+}
+
+#[test]
+fn crawl_function_visits_each_function_once() {
+    let mut source = sample_source();
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    generator.crawl_function(
+        &mut source,
+        "/root/a.rs",
+        Position {
+            line: 5,
+            character: 3,
+        },
+        &both_directions(),
+    );
+
+    // 循环调用不应该导致重复请求
+    for (request, count) in &source.requests {
+        assert_eq!(*count, 1, "请求 '{}' 应该只发送一次", request);
+    }
+}
+
+#[test]
+fn crawl_function_with_depth_and_direction() {
+    let mut source = sample_source();
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let options = TraversalOptions {
+        directions: Direction::Outgoing.into(),
+        max_depth: Some(1),
+        ..ignore_dir()
+    };
+    generator.crawl_function(
+        &mut source,
+        "/root/a.rs",
+        Position {
+            line: 0,
+            character: 3,
+        },
+        &options,
+    );
+
+    let edges = call_edges(&generator, &source);
+    assert_eq!(
+        edges,
+        HashSet::from([edge("main", "f1")]),
+        "深度为1时只应该有一层调用"
+    );
+    assert!(
+        !source.requests.keys().any(|r| r.starts_with("incoming")),
+        "不应该请求incoming calls"
+    );
+    assert!(
+        !generator.files.contains_key("/root/b.rs"),
+        "超出深度的文件不应该被添加"
+    );
+
+    let a_symbols = &generator.files["/root/a.rs"].symbols;
+    assert_eq!(a_symbols.len(), 2, "深度边界上的函数也应该保留");
+}
+
+#[test]
+fn crawl_function_reports_errors() {
+    let mut source = sample_source();
+    // b.rs 的符号请求会失败
+    source.symbols.remove("/root/b.rs");
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+
+    let errors = generator.crawl_function(
+        &mut source,
+        "/root/a.rs",
+        Position {
+            line: 5,
+            character: 3,
+        },
+        &both_directions(),
+    );
+    assert_eq!(errors, vec!["no such file: /root/b.rs".to_string()]);
+    assert!(
+        generator.files.contains_key("/root/a.rs"),
+        "其他文件应该照常添加"
+    );
+}
+
+#[test]
+fn crawl_files() {
+    let mut source = sample_source();
+    let main = location("/root/a.rs", &source.symbols["/root/a.rs"][0]);
+    source
+        .implementations
+        .insert(("/root/b.rs".to_string(), 10), vec![main]);
+    source.symbols.get_mut("/root/b.rs").unwrap()[2].kind = SymbolKind::Interface;
+
+    let mut generator = GraphGenerator::new("/root".to_string(), "");
+    let files = ["/root/a.rs", "/root/b.rs", "/root/ignored/c.rs"].map(String::from);
+
+    let errors = generator.crawl_files(&mut source, &files, &ignore_dir());
+    assert!(errors.is_empty(), "不应该有错误: {:?}", errors);
+
+    assert_eq!(generator.files.len(), 2, "被忽略的文件不应该被添加");
+    let edges = call_edges(&generator, &source);
+    for e in [
+        edge("main", "f1"),
+        edge("f1", "f2"),
+        edge("f2", "f3"),
+        edge("f3", "f1"),
+    ] {
+        assert!(edges.contains(&e), "应该包含调用边 {:?}", e);
+    }
+    for (request, count) in source
+        .requests
+        .iter()
+        .filter(|(r, _)| r.starts_with("incoming"))
+    {
+        assert_eq!(*count, 1, "请求 '{}' 应该只发送一次", request);
+    }
+    // 默认只沿调用者方向遍历
+    assert!(
+        !source.requests.keys().any(|r| r.starts_with("outgoing")),
+        "默认不应该请求outgoing calls"
+    );
+
+    let graph = generator.generate_graph();
+    assert!(
+        graph
+            .relations()
+            .iter()
+            .any(|r| matches!(r.kind, crate::graph_model::RelationKind::Impl)),
+        "应该包含接口实现关系"
+    );
+}
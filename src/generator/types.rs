@@ -1,6 +1,7 @@
 use {
-    crate::lsp_types::{CallHierarchyItem, DocumentSymbol, Position},
-    std::{collections::HashMap, fmt::Display, hash::Hash, path::PathBuf},
+    crate::lsp_types::{DocumentSymbol, Position},
+    serde::{Deserialize, Serialize},
+    std::{fmt::Display, hash::Hash, path::PathBuf},
 };
 
 pub(crate) struct FileOutline {
@@ -9,7 +10,7 @@ pub(crate) struct FileOutline {
     pub symbols: Vec<DocumentSymbol>,
 }
 
-#[derive(Hash, PartialEq, Eq, Clone)]
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct SymbolLocation {
     pub path: String,
     pub line: u32,
@@ -31,23 +32,3 @@ impl Display for SymbolLocation {
         write!(f, r#""{}":"{}_{}""#, self.path, self.line, self.character)
     }
 }
-
-pub(crate) trait LocationId {
-    fn location_id(&self, files: &HashMap<String, FileOutline>) -> Option<(u32, u32, u32)>;
-}
-
-impl LocationId for SymbolLocation {
-    fn location_id(&self, files: &HashMap<String, FileOutline>) -> Option<(u32, u32, u32)> {
-        Some((files.get(&self.path)?.id, self.line, self.character))
-    }
-}
-
-impl LocationId for CallHierarchyItem {
-    fn location_id(&self, files: &HashMap<String, FileOutline>) -> Option<(u32, u32, u32)> {
-        Some((
-            files.get(&self.uri.path)?.id,
-            self.selection_range.start.line,
-            self.selection_range.start.character,
-        ))
-    }
-}
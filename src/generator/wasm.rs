@@ -1,7 +1,15 @@
 use {
-    super::GraphGenerator,
-    crate::lsp_types::{
-        CallHierarchyIncomingCall, CallHierarchyOutgoingCall, DocumentSymbol, Location, Position,
+    super::{DeadCodeOptions, GraphGenerator, Granularity},
+    crate::{
+        graph::{
+            dot::{DotOptions, EdgeWeighting, NodeMetric},
+            svg::EdgeRouting,
+        },
+        graph_model::{GlobalPosition, SearchMode, SymbolId},
+        lsp_types::{
+            CallHierarchyIncomingCall, CallHierarchyOutgoingCall, DocumentSymbol, Location, Position,
+            TypeHierarchyItem,
+        },
     },
     std::cell::RefCell,
     wasm_bindgen::prelude::*,
@@ -73,6 +81,63 @@ impl GraphGeneratorWasm {
         false // 默认不过滤，作为错误情况的回退
     }
 
+    /// Sets how edges are weighted by their call counts in the DOT output, `weighting` is one of
+    /// "off", "linear" and "log", and `node_metric` one of "off", "fan-in", "fan-out", "depth"
+    /// and "betweenness".
+    pub fn set_dot_options(
+        &self,
+        weighting: String,
+        call_count_labels: bool,
+        highlight_cycles: bool,
+        node_metric: String,
+    ) {
+        let edge_weighting = match weighting.as_str() {
+            "linear" => EdgeWeighting::Linear,
+            "log" => EdgeWeighting::Logarithmic,
+            _ => EdgeWeighting::Off,
+        };
+        let node_metric = match node_metric.as_str() {
+            "fan-in" => NodeMetric::FanIn,
+            "fan-out" => NodeMetric::FanOut,
+            "depth" => NodeMetric::Depth,
+            "betweenness" => NodeMetric::Betweenness,
+            _ => NodeMetric::Off,
+        };
+
+        match self.inner.try_borrow_mut() {
+            Ok(mut inner) => inner.set_dot_options(DotOptions {
+                edge_weighting,
+                call_count_labels,
+                highlight_cycles,
+                node_metric,
+                // the webview locates symbols by the positions in the cell ids
+                symbol_ids: false,
+            }),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for set_dot_options"));
+            }
+        }
+    }
+
+    /// Sets the level of detail of the generated graphs, `granularity` is one of "symbol",
+    /// "file" and "directory".
+    pub fn set_granularity(&self, granularity: String) {
+        let granularity = match granularity.as_str() {
+            "file" => Granularity::File,
+            "directory" => Granularity::Directory,
+            _ => Granularity::Symbol,
+        };
+
+        match self.inner.try_borrow_mut() {
+            Ok(mut inner) => inner.set_granularity(granularity),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for set_granularity"));
+            }
+        }
+    }
+
     pub fn add_file(&self, file_path: String, symbols: JsValue) -> bool {
         // 使用match处理反序列化可能的错误，避免unwrap导致的panic
         let symbols = match serde_wasm_bindgen::from_value::<Vec<DocumentSymbol>>(symbols) {
@@ -109,6 +174,37 @@ impl GraphGeneratorWasm {
         false // 如果所有尝试都失败，返回false
     }
 
+    pub fn remove_file(&self, file_path: String) -> bool {
+        match self.inner.try_borrow_mut() {
+            Ok(mut inner) => inner.remove_file(&file_path),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for remove_file"));
+                false
+            }
+        }
+    }
+
+    pub fn update_file(&self, file_path: String, symbols: JsValue) -> bool {
+        let symbols = match serde_wasm_bindgen::from_value::<Vec<DocumentSymbol>>(symbols) {
+            Ok(s) => s,
+            Err(err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to deserialize symbols: {:?}", err)));
+                return false;
+            }
+        };
+
+        match self.inner.try_borrow_mut() {
+            Ok(mut inner) => inner.update_file(file_path, symbols),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for update_file"));
+                false
+            }
+        }
+    }
+
     pub fn add_incoming_calls(&self, file_path: String, position: JsValue, calls: JsValue) {
         // 使用match处理反序列化可能的错误，避免unwrap导致的panic
         let position = match serde_wasm_bindgen::from_value::<Position>(position) {
@@ -249,6 +345,62 @@ impl GraphGeneratorWasm {
         }
     }
 
+    pub fn add_references(&self, file_path: String, position: JsValue, locations: JsValue) {
+        let position = match serde_wasm_bindgen::from_value::<Position>(position) {
+            Ok(pos) => pos,
+            Err(err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to deserialize position: {:?}", err)));
+                return;
+            }
+        };
+
+        let locations = match serde_wasm_bindgen::from_value::<Vec<Location>>(locations) {
+            Ok(locations) => locations,
+            Err(err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to deserialize locations: {:?}", err)));
+                return;
+            }
+        };
+
+        match self.inner.try_borrow_mut() {
+            Ok(mut inner) => inner.add_references(file_path, position, locations),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for add_references"));
+            }
+        }
+    }
+
+    pub fn add_supertypes(&self, file_path: String, position: JsValue, items: JsValue) {
+        let Some((position, items)) = Self::type_hierarchy_args(position, items) else {
+            return;
+        };
+
+        match self.inner.try_borrow_mut() {
+            Ok(mut inner) => inner.add_supertypes(file_path, position, items),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for add_supertypes"));
+            }
+        }
+    }
+
+    pub fn add_subtypes(&self, file_path: String, position: JsValue, items: JsValue) {
+        let Some((position, items)) = Self::type_hierarchy_args(position, items) else {
+            return;
+        };
+
+        match self.inner.try_borrow_mut() {
+            Ok(mut inner) => inner.add_subtypes(file_path, position, items),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for add_subtypes"));
+            }
+        }
+    }
+
     pub fn highlight(&self, file_path: String, position: JsValue) {
         // 使用match处理反序列化可能的错误，避免unwrap导致的panic
         let position = match serde_wasm_bindgen::from_value::<Position>(position) {
@@ -334,6 +486,36 @@ impl GraphGeneratorWasm {
         String::new() // 如果所有尝试都失败，返回空字符串
     }
 
+    /// Generates a PlantUML class diagram, or a component diagram with a coarser granularity.
+    pub fn generate_plantuml_source(&self) -> String {
+        match self.inner.try_borrow() {
+            Ok(inner) => inner.generate_plantuml_source(),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for generate_plantuml_source"));
+                String::new()
+            }
+        }
+    }
+
+    /// Lays the graph out and draws it as SVG without Graphviz, `routing` is "spline" or
+    /// "orthogonal".
+    pub fn generate_svg(&self, routing: String) -> String {
+        let routing = match routing.as_str() {
+            "orthogonal" => EdgeRouting::Orthogonal,
+            _ => EdgeRouting::Spline,
+        };
+
+        match self.inner.try_borrow() {
+            Ok(inner) => inner.generate_svg(routing),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for generate_svg"));
+                String::new()
+            }
+        }
+    }
+
     pub fn generate_graph(&self) -> JsValue {
         // 尝试最多3次借用，以处理可能的临时借用冲突
         for attempt in 0..3 {
@@ -360,6 +542,258 @@ impl GraphGeneratorWasm {
         JsValue::NULL // 如果所有尝试都失败，返回NULL
     }
     
+    /// Sets the exemptions of `find_dead_code`, `entry_points` is an array of function names.
+    pub fn set_dead_code_options(
+        &self,
+        entry_points: JsValue,
+        exempt_tests: bool,
+        exempt_impls: bool,
+        exempt_exported: bool,
+    ) {
+        let entry_points = serde_wasm_bindgen::from_value::<Vec<String>>(entry_points)
+            .unwrap_or_else(|_| DeadCodeOptions::default().entry_points);
+
+        match self.inner.try_borrow_mut() {
+            Ok(mut inner) => inner.set_dead_code_options(DeadCodeOptions {
+                entry_points,
+                exempt_tests,
+                exempt_impls,
+                exempt_exported,
+            }),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for set_dead_code_options"));
+            }
+        }
+    }
+
+    /// Returns the global positions of the functions without callers, see `GraphGenerator::find_dead_code`.
+    pub fn find_dead_code(&self) -> JsValue {
+        match self.inner.try_borrow() {
+            Ok(inner) => serde_wasm_bindgen::to_value(&inner.find_dead_code()).unwrap_or(JsValue::NULL),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for find_dead_code"));
+                JsValue::NULL
+            }
+        }
+    }
+
+    /// Returns the per-symbol and per-file metrics, see `GraphGenerator::compute_metrics`.
+    pub fn compute_metrics(&self) -> JsValue {
+        match self.inner.try_borrow() {
+            Ok(inner) => serde_wasm_bindgen::to_value(&inner.compute_metrics()).unwrap_or(JsValue::NULL),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for compute_metrics"));
+                JsValue::NULL
+            }
+        }
+    }
+
+    /// Returns the cycles at the current granularity, see `GraphGenerator::find_cycles`.
+    pub fn find_cycles(&self) -> JsValue {
+        match self.inner.try_borrow() {
+            Ok(inner) => serde_wasm_bindgen::to_value(&inner.find_cycles()).unwrap_or(JsValue::NULL),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for find_cycles"));
+                JsValue::NULL
+            }
+        }
+    }
+
+    /// Returns a generator restricted to the neighborhood of `root`, see `GraphGenerator::generate_focused_graph`.
+    pub fn generate_focused_graph(
+        &self,
+        root: JsValue,
+        max_in_depth: u32,
+        max_out_depth: u32,
+    ) -> Option<GraphGeneratorWasm> {
+        let root = match serde_wasm_bindgen::from_value::<GlobalPosition>(root) {
+            Ok(pos) => pos,
+            Err(err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to deserialize global position: {:?}", err)));
+                return None;
+            }
+        };
+
+        match self.inner.try_borrow() {
+            Ok(inner) => inner
+                .generate_focused_graph(root, max_in_depth, max_out_depth)
+                .map(|focused| GraphGeneratorWasm {
+                    inner: RefCell::new(focused),
+                }),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for generate_focused_graph"));
+                None
+            }
+        }
+    }
+
+    /// Returns the call chains from `from` to `to` as arrays of global positions, see
+    /// `GraphGenerator::find_paths`.
+    pub fn find_paths(&self, from: JsValue, to: JsValue, max_paths: usize, max_len: usize) -> JsValue {
+        let (from, to) = match Self::path_ends(from, to) {
+            Some(ends) => ends,
+            None => return JsValue::NULL,
+        };
+
+        match self.inner.try_borrow() {
+            Ok(inner) => serde_wasm_bindgen::to_value(&inner.find_paths(from, to, max_paths, max_len))
+                .unwrap_or(JsValue::NULL),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for find_paths"));
+                JsValue::NULL
+            }
+        }
+    }
+
+    /// Returns a generator restricted to the call chains from `from` to `to`, see
+    /// `GraphGenerator::generate_path_graph`.
+    pub fn generate_path_graph(
+        &self,
+        from: JsValue,
+        to: JsValue,
+        max_paths: usize,
+        max_len: usize,
+    ) -> Option<GraphGeneratorWasm> {
+        let (from, to) = Self::path_ends(from, to)?;
+
+        match self.inner.try_borrow() {
+            Ok(inner) => inner
+                .generate_path_graph(from, to, max_paths, max_len)
+                .map(|paths| GraphGeneratorWasm {
+                    inner: RefCell::new(paths),
+                }),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for generate_path_graph"));
+                None
+            }
+        }
+    }
+
+    /// Serializes the generator state into a JSON snapshot, see `GraphGenerator::to_snapshot`.
+    pub fn to_snapshot(&self) -> Option<String> {
+        match self.inner.try_borrow() {
+            Ok(inner) => Some(inner.to_snapshot()),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for to_snapshot"));
+                None
+            }
+        }
+    }
+
+    /// Restores a generator from a JSON snapshot, or returns `undefined` if the snapshot is invalid.
+    pub fn from_snapshot(json: String) -> Option<GraphGeneratorWasm> {
+        match GraphGenerator::from_snapshot(&json) {
+            Ok(generator) => Some(GraphGeneratorWasm {
+                inner: RefCell::new(generator),
+            }),
+            Err(err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to restore GraphGenerator from snapshot: {}", err)));
+                None
+            }
+        }
+    }
+
+    /// Compares the graph of the snapshot `old`, e.g. taken on the base branch, with the current
+    /// graph, see `Graph::diff`. Returns `null` if the snapshot is invalid.
+    pub fn diff_snapshot(&self, old: String) -> JsValue {
+        let old = match GraphGenerator::from_snapshot(&old) {
+            Ok(generator) => generator.generate_graph(),
+            Err(_err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to restore GraphGenerator from snapshot: {}", _err)));
+                return JsValue::NULL;
+            }
+        };
+
+        match self.inner.try_borrow() {
+            Ok(inner) => {
+                let diff = old.diff(&inner.generate_graph());
+                serde_wasm_bindgen::to_value(&diff).unwrap_or(JsValue::NULL)
+            },
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for diff_snapshot"));
+                JsValue::NULL
+            }
+        }
+    }
+
+    /// Draws the current graph with the edges added since the snapshot `old` in green and the
+    /// removed ones in red, `format` is "dot" or "mermaid".
+    pub fn generate_diff_source(&self, old: String, format: String) -> String {
+        let old = match GraphGenerator::from_snapshot(&old) {
+            Ok(generator) => generator.generate_graph(),
+            Err(_err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to restore GraphGenerator from snapshot: {}", _err)));
+                return String::new();
+            }
+        };
+
+        match self.inner.try_borrow() {
+            Ok(inner) => {
+                let graph = inner.generate_graph();
+                let diff = old.diff(&graph);
+                match format.as_str() {
+                    "mermaid" => inner.render_diff_mermaid(&graph, &diff),
+                    _ => inner.render_diff_dot(&graph, &diff),
+                }
+            },
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for generate_diff_source"));
+                String::new()
+            }
+        }
+    }
+
+    /// Returns the id of the symbol at `position`, which stays the same when the symbol moves
+    /// within its file, or `undefined` if there is no symbol there.
+    pub fn symbol_id(&self, position: JsValue) -> Option<String> {
+        let position = match serde_wasm_bindgen::from_value::<GlobalPosition>(position) {
+            Ok(pos) => pos,
+            Err(_err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to deserialize position: {:?}", _err)));
+                return None;
+            }
+        };
+
+        match self.inner.try_borrow() {
+            Ok(inner) => inner.generate_graph().symbol_id(position).map(|id| id.to_string()),
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for symbol_id"));
+                None
+            }
+        }
+    }
+
+    /// Returns the current position of the symbol with id `id`, or `null` if there is none.
+    pub fn symbol_position(&self, id: String) -> JsValue {
+        match self.inner.try_borrow() {
+            Ok(inner) => match inner.generate_graph().symbol_position(&SymbolId::from(id)) {
+                Some(position) => serde_wasm_bindgen::to_value(&position).unwrap_or(JsValue::NULL),
+                None => JsValue::NULL,
+            },
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for symbol_position"));
+                JsValue::NULL
+            }
+        }
+    }
+
     pub fn search_symbols(&self, query: String, case_sensitive: bool) -> JsValue {
         match self.inner.try_borrow() {
             Ok(inner) => {
@@ -375,6 +809,54 @@ impl GraphGeneratorWasm {
         }
     }
     
+    /// Searches for symbols with `mode` one of "substring", "prefix", "regex", "fuzzy" and
+    /// "word-prefix", best matches first. Returns `null` if the regex is invalid.
+    pub fn search_symbols_with_mode(&self, query: String, mode: String, case_sensitive: bool) -> JsValue {
+        let mode = match mode.as_str() {
+            "prefix" => SearchMode::Prefix,
+            "regex" => SearchMode::Regex,
+            "fuzzy" => SearchMode::Fuzzy,
+            "word-prefix" => SearchMode::WordPrefix,
+            _ => SearchMode::Substring,
+        };
+
+        match self.inner.try_borrow() {
+            Ok(inner) => match inner.generate_graph().search_symbols_with_mode(&query, mode, case_sensitive) {
+                Ok(results) => serde_wasm_bindgen::to_value(&results).unwrap_or(JsValue::NULL),
+                Err(_err) => {
+                    #[cfg(feature = "vscode")]
+                    console::error_1(&JsValue::from_str(&format!("Failed to search symbols: {}", _err)));
+                    JsValue::NULL
+                }
+            },
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for search_symbols_with_mode"));
+                JsValue::NULL
+            }
+        }
+    }
+
+    /// Returns the part of the graph selected by `query`, such as
+    /// `kind:method file:src/net/** -callers:test_*`, or `null` if the query is invalid.
+    pub fn query_graph(&self, query: String) -> JsValue {
+        match self.inner.try_borrow() {
+            Ok(inner) => match inner.generate_graph().query(&query) {
+                Ok(graph) => serde_wasm_bindgen::to_value(&graph).unwrap_or(JsValue::NULL),
+                Err(_err) => {
+                    #[cfg(feature = "vscode")]
+                    console::error_1(&JsValue::from_str(&format!("Failed to query graph: {}", _err)));
+                    JsValue::NULL
+                }
+            },
+            Err(_) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str("Failed to borrow GraphGenerator for query_graph"));
+                JsValue::NULL
+            }
+        }
+    }
+
     pub fn search_files(&self, query: String, case_sensitive: bool) -> JsValue {
         match self.inner.try_borrow() {
             Ok(inner) => {
@@ -437,3 +919,39 @@ impl GraphGeneratorWasm {
         }
     }
 }
+
+impl GraphGeneratorWasm {
+    fn type_hierarchy_args(position: JsValue, items: JsValue) -> Option<(Position, Vec<TypeHierarchyItem>)> {
+        let position = match serde_wasm_bindgen::from_value::<Position>(position) {
+            Ok(pos) => pos,
+            Err(err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to deserialize position: {:?}", err)));
+                return None;
+            }
+        };
+
+        match serde_wasm_bindgen::from_value::<Vec<TypeHierarchyItem>>(items) {
+            Ok(items) => Some((position, items)),
+            Err(err) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to deserialize type hierarchy items: {:?}", err)));
+                None
+            }
+        }
+    }
+
+    fn path_ends(from: JsValue, to: JsValue) -> Option<(GlobalPosition, GlobalPosition)> {
+        match (
+            serde_wasm_bindgen::from_value::<GlobalPosition>(from),
+            serde_wasm_bindgen::from_value::<GlobalPosition>(to),
+        ) {
+            (Ok(from), Ok(to)) => Some((from, to)),
+            (Err(err), _) | (_, Err(err)) => {
+                #[cfg(feature = "vscode")]
+                console::error_1(&JsValue::from_str(&format!("Failed to deserialize global position: {:?}", err)));
+                None
+            }
+        }
+    }
+}
@@ -1,21 +1,25 @@
 use {
+    crate::{
+        graph_model::{cycle_edges, SymbolId},
+        lsp_types::Range,
+    },
     enumset::{EnumSet, EnumSetType},
     std::{
-        collections::HashSet,
+        collections::{btree_map::Entry, BTreeMap, HashMap, HashSet},
         hash::{Hash, Hasher},
     },
 };
 
 pub mod dot;
+pub(crate) mod layout;
+pub mod mermaid;
+pub mod plantuml;
+pub mod svg;
 
-pub trait GenerateSVG {
-    fn generate_svg(
-        &self,
-        tables: &[TableNode],
-        // nodes: &[Node],
-        edges: &[Edge],
-        subgraphs: &[Subgraph],
-    ) -> String;
+/// An output format of symbol-level graphs, which are made of a table per file, the edges
+/// between the cells of the tables, and the directories containing the files.
+pub trait Renderer {
+    fn render(&self, tables: Vec<TableNode>, edges: Vec<Edge>, subgraphs: &[Subgraph]) -> String;
 }
 
 #[derive(Debug, Clone)]
@@ -23,6 +27,68 @@ pub struct Edge {
     pub from: (u32, u32, u32),
     pub to: (u32, u32, u32),
     pub classes: EnumSet<CssClass>,
+    /// Ranges in the caller's file where the calls happen, empty for non-call edges.
+    pub call_sites: Vec<Range>,
+}
+
+impl Edge {
+    pub fn call_count(&self) -> usize {
+        self.call_sites.len()
+    }
+
+    /// Adds `CssClass::Cycle` to the edges lying on cycles, leaving out the edges marked with
+    /// `CssClass::Removed`.
+    pub fn mark_cycles(edges: &mut [Edge]) {
+        let mut edges = edges
+            .iter_mut()
+            .filter(|e| !e.classes.contains(CssClass::Removed))
+            .collect::<Vec<_>>();
+        let ends = edges.iter().map(|e| (e.from, e.to)).collect::<Vec<_>>();
+        for (edge, on_cycle) in edges.iter_mut().zip(cycle_edges(&ends)) {
+            if on_cycle {
+                edge.classes.insert(CssClass::Cycle);
+            }
+        }
+    }
+
+    /// Merges edges between the same cells, e.g. a call reported by both the incoming and
+    /// the outgoing calls, keeping the distinct call sites of all of them. A call merged with
+    /// another relation stays a call. The edges are sorted by their ends.
+    pub fn merge<I: Iterator<Item = Edge>>(edges: I) -> Vec<Edge> {
+        let kinds = CssClass::Impl | CssClass::Inherit | CssClass::Reference;
+        let mut merged: BTreeMap<_, Edge> = BTreeMap::new();
+
+        for edge in edges {
+            match merged.entry((edge.from, edge.to)) {
+                Entry::Vacant(entry) => {
+                    entry.insert(edge);
+                }
+                Entry::Occupied(mut entry) => {
+                    let existing = entry.get_mut();
+                    let is_call = |classes: EnumSet<CssClass>| (classes & kinds).is_empty();
+                    let call = is_call(existing.classes) || is_call(edge.classes);
+                    existing.classes |= edge.classes;
+                    if call {
+                        existing.classes -= kinds;
+                    }
+                    for range in edge.call_sites {
+                        if !existing.call_sites.contains(&range) {
+                            existing.call_sites.push(range);
+                        }
+                    }
+                }
+            }
+        }
+
+        merged
+            .into_values()
+            .map(|mut edge| {
+                edge.call_sites.sort_by_key(|range| range.start);
+                edge.call_sites.dedup();
+                edge
+            })
+            .collect()
+    }
 }
 
 impl Hash for Edge {
@@ -40,6 +106,35 @@ impl PartialEq for Edge {
 
 impl Eq for Edge {}
 
+/// A node standing for a whole file or directory in a collapsed graph.
+#[derive(Debug)]
+pub struct GroupNode {
+    pub id: u32,
+    pub title: String,
+    pub path: String,
+}
+
+/// Symbol-level edges between two group nodes, aggregated into one.
+#[derive(Debug)]
+pub struct GroupEdge {
+    pub from: u32,
+    pub to: u32,
+    pub classes: EnumSet<CssClass>,
+    pub count: usize,
+}
+
+impl GroupEdge {
+    /// Adds `CssClass::Cycle` to the edges lying on cycles.
+    pub fn mark_cycles(edges: &mut [GroupEdge]) {
+        let ends = edges.iter().map(|e| (e.from, e.to)).collect::<Vec<_>>();
+        for (edge, on_cycle) in edges.iter_mut().zip(cycle_edges(&ends)) {
+            if on_cycle {
+                edge.classes.insert(CssClass::Cycle);
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Cell {
     pub range_start: (u32, u32),
@@ -47,6 +142,8 @@ pub struct Cell {
     pub title: String,
     pub style: Style,
     pub symbol_kind: Option<crate::lsp_types::SymbolKind>,
+    /// Id of the symbol, used instead of the position with `DotOptions::symbol_ids`
+    pub symbol_id: Option<SymbolId>,
     pub children: Vec<Cell>,
 }
 
@@ -57,6 +154,40 @@ impl Cell {
         }
         self.children.iter_mut().for_each(|c| c.highlight(cells));
     }
+
+    pub fn color(&mut self, colors: &HashMap<(u32, u32), String>) {
+        if let Some(color) = colors.get(&self.range_start) {
+            self.style.bg_color = Some(color.clone());
+        }
+        self.children.iter_mut().for_each(|c| c.color(colors));
+    }
+
+    /// Whether the cell stands for a type, an interface, a module or a block such as an impl
+    /// block, which contain other symbols, rather than for a function or a property.
+    pub fn is_container(&self) -> bool {
+        let classes = self.style.classes;
+        // 只有Cell类但有子节点的情况（如impl块）也是容器
+        classes.contains(CssClass::Type)
+            || classes.contains(CssClass::Interface)
+            || classes.contains(CssClass::Module)
+            || (classes.contains(CssClass::Cell)
+                && !classes.contains(CssClass::Function)
+                && !classes.contains(CssClass::Method)
+                && !classes.contains(CssClass::Constructor)
+                && !classes.contains(CssClass::Property))
+    }
+
+    pub fn set_symbol_id(&mut self, ids: &HashMap<(u32, u32), SymbolId>) {
+        self.symbol_id = ids.get(&self.range_start).cloned();
+        self.children.iter_mut().for_each(|c| c.set_symbol_id(ids));
+    }
+
+    fn collect_symbol_ids(&self, table_id: u32, ids: &mut HashMap<(u32, u32, u32), SymbolId>) {
+        if let Some(id) = &self.symbol_id {
+            ids.insert((table_id, self.range_start.0, self.range_start.1), id.clone());
+        }
+        self.children.iter().for_each(|c| c.collect_symbol_ids(table_id, ids));
+    }
 }
 
 #[derive(Debug)]
@@ -71,6 +202,42 @@ impl TableNode {
     pub fn highlight_cells(&mut self, cells: &HashSet<(u32, u32)>) {
         self.sections.iter_mut().for_each(|c| c.highlight(cells));
     }
+
+    /// Sets the background colors of the cells, keyed by the start of their ranges.
+    pub fn color_cells(&mut self, colors: &HashMap<(u32, u32), String>) {
+        self.sections.iter_mut().for_each(|c| c.color(colors));
+    }
+
+    /// Sets the ids of the symbols of the cells, keyed by the start of their ranges.
+    pub fn set_symbol_ids(&mut self, ids: &HashMap<(u32, u32), SymbolId>) {
+        self.sections.iter_mut().for_each(|c| c.set_symbol_id(ids));
+    }
+
+    /// Symbol ids of the cells of `tables`, keyed by table id and start of the cell, for the
+    /// renderers to name the cells and the ends of the edges after.
+    pub(crate) fn symbol_ids(tables: &[TableNode]) -> HashMap<(u32, u32, u32), SymbolId> {
+        let mut ids = HashMap::new();
+        for table in tables {
+            table
+                .sections
+                .iter()
+                .for_each(|c| c.collect_symbol_ids(table.id, &mut ids));
+        }
+        ids
+    }
+}
+
+/// Id of the cell of table `cell.0` starting at line `cell.1` and character `cell.2`: the id of
+/// its symbol if it is in `ids`, `{table}{separator}{line}_{character}` otherwise.
+pub(crate) fn cell_id(
+    ids: &HashMap<(u32, u32, u32), SymbolId>,
+    cell: (u32, u32, u32),
+    separator: char,
+) -> String {
+    match ids.get(&cell) {
+        Some(id) => id.to_string(),
+        None => format!("{}{}{}_{}", cell.0, separator, cell.1, cell.2),
+    }
 }
 
 #[derive(Debug)]
@@ -86,6 +253,7 @@ pub struct Style {
     pub border: Option<u8>,
     pub icon: Option<char>,
     pub classes: EnumSet<CssClass>,
+    pub bg_color: Option<String>,
 }
 
 #[derive(EnumSetType, Debug)]
@@ -102,6 +270,11 @@ pub enum CssClass {
     Type,
 
     Impl,
+    Inherit,
+    Reference,
+    Cycle,
+    Added,
+    Removed,
 
     Clickable,
     Highlight,
@@ -122,6 +295,11 @@ impl CssClass {
             CssClass::Property => "property",
 
             CssClass::Impl => "impl",
+            CssClass::Inherit => "inherit",
+            CssClass::Reference => "reference",
+            CssClass::Cycle => "cycle",
+            CssClass::Added => "added",
+            CssClass::Removed => "removed",
 
             CssClass::Clickable => "clickable",
             CssClass::Highlight => "highlight",
@@ -1,8 +1,11 @@
 use {
-    super::CssClass,
-    crate::graph::{Cell, Edge, Subgraph, TableNode},
+    super::{cell_id, CssClass, Renderer},
+    crate::{
+        graph::{Cell, Edge, GroupEdge, GroupNode, Subgraph, TableNode},
+        graph_model::SymbolId,
+    },
     enumset::EnumSet,
-    std::iter,
+    std::{collections::HashMap, iter},
 };
 
 pub(crate) fn escape_html(s: &str) -> String {
@@ -14,21 +17,24 @@ pub(crate) fn escape_html(s: &str) -> String {
 const EMPTY_STRING: String = String::new();
 
 // Color scheme from crabviz-ref
-const BG_COLOR: &str = "#f5fffa";
+pub(super) const BG_COLOR: &str = "#f5fffa";
 const SELECTED_COLOR: &str = "#4fe1f4";
 
-const EDGE_COLOR: &str = "#548f9e";
+pub(super) const EDGE_COLOR: &str = "#548f9e";
 const EDGE_INCOMING_COLOR: &str = "#698b69";
 const EDGE_OUTGOING_COLOR: &str = "#008acd";
+pub(super) const EDGE_CYCLE_COLOR: &str = "#d1242f";
+pub(super) const EDGE_ADDED_COLOR: &str = "#1a7f37";
+pub(super) const EDGE_REMOVED_COLOR: &str = "#cf222e";
 const EDGE_INCOMING_OUTGOING_COLOR: &str = "#2c3e50";
 
-const CLUSTER_LABEL_BG_COLOR: &str = "#f8f9fa";
+pub(super) const CLUSTER_LABEL_BG_COLOR: &str = "#f8f9fa";
 
-const NODE_BG_COLOR: &str = "#f4f5f1";
+pub(super) const NODE_BG_COLOR: &str = "#f4f5f1";
 
 const SYMBOL_DEFAULT_BG_COLOR: &str = NODE_BG_COLOR;
-const SYMBOL_DEFAULT_BORDER_COLOR: &str = "#6c757d";
-const SYMBOL_DEFAULT_TEXT_COLOR: &str = "#363636";
+pub(super) const SYMBOL_DEFAULT_BORDER_COLOR: &str = "#6c757d";
+pub(super) const SYMBOL_DEFAULT_TEXT_COLOR: &str = "#363636";
 
 const INTERFACE_BG_COLOR: &str = "#fff8dc";
 const INTERFACE_BORDER_COLOR: &str = "#a69348";
@@ -42,7 +48,7 @@ const CONSTRUCTOR_BORDER_COLOR: &str = "#a66e3c";
 const METHOD_BG_COLOR: &str = "#fff8c5";
 const METHOD_BORDER_COLOR: &str = "#d4a72c";
 
-const FUNCTION_BG_COLOR: &str = "#e8f5e8";
+pub(super) const FUNCTION_BG_COLOR: &str = "#e8f5e8";
 const FUNCTION_BORDER_COLOR: &str = "#7cb342";
 
 const STRUCT_BG_COLOR: &str = "#ddf4ff";
@@ -51,6 +57,100 @@ const STRUCT_BORDER_COLOR: &str = "#54aeff";
 const TYPE_ICON_COLOR: &str = "#8969da";
 const PROPERTY_ICON_COLOR: &str = "#5f9348";
 
+/// Background colors of the symbols with the lowest and the highest metric values.
+const METRIC_LOW_COLOR: (u8, u8, u8) = (0xff, 0xf5, 0xf0);
+const METRIC_HIGH_COLOR: (u8, u8, u8) = (0xcb, 0x18, 0x1d);
+
+/// Graphviz `penwidth` of the edges with the most calls.
+pub(super) const MAX_PENWIDTH: f64 = 8.0;
+
+/// How call counts are turned into edge weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeWeighting {
+    #[default]
+    Off,
+    Linear,
+    Logarithmic,
+}
+
+impl EdgeWeighting {
+    /// Scale factor of an edge with `count` calls, 1 for a single call.
+    pub(super) fn scale(self, count: usize) -> Option<f64> {
+        let count = count as f64;
+        match self {
+            EdgeWeighting::Off => None,
+            EdgeWeighting::Linear => Some(count),
+            EdgeWeighting::Logarithmic => Some(1.0 + count.log2()),
+        }
+    }
+}
+
+/// Symbol metric shown as the background color of the symbols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeMetric {
+    #[default]
+    Off,
+    FanIn,
+    FanOut,
+    Depth,
+    Betweenness,
+}
+
+/// Color of the edges added or removed in a diff, or lying on cycles, `None` for the default
+/// color.
+pub(super) fn edge_color(classes: EnumSet<CssClass>) -> Option<&'static str> {
+    if classes.contains(CssClass::Added) {
+        Some(EDGE_ADDED_COLOR)
+    } else if classes.contains(CssClass::Removed) {
+        Some(EDGE_REMOVED_COLOR)
+    } else if classes.contains(CssClass::Cycle) {
+        Some(EDGE_CYCLE_COLOR)
+    } else {
+        None
+    }
+}
+
+/// Background color of a symbol whose metric is `ratio` of the highest value.
+pub(crate) fn metric_color(ratio: f64) -> String {
+    let ratio = ratio.clamp(0.0, 1.0);
+    let mix = |low: u8, high: u8| (low as f64 + (high as f64 - low as f64) * ratio).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        mix(METRIC_LOW_COLOR.0, METRIC_HIGH_COLOR.0),
+        mix(METRIC_LOW_COLOR.1, METRIC_HIGH_COLOR.1),
+        mix(METRIC_LOW_COLOR.2, METRIC_HIGH_COLOR.2),
+    )
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DotOptions {
+    pub edge_weighting: EdgeWeighting,
+    /// Labels the edges having more than one call with their call counts.
+    pub call_count_labels: bool,
+    /// Marks the edges lying on cycles with `CssClass::Cycle`.
+    pub highlight_cycles: bool,
+    /// Colors the symbols by one of their metrics.
+    pub node_metric: NodeMetric,
+    /// Names the cells and the ends of the edges after the ids of the symbols, which survive
+    /// edits moving the symbols around, instead of their positions.
+    pub symbol_ids: bool,
+}
+
+/// Renders symbol-level graphs as DOT sources.
+pub struct DotRenderer<'a> {
+    pub options: &'a DotOptions,
+}
+
+impl Renderer for DotRenderer<'_> {
+    fn render(&self, tables: Vec<TableNode>, edges: Vec<Edge>, subgraphs: &[Subgraph]) -> String {
+        let ids = match self.options.symbol_ids {
+            true => TableNode::symbol_ids(&tables),
+            false => HashMap::new(),
+        };
+        Dot::generate_dot_source(tables.into_iter(), edges.into_iter(), subgraphs, &ids, self.options)
+    }
+}
+
 pub(crate) struct Dot;
 
 impl Dot {
@@ -59,6 +159,8 @@ impl Dot {
         // nodes: &[Node],
         edges: E,
         subgraphs: &[Subgraph],
+        ids: &HashMap<(u32, u32, u32), SymbolId>,
+        options: &DotOptions,
     ) -> String
     where
         T: Iterator<Item = TableNode>,
@@ -82,7 +184,7 @@ impl Dot {
                     sections = table
                         .sections
                         .iter()
-                        .map(|node| Dot::process_cell(table.id, node))
+                        .map(|node| Dot::process_cell(table.id, node, ids))
                         .collect::<Vec<_>>()
                         .join("\n"),
                 )
@@ -125,11 +227,86 @@ digraph {{
             EDGE_COLOR,
             tables,
             Dot::clusters(subgraphs),
-            Dot::process_edges(edges),
+            Dot::process_edges(edges, ids, options),
+        )
+    }
+
+    /// Generates a graph with one plain node per file or directory, whose edges are always
+    /// labeled with their counts.
+    pub fn generate_group_dot_source(
+        nodes: &[GroupNode],
+        edges: &[GroupEdge],
+        options: &DotOptions,
+    ) -> String {
+        let nodes = nodes
+            .iter()
+            .map(|node| {
+                format!(
+                    r#""{id}" [id="{id}", label="{title}", href="{path}"];"#,
+                    id = node.id,
+                    title = escape_html(&node.title),
+                    path = escape_html(&node.path),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n    ");
+
+        let options = DotOptions {
+            call_count_labels: false,
+            ..*options
+        };
+        let edges = edges
+            .iter()
+            .map(|edge| {
+                let attrs = iter::once(format!(r#"id="{} -> {}""#, edge.from, edge.to))
+                    .chain(iter::once(format!(r#"label="{}""#, edge.count)))
+                    .chain(Dot::edge_weight(edge.count, &options))
+                    .chain(Dot::edge_style(edge.classes))
+                    .chain(iter::once(Dot::css_classes(edge.classes)))
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>();
+
+                format!(
+                    r#""{}" -> "{}" [{}];"#,
+                    edge.from,
+                    edge.to,
+                    attrs.join(", ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n    ");
+
+        format!(
+            r#"
+digraph {{
+    graph [
+        rankdir = "LR"
+        ranksep = 2.0
+        fontname = "Arial"
+        bgcolor = "{}"
+    ];
+    node [
+        fontsize = "16"
+        fontname = "Arial"
+        shape = "box"
+        style = "rounded, filled"
+        fillcolor = "{}"
+        color = "{}"
+    ];
+    edge [
+        color = "{}"
+    ];
+
+    {}
+
+    {}
+}}
+            "#,
+            BG_COLOR, NODE_BG_COLOR, SYMBOL_DEFAULT_BORDER_COLOR, EDGE_COLOR, nodes, edges,
         )
     }
 
-    fn process_cell(table_id: u32, cell: &Cell) -> String {
+    fn process_cell(table_id: u32, cell: &Cell, ids: &HashMap<(u32, u32, u32), SymbolId>) -> String {
         let styles = [
             cell.style
                 .border
@@ -150,6 +327,7 @@ digraph {{
             escape_html(&cell.title)
         );
         let port = format!("{}_{}", cell.range_start.0, cell.range_start.1);
+        let id = cell_id(ids, (table_id, cell.range_start.0, cell.range_start.1), ':');
 
         if cell.children.is_empty() {
             let href = if let Some(kind) = cell.symbol_kind {
@@ -157,10 +335,14 @@ digraph {{
             } else {
                 Dot::css_classes_href(cell.style.classes)
             };
+            let styles = match &cell.style.bg_color {
+                Some(color) => format!(r#"{} BGCOLOR="{}""#, styles, color),
+                None => styles,
+            };
             format!(
-                r#"     <TR><TD PORT="{port}" ID="{table_id}:{port}" {styles} {href}>{title}</TD></TR>"#,
+                r#"     <TR><TD PORT="{port}" ID="{id}" {styles} {href}>{title}</TD></TR>"#,
                 port = port,
-                table_id = table_id,
+                id = id,
                 styles = styles,
                 href = href,
                 title = title
@@ -184,21 +366,20 @@ digraph {{
             format!(
                 r#"
             <TR><TD BORDER="0" CELLPADDING="0">
-            <TABLE ID="{table_id}:{port}" CELLSPACING="8" CELLPADDING="4" CELLBORDER="1" {table_styles} BGCOLOR="{bg_color}" {href}>
+            <TABLE ID="{id}" CELLSPACING="8" CELLPADDING="4" CELLBORDER="1" {table_styles} BGCOLOR="{bg_color}" {href}>
             {content}
             </TABLE>
             </TD></TR>
             "#,
-                table_id = table_id,
-                port = port,
+                id = id,
                 table_styles = table_styles,
-                bg_color = FUNCTION_BG_COLOR,
+                bg_color = cell.style.bg_color.as_deref().unwrap_or(FUNCTION_BG_COLOR),
                 href = href,
                 content = iter::once(dot_cell)
                      .chain(
                          cell.children
                              .iter()
-                             .map(|item| Dot::process_cell(table_id, item))
+                             .map(|item| Dot::process_cell(table_id, item, ids))
                      )
                      .collect::<Vec<_>>()
                      .join("\n"),
@@ -206,7 +387,7 @@ digraph {{
         }
     }
 
-    fn process_edges<E>(edges: E) -> String
+    fn process_edges<E>(edges: E, ids: &HashMap<(u32, u32, u32), SymbolId>, options: &DotOptions) -> String
     where
         E: Iterator<Item = Edge>,
     {
@@ -215,8 +396,8 @@ digraph {{
                 let from = format!(r#"{}:"{}_{}""#, edge.from.0, edge.from.1, edge.from.2);
                 let to = format!(r#"{}:"{}_{}""#, edge.to.0, edge.to.1, edge.to.2);
 
-                let from_id = format!("{}:{}_{}", edge.from.0, edge.from.1, edge.from.2);
-                let to_id = format!("{}:{}_{}", edge.to.0, edge.to.1, edge.to.2);
+                let from_id = cell_id(ids, edge.from, ':');
+                let to_id = cell_id(ids, edge.to, ':');
                 
                 let attrs = iter::once(format!(
                     r#"id="{} -> {}""#,
@@ -230,6 +411,9 @@ digraph {{
                     r#"datato="{}""#,
                     to_id
                 )))
+                .chain(iter::once(Dot::call_sites(&edge)))
+                .chain(Dot::edge_weight(edge.call_count(), options))
+                .chain(Dot::edge_style(edge.classes))
                 .chain(iter::once(Dot::css_classes(edge.classes)))
                 .filter(|s| !s.is_empty())
                 .collect::<Vec<_>>();
@@ -240,6 +424,64 @@ digraph {{
             .join("\n    ")
     }
 
+    fn edge_style(classes: EnumSet<CssClass>) -> Option<String> {
+        let style = Dot::edge_kind_style(classes);
+        let Some(color) = edge_color(classes) else {
+            return style;
+        };
+
+        let color = format!(r#"color="{}""#, color);
+        Some(match style {
+            Some(style) => format!("{}, {}", style, color),
+            None => color,
+        })
+    }
+
+    fn edge_kind_style(classes: EnumSet<CssClass>) -> Option<String> {
+        if classes.contains(CssClass::Inherit) {
+            // hollow triangles, as in UML class diagrams
+            Some(r#"arrowhead="empty""#.to_string())
+        } else if classes.contains(CssClass::Reference) {
+            Some(r#"style="dotted", arrowhead="open""#.to_string())
+        } else {
+            None
+        }
+    }
+
+    /// `penwidth`, `weight` and `label` attributes of edges with more than one call.
+    fn edge_weight(count: usize, options: &DotOptions) -> Vec<String> {
+        if count <= 1 {
+            return vec![];
+        }
+
+        let mut attrs = vec![];
+        if let Some(scale) = options.edge_weighting.scale(count) {
+            attrs.push(format!(r#"penwidth="{:.2}""#, scale.min(MAX_PENWIDTH)));
+            // dot only accepts integer weights
+            attrs.push(format!(r#"weight="{}""#, scale.round() as u32));
+        }
+        if options.call_count_labels {
+            attrs.push(format!(r#"label="{}""#, count));
+        }
+        attrs
+    }
+
+    /// Start positions of the call sites in the caller's file, so that the frontend can jump to them.
+    fn call_sites(edge: &Edge) -> String {
+        if edge.call_sites.is_empty() {
+            "".to_string()
+        } else {
+            format!(
+                r#"datacallsites="{}""#,
+                edge.call_sites
+                    .iter()
+                    .map(|range| format!("{}:{}", range.start.line, range.start.character))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        }
+    }
+
     fn clusters(subgraphs: &[Subgraph]) -> String {
         subgraphs
             .iter()
@@ -0,0 +1,617 @@
+//! Layered layout of directed graphs drawn from left to right. Cycles are broken by reversing
+//! some edges, the nodes are assigned to ranks, the nodes of each rank are ordered to reduce
+//! edge crossings, and finally placed as close as possible to the ports of their neighbors.
+
+use std::collections::{HashMap, VecDeque};
+
+/// Sweeps over the ranks reordering their nodes.
+const ORDER_ITERATIONS: usize = 24;
+/// Sweeps over the ranks moving the nodes towards their neighbors.
+const PLACE_ITERATIONS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Where edges meet a node, relative to the top left corner of the node: outgoing edges leave
+/// at `right` and incoming ones arrive at `left`, both at height `y`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct Port {
+    pub y: f64,
+    pub left: f64,
+    pub right: f64,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct LayoutNode {
+    pub width: f64,
+    pub height: f64,
+    /// Clusters containing the node, outermost first. The nodes of a cluster are kept next
+    /// to each other in every rank.
+    pub clusters: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct LayoutEdge {
+    pub from: usize,
+    pub to: usize,
+    pub from_port: Port,
+    pub to_port: Port,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Spacing {
+    /// Horizontal space between ranks
+    pub rank: f64,
+    /// Vertical space between nodes
+    pub node: f64,
+    /// Vertical space between an edge and a node or another edge
+    pub edge: f64,
+    /// Space kept above and below the nodes for each cluster containing them
+    pub cluster_top: f64,
+    pub cluster_bottom: f64,
+}
+
+#[derive(Debug)]
+pub(crate) struct Layout {
+    /// Top left corners of the nodes
+    pub nodes: Vec<Point>,
+    /// Points each edge goes through, from its source port to its target port. Edges from a
+    /// node to itself are left empty.
+    pub edges: Vec<Vec<Point>>,
+}
+
+/// A node, or a dummy node where an edge crosses a rank.
+struct Vertex {
+    rank: usize,
+    width: f64,
+    height: f64,
+    clusters: Vec<usize>,
+    dummy: bool,
+}
+
+/// Part of an edge between two adjacent ranks.
+struct Segment {
+    tail: usize,
+    head: usize,
+    tail_port: Port,
+    head_port: Port,
+}
+
+struct LayeredGraph {
+    vertices: Vec<Vertex>,
+    segments: Vec<Segment>,
+    incoming: Vec<Vec<usize>>,
+    outgoing: Vec<Vec<usize>>,
+    /// Vertices of each rank, in order
+    ranks: Vec<Vec<usize>>,
+}
+
+pub(crate) fn layout(nodes: &[LayoutNode], edges: &[LayoutEdge], spacing: &Spacing) -> Layout {
+    let reversed = feedback_edges(nodes.len(), edges);
+    let ranks = assign_ranks(nodes.len(), edges, &reversed);
+
+    let mut vertices = nodes
+        .iter()
+        .zip(&ranks)
+        .map(|(node, &rank)| Vertex {
+            rank,
+            width: node.width,
+            height: node.height,
+            clusters: node.clusters.clone(),
+            dummy: false,
+        })
+        .collect::<Vec<_>>();
+
+    // edges spanning several ranks go through a dummy vertex in each rank between their ends
+    let mut segments = vec![];
+    let mut chains = vec![];
+    for (edge, &reversed) in edges.iter().zip(&reversed) {
+        if edge.from == edge.to {
+            chains.push(vec![]);
+            continue;
+        }
+
+        let (tail, head, tail_port, head_port) = if reversed {
+            (edge.to, edge.from, edge.to_port, edge.from_port)
+        } else {
+            (edge.from, edge.to, edge.from_port, edge.to_port)
+        };
+        let clusters = common_prefix(&vertices[tail].clusters, &vertices[head].clusters).to_vec();
+
+        let mut chain = vec![];
+        let (mut prev, mut prev_port) = (tail, tail_port);
+        for rank in ranks[tail] + 1..ranks[head] {
+            vertices.push(Vertex {
+                rank,
+                width: 0.0,
+                height: 0.0,
+                clusters: clusters.clone(),
+                dummy: true,
+            });
+            let dummy = vertices.len() - 1;
+            chain.push(segments.len());
+            segments.push(Segment {
+                tail: prev,
+                head: dummy,
+                tail_port: prev_port,
+                head_port: Port::default(),
+            });
+            (prev, prev_port) = (dummy, Port::default());
+        }
+        chain.push(segments.len());
+        segments.push(Segment {
+            tail: prev,
+            head,
+            tail_port: prev_port,
+            head_port,
+        });
+        chains.push(chain);
+    }
+
+    let mut graph = LayeredGraph::new(vertices, segments);
+    graph.order();
+    let (columns, ys) = graph.place(spacing);
+
+    let x = |v: usize| {
+        let (left, width) = columns[graph.vertices[v].rank];
+        left + (width - graph.vertices[v].width) / 2.0
+    };
+
+    let edges = chains
+        .iter()
+        .zip(&reversed)
+        .map(|(chain, &reversed)| {
+            let Some(&first) = chain.first() else {
+                return vec![];
+            };
+
+            let first = &graph.segments[first];
+            let mut points = vec![Point {
+                x: x(first.tail) + first.tail_port.right,
+                y: ys[first.tail] + first.tail_port.y,
+            }];
+            for &segment in chain {
+                let Segment {
+                    head, head_port, ..
+                } = graph.segments[segment];
+                let vertex = &graph.vertices[head];
+                if vertex.dummy {
+                    // dummies go straight through their rank, clear of the nodes
+                    let (left, width) = columns[vertex.rank];
+                    points.push(Point {
+                        x: left,
+                        y: ys[head],
+                    });
+                    points.push(Point {
+                        x: left + width,
+                        y: ys[head],
+                    });
+                } else {
+                    points.push(Point {
+                        x: x(head) + head_port.left,
+                        y: ys[head] + head_port.y,
+                    });
+                }
+            }
+
+            if reversed {
+                points.reverse();
+            }
+            points
+        })
+        .collect();
+
+    Layout {
+        nodes: (0..nodes.len())
+            .map(|v| Point { x: x(v), y: ys[v] })
+            .collect(),
+        edges,
+    }
+}
+
+/// Edges to reverse to make the graph acyclic: the back edges of a depth-first search.
+fn feedback_edges(n: usize, edges: &[LayoutEdge]) -> Vec<bool> {
+    let mut outgoing = vec![vec![]; n];
+    for (i, edge) in edges.iter().enumerate() {
+        if edge.from != edge.to {
+            outgoing[edge.from].push(i);
+        }
+    }
+
+    // 0: not visited, 1: on the stack, 2: done
+    let mut state = vec![0u8; n];
+    let mut reversed = vec![false; edges.len()];
+    for root in 0..n {
+        if state[root] != 0 {
+            continue;
+        }
+
+        state[root] = 1;
+        let mut stack = vec![(root, 0)];
+        while let Some(&(v, i)) = stack.last() {
+            match outgoing[v].get(i) {
+                Some(&edge) => {
+                    stack.last_mut().unwrap().1 += 1;
+                    let w = edges[edge].to;
+                    match state[w] {
+                        0 => {
+                            state[w] = 1;
+                            stack.push((w, 0));
+                        }
+                        1 => reversed[edge] = true,
+                        _ => {}
+                    }
+                }
+                None => {
+                    state[v] = 2;
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    reversed
+}
+
+/// Ranks of the longest paths from the sources, with the sources then moved next to their
+/// nearest successors.
+fn assign_ranks(n: usize, edges: &[LayoutEdge], reversed: &[bool]) -> Vec<usize> {
+    let mut successors = vec![vec![]; n];
+    let mut indegree = vec![0; n];
+    for (edge, &reversed) in edges.iter().zip(reversed) {
+        if edge.from == edge.to {
+            continue;
+        }
+        let (tail, head) = if reversed {
+            (edge.to, edge.from)
+        } else {
+            (edge.from, edge.to)
+        };
+        successors[tail].push(head);
+        indegree[head] += 1;
+    }
+
+    let sources = (0..n).filter(|&v| indegree[v] == 0).collect::<Vec<_>>();
+    let mut queue = sources.iter().copied().collect::<VecDeque<_>>();
+    let mut ranks = vec![0; n];
+    while let Some(v) = queue.pop_front() {
+        for &w in &successors[v] {
+            ranks[w] = ranks[w].max(ranks[v] + 1);
+            indegree[w] -= 1;
+            if indegree[w] == 0 {
+                queue.push_back(w);
+            }
+        }
+    }
+
+    for v in sources {
+        if let Some(nearest) = successors[v].iter().map(|&w| ranks[w]).min() {
+            ranks[v] = nearest - 1;
+        }
+    }
+
+    ranks
+}
+
+fn common_prefix<'a>(a: &'a [usize], b: &[usize]) -> &'a [usize] {
+    let len = a.iter().zip(b).take_while(|(a, b)| a == b).count();
+    &a[..len]
+}
+
+impl LayeredGraph {
+    fn new(vertices: Vec<Vertex>, segments: Vec<Segment>) -> Self {
+        let mut incoming = vec![vec![]; vertices.len()];
+        let mut outgoing = vec![vec![]; vertices.len()];
+        for (i, segment) in segments.iter().enumerate() {
+            outgoing[segment.tail].push(i);
+            incoming[segment.head].push(i);
+        }
+
+        let mut ranks = vec![vec![]; vertices.iter().map(|v| v.rank + 1).max().unwrap_or(0)];
+        for (i, vertex) in vertices.iter().enumerate() {
+            ranks[vertex.rank].push(i);
+        }
+
+        let mut graph = LayeredGraph {
+            vertices,
+            segments,
+            incoming,
+            outgoing,
+            ranks,
+        };
+        for r in 0..graph.ranks.len() {
+            let keys = graph.positions();
+            graph.sort_rank(r, &keys);
+        }
+        graph
+    }
+
+    /// Index of each vertex in its rank.
+    fn positions(&self) -> Vec<f64> {
+        let mut positions = vec![0.0; self.vertices.len()];
+        for rank in &self.ranks {
+            for (i, &v) in rank.iter().enumerate() {
+                positions[v] = i as f64;
+            }
+        }
+        positions
+    }
+
+    /// Position of a port in the order of its rank, between the positions of its vertex and
+    /// of the next one.
+    fn port_position(&self, positions: &[f64], v: usize, port: &Port) -> f64 {
+        let height = self.vertices[v].height;
+        let fraction = if height > 0.0 {
+            (port.y / height).clamp(0.0, 0.99)
+        } else {
+            0.5
+        };
+        positions[v] + fraction
+    }
+
+    /// Reorders the ranks by the barycenters of the neighbors of their vertices, sweeping
+    /// alternately down and up, and keeps the orders with the fewest crossings.
+    fn order(&mut self) {
+        let mut best = self.ranks.clone();
+        let mut fewest = self.crossings();
+
+        for i in 0..ORDER_ITERATIONS {
+            if fewest == 0 {
+                break;
+            }
+
+            if i % 2 == 0 {
+                for r in 1..self.ranks.len() {
+                    self.reorder(r, true);
+                }
+            } else {
+                for r in (0..self.ranks.len().saturating_sub(1)).rev() {
+                    self.reorder(r, false);
+                }
+            }
+
+            let crossings = self.crossings();
+            if crossings < fewest {
+                fewest = crossings;
+                best = self.ranks.clone();
+            }
+        }
+
+        self.ranks = best;
+    }
+
+    /// Sorts rank `r` by the barycenters of the neighbors in the previous rank if `down`,
+    /// or in the next rank otherwise. Vertices without such neighbors stay where they are.
+    fn reorder(&mut self, r: usize, down: bool) {
+        let positions = self.positions();
+        let mut keys = positions.clone();
+
+        for &v in &self.ranks[r] {
+            let neighbors = if down {
+                &self.incoming[v]
+            } else {
+                &self.outgoing[v]
+            };
+            if neighbors.is_empty() {
+                continue;
+            }
+
+            let sum = neighbors
+                .iter()
+                .map(|&s| {
+                    let segment = &self.segments[s];
+                    if down {
+                        self.port_position(&positions, segment.tail, &segment.tail_port)
+                    } else {
+                        self.port_position(&positions, segment.head, &segment.head_port)
+                    }
+                })
+                .sum::<f64>();
+            keys[v] = sum / neighbors.len() as f64;
+        }
+
+        self.sort_rank(r, &keys);
+    }
+
+    /// Sorts rank `r` by `keys`, keeping the vertices of each cluster together: clusters are
+    /// ordered by the mean key of their vertices.
+    fn sort_rank(&mut self, r: usize, keys: &[f64]) {
+        let rank = std::mem::take(&mut self.ranks[r]);
+        self.ranks[r] = self.sort_group(rank, 0, keys);
+    }
+
+    fn sort_group(&self, vertices: Vec<usize>, depth: usize, keys: &[f64]) -> Vec<usize> {
+        // (key, vertices, whether the vertices are a cluster)
+        let mut groups: Vec<(f64, Vec<usize>, bool)> = vec![];
+        let mut clusters = HashMap::new();
+        for v in vertices {
+            match self.vertices[v].clusters.get(depth) {
+                Some(cluster) => {
+                    let group = *clusters.entry(*cluster).or_insert_with(|| {
+                        groups.push((0.0, vec![], true));
+                        groups.len() - 1
+                    });
+                    groups[group].1.push(v);
+                }
+                None => groups.push((0.0, vec![v], false)),
+            }
+        }
+
+        for group in &mut groups {
+            group.0 = group.1.iter().map(|&v| keys[v]).sum::<f64>() / group.1.len() as f64;
+        }
+        groups.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        groups
+            .into_iter()
+            .flat_map(|(_, vertices, cluster)| match cluster {
+                true => self.sort_group(vertices, depth + 1, keys),
+                false => vertices,
+            })
+            .collect()
+    }
+
+    fn crossings(&self) -> usize {
+        let positions = self.positions();
+
+        let mut crossings = 0;
+        for rank in &self.ranks {
+            let ends = rank
+                .iter()
+                .flat_map(|&v| &self.outgoing[v])
+                .map(|&s| {
+                    let segment = &self.segments[s];
+                    (
+                        self.port_position(&positions, segment.tail, &segment.tail_port),
+                        self.port_position(&positions, segment.head, &segment.head_port),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            for (i, a) in ends.iter().enumerate() {
+                crossings += ends[i + 1..]
+                    .iter()
+                    .filter(|b| (a.0 - b.0) * (a.1 - b.1) < 0.0)
+                    .count();
+            }
+        }
+        crossings
+    }
+
+    /// Left and width of each rank, and top of each vertex.
+    fn place(&self, spacing: &Spacing) -> (Vec<(f64, f64)>, Vec<f64>) {
+        let mut columns = vec![];
+        let mut left = 0.0;
+        for rank in &self.ranks {
+            let width = rank
+                .iter()
+                .map(|&v| self.vertices[v].width)
+                .fold(0.0, f64::max);
+            columns.push((left, width));
+            left += width + spacing.rank;
+        }
+
+        let gaps = self
+            .ranks
+            .iter()
+            .map(|rank| {
+                rank.windows(2)
+                    .map(|pair| self.gap(pair[0], pair[1], spacing))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut ys = vec![0.0; self.vertices.len()];
+        for (rank, gaps) in self.ranks.iter().zip(&gaps) {
+            let mut y = 0.0;
+            for (i, &v) in rank.iter().enumerate() {
+                ys[v] = y;
+                y += gaps.get(i).copied().unwrap_or_default();
+            }
+        }
+
+        // moves every vertex to the mean height of its neighbors, as far as its rank allows
+        for i in 0..PLACE_ITERATIONS {
+            let order = (0..self.ranks.len()).collect::<Vec<_>>();
+            let order = match i % 2 {
+                0 => order,
+                _ => order.into_iter().rev().collect(),
+            };
+
+            for r in order {
+                let desired = self.ranks[r]
+                    .iter()
+                    .map(|&v| self.desired_y(v, &ys))
+                    .collect::<Vec<_>>();
+                for (&v, y) in self.ranks[r].iter().zip(stack(&desired, &gaps[r])) {
+                    ys[v] = y;
+                }
+            }
+        }
+
+        // clusters extend above their vertices
+        let top = self
+            .vertices
+            .iter()
+            .zip(&ys)
+            .map(|(vertex, y)| y - vertex.clusters.len() as f64 * spacing.cluster_top)
+            .fold(f64::INFINITY, f64::min);
+        if top.is_finite() {
+            ys.iter_mut().for_each(|y| *y -= top);
+        }
+
+        (columns, ys)
+    }
+
+    /// Distance between the tops of `a` and `b`, with `b` right below `a`.
+    fn gap(&self, a: usize, b: usize, spacing: &Spacing) -> f64 {
+        let (a, b) = (&self.vertices[a], &self.vertices[b]);
+        let common = common_prefix(&a.clusters, &b.clusters).len();
+        let space = if a.dummy || b.dummy {
+            spacing.edge
+        } else {
+            spacing.node
+        };
+
+        a.height
+            + space
+            + (a.clusters.len() - common) as f64 * spacing.cluster_bottom
+            + (b.clusters.len() - common) as f64 * spacing.cluster_top
+    }
+
+    /// Top of `v` making its edges as horizontal as possible on average.
+    fn desired_y(&self, v: usize, ys: &[f64]) -> f64 {
+        let incoming = self.incoming[v].iter().map(|&s| {
+            let segment = &self.segments[s];
+            ys[segment.tail] + segment.tail_port.y - segment.head_port.y
+        });
+        let outgoing = self.outgoing[v].iter().map(|&s| {
+            let segment = &self.segments[s];
+            ys[segment.head] + segment.head_port.y - segment.tail_port.y
+        });
+
+        let (sum, count) = incoming
+            .chain(outgoing)
+            .fold((0.0, 0), |(sum, count), y| (sum + y, count + 1));
+        match count {
+            0 => ys[v],
+            _ => sum / count as f64,
+        }
+    }
+}
+
+/// The tops closest to `desired`, in the least squares sense, leaving at least `gaps[i]`
+/// between the tops of the vertices `i` and `i + 1`. Subtracting the minimum offsets of the
+/// vertices turns this into an isotonic regression, solved by pooling adjacent violators.
+fn stack(desired: &[f64], gaps: &[f64]) -> Vec<f64> {
+    let offsets = std::iter::once(0.0)
+        .chain(gaps.iter().scan(0.0, |offset, gap| {
+            *offset += gap;
+            Some(*offset)
+        }))
+        .collect::<Vec<_>>();
+
+    // (sum, count) of the pooled values
+    let mut blocks: Vec<(f64, usize)> = vec![];
+    for (y, offset) in desired.iter().zip(&offsets) {
+        let mut block = (y - offset, 1);
+        while let Some(&(sum, count)) = blocks.last() {
+            if sum / count as f64 <= block.0 / block.1 as f64 {
+                break;
+            }
+            blocks.pop();
+            block = (block.0 + sum, block.1 + count);
+        }
+        blocks.push(block);
+    }
+
+    blocks
+        .into_iter()
+        .flat_map(|(sum, count)| std::iter::repeat_n(sum / count as f64, count))
+        .zip(&offsets)
+        .map(|(y, offset)| y + offset)
+        .collect()
+}
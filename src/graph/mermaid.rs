@@ -0,0 +1,187 @@
+use {
+    super::{
+        cell_id,
+        dot::{EDGE_ADDED_COLOR, EDGE_REMOVED_COLOR},
+        Cell, CssClass, Edge, Renderer, Subgraph, TableNode,
+    },
+    crate::graph_model::SymbolId,
+    enumset::EnumSet,
+    std::{
+        collections::{BTreeMap, HashMap},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Renders symbol-level graphs as Mermaid flowcharts, with a subgraph per directory, per file
+/// and per type containing other symbols.
+pub struct MermaidRenderer<'a> {
+    /// Directory the titles of the directory subgraphs are relative to
+    pub root: &'a str,
+    /// Uses the ids of the symbols as node ids instead of their positions
+    pub symbol_ids: bool,
+}
+
+impl Renderer for MermaidRenderer<'_> {
+    fn render(&self, tables: Vec<TableNode>, edges: Vec<Edge>, _subgraphs: &[Subgraph]) -> String {
+        let mut mermaid = String::from("flowchart LR\n");
+        let ids = match self.symbol_ids {
+            true => TableNode::symbol_ids(&tables),
+            false => HashMap::new(),
+        };
+
+        // 将表格按目录和文件分组，目录按路径排序，没有路径的表格放在所有subgraph之外
+        let mut dir_files: BTreeMap<PathBuf, Vec<(&str, &TableNode)>> = BTreeMap::new();
+        let mut orphan_tables = Vec::new();
+        for table in &tables {
+            match table.path.as_deref() {
+                Some(path) => {
+                    let parent = Path::new(path).parent().unwrap_or(Path::new(""));
+                    dir_files
+                        .entry(parent.to_path_buf())
+                        .or_default()
+                        .push((path, table));
+                }
+                None => orphan_tables.push(table),
+            }
+        }
+
+        let root_path = Path::new(self.root);
+        for (dir_index, (dir_path, files)) in dir_files.iter().enumerate() {
+            // 使用相对于项目根目录的目录路径作为subgraph标题，确保以"/"开头
+            let relative = dir_path.strip_prefix(root_path).unwrap_or(dir_path);
+            let dir_title = format!("/{}", relative.to_string_lossy());
+            mermaid.push_str(&format!(
+                "    subgraph dir{} [\"{}\"]\n",
+                dir_index, dir_title
+            ));
+
+            for (file_index, (file_path, table)) in files.iter().enumerate() {
+                // 只使用文件名作为subgraph标题
+                let path = Path::new(file_path);
+                let file_title = path
+                    .file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy();
+                mermaid.push_str(&format!(
+                    "        subgraph file{}_{} [\"{}\"]\n",
+                    dir_index, file_index, file_title
+                ));
+                for section in &table.sections {
+                    add_cell(table.id, section, &ids, &mut mermaid, 3);
+                }
+                mermaid.push_str("        end\n");
+            }
+
+            mermaid.push_str("    end\n");
+        }
+
+        for table in orphan_tables {
+            for section in &table.sections {
+                add_cell(table.id, section, &ids, &mut mermaid, 1);
+            }
+        }
+
+        for edge in &edges {
+            let from = cell_id(&ids, edge.from, '_');
+            let to = cell_id(&ids, edge.to, '_');
+            // 调用次数大于1时，在边上标注调用次数
+            let label = Some(edge.call_count()).filter(|count| *count > 1);
+            mermaid.push_str(&mermaid_edge(&from, &to, edge.classes, label));
+        }
+
+        // 按边的序号为diff中新增和删除的边着色
+        for (class, color) in [
+            (CssClass::Added, EDGE_ADDED_COLOR),
+            (CssClass::Removed, EDGE_REMOVED_COLOR),
+        ] {
+            let indices = edges
+                .iter()
+                .enumerate()
+                .filter(|(_, edge)| edge.classes.contains(class))
+                .map(|(i, _)| i.to_string())
+                .collect::<Vec<_>>();
+            if !indices.is_empty() {
+                mermaid.push_str(&format!(
+                    "    linkStyle {} stroke:{}\n",
+                    indices.join(","),
+                    color
+                ));
+            }
+        }
+
+        mermaid
+    }
+}
+
+/// 继承关系使用粗箭头，引用关系使用虚线箭头
+pub(crate) fn mermaid_edge(
+    from: &str,
+    to: &str,
+    classes: EnumSet<CssClass>,
+    label: Option<usize>,
+) -> String {
+    let arrow = if classes.contains(CssClass::Inherit) {
+        "==>"
+    } else if classes.contains(CssClass::Reference) {
+        "-.->"
+    } else {
+        "-->"
+    };
+
+    match label {
+        Some(label) => format!("    {} {}|{}| {}\n", from, arrow, label, to),
+        None => format!("    {} {} {}\n", from, arrow, to),
+    }
+}
+
+/// 类型、接口和模块等容器类型的单元格，如果有子节点，则生成一个subgraph
+fn add_cell(
+    table_id: u32,
+    cell: &Cell,
+    ids: &HashMap<(u32, u32, u32), SymbolId>,
+    mermaid: &mut String,
+    indent_level: usize,
+) {
+    let indent = "    ".repeat(indent_level);
+    let id = cell_id(ids, (table_id, cell.range_start.0, cell.range_start.1), '_');
+    let label = cell
+        .title
+        .replace('"', "\\\"")
+        .replace('[', "\\[")
+        .replace(']', "\\]");
+
+    let classes = cell.style.classes;
+    if cell.is_container() && !cell.children.is_empty() {
+        mermaid.push_str(&format!("{}subgraph {} [\"{}\"]\n", indent, id, label));
+        for child in &cell.children {
+            add_cell(table_id, child, ids, mermaid, indent_level + 1);
+        }
+        mermaid.push_str(&format!("{}end\n", indent));
+        return;
+    }
+
+    mermaid.push_str(&format!("{}{}[\"{}\"]", indent, id, label));
+    if classes.is_empty() {
+        mermaid.push('\n');
+    } else {
+        let primary_class = [
+            (CssClass::Type, "type"),
+            (CssClass::Interface, "interface"),
+            (CssClass::Module, "module"),
+            (CssClass::Function, "function"),
+            (CssClass::Method, "method"),
+            (CssClass::Constructor, "constructor"),
+            (CssClass::Property, "property"),
+            (CssClass::Impl, "impl"),
+        ]
+        .into_iter()
+        .find(|(class, _)| classes.contains(*class))
+        .map_or("cell", |(_, name)| name);
+        mermaid.push_str(&format!(":::{}\n", primary_class));
+    }
+
+    // 非容器类型节点的子节点与其并列
+    for child in &cell.children {
+        add_cell(table_id, child, ids, mermaid, indent_level);
+    }
+}
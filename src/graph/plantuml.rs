@@ -0,0 +1,270 @@
+use {
+    super::{
+        cell_id,
+        dot::{EDGE_ADDED_COLOR, EDGE_REMOVED_COLOR},
+        Cell, CssClass, Edge, Renderer, Subgraph, TableNode,
+    },
+    crate::{graph_model::SymbolId, lsp_types::SymbolKind},
+    enumset::EnumSet,
+    std::collections::{BTreeMap, HashMap},
+};
+
+/// Renders symbol-level graphs as PlantUML class diagrams, with nested packages for the
+/// directories and a package per file. Types are drawn as classes listing their members, the
+/// other symbols as classes of their own.
+pub struct PlantUmlRenderer {
+    /// Uses the ids of the symbols as aliases instead of their positions
+    pub symbol_ids: bool,
+}
+
+impl Renderer for PlantUmlRenderer {
+    fn render(&self, tables: Vec<TableNode>, edges: Vec<Edge>, subgraphs: &[Subgraph]) -> String {
+        let ids = match self.symbol_ids {
+            true => TableNode::symbol_ids(&tables),
+            false => HashMap::new(),
+        };
+        let mut diagram = Diagram {
+            ids: &ids,
+            tables: tables.iter().map(|table| (table.id, table)).collect(),
+            classes: HashMap::new(),
+            packages: 0,
+            uml: String::from("@startuml\nset namespaceSeparator none\nhide empty members\n\n"),
+        };
+
+        for subgraph in subgraphs {
+            diagram.add_package(subgraph, 0);
+        }
+        // 不属于任何目录的表格
+        let rest = diagram.tables.keys().copied().collect::<Vec<_>>();
+        for id in rest {
+            diagram.add_table(id, 0);
+        }
+
+        diagram.uml.push('\n');
+        diagram.add_edges(&edges);
+        diagram.uml.push_str("@enduml\n");
+        diagram.uml
+    }
+}
+
+/// 继承关系使用泛化箭头，实现关系使用实现箭头，引用关系使用关联箭头，调用关系使用依赖箭头
+pub(crate) fn plantuml_edge(
+    from: &str,
+    to: &str,
+    classes: EnumSet<CssClass>,
+    label: Option<usize>,
+) -> String {
+    let (line, head) = if classes.contains(CssClass::Inherit) {
+        ('-', "|>")
+    } else if classes.contains(CssClass::Impl) {
+        ('.', "|>")
+    } else if classes.contains(CssClass::Reference) {
+        ('-', ">")
+    } else {
+        ('.', ">")
+    };
+    let color = if classes.contains(CssClass::Added) {
+        format!("[{}]", EDGE_ADDED_COLOR)
+    } else if classes.contains(CssClass::Removed) {
+        format!("[{}]", EDGE_REMOVED_COLOR)
+    } else {
+        String::new()
+    };
+
+    let arrow = format!("{}{}{}{}", line, color, line, head);
+    match label {
+        Some(label) => format!("{} {} {} : {}\n", from, arrow, to, label),
+        None => format!("{} {} {}\n", from, arrow, to),
+    }
+}
+
+fn escape(title: &str) -> String {
+    title.replace('"', "'")
+}
+
+struct Diagram<'a> {
+    ids: &'a HashMap<(u32, u32, u32), SymbolId>,
+    /// 尚未输出的表格
+    tables: BTreeMap<u32, &'a TableNode>,
+    /// 每个单元格所在的类的别名
+    classes: HashMap<(u32, u32, u32), String>,
+    packages: usize,
+    uml: String,
+}
+
+impl Diagram<'_> {
+    /// 目录对应嵌套的package，标题为空的目录（根目录）不生成package
+    fn add_package(&mut self, subgraph: &Subgraph, indent_level: usize) {
+        let indent = "  ".repeat(indent_level);
+        let inner_level = match subgraph.title.is_empty() {
+            true => indent_level,
+            false => {
+                self.packages += 1;
+                self.uml.push_str(&format!(
+                    "{}package \"{}\" as d{} {{\n",
+                    indent,
+                    escape(&subgraph.title),
+                    self.packages
+                ));
+                indent_level + 1
+            }
+        };
+
+        for node in &subgraph.nodes {
+            if let Ok(id) = node.parse() {
+                self.add_table(id, inner_level);
+            }
+        }
+        for subgraph in &subgraph.subgraphs {
+            self.add_package(subgraph, inner_level);
+        }
+
+        if !subgraph.title.is_empty() {
+            self.uml.push_str(&format!("{}}}\n", indent));
+        }
+    }
+
+    fn add_table(&mut self, id: u32, indent_level: usize) {
+        let Some(table) = self.tables.remove(&id) else {
+            return;
+        };
+
+        let indent = "  ".repeat(indent_level);
+        self.uml.push_str(&format!(
+            "{}package \"{}\" as f{} <<Rectangle>> {{\n",
+            indent,
+            escape(&table.title),
+            table.id
+        ));
+        for section in &table.sections {
+            self.add_cell(table.id, section, indent_level + 1);
+        }
+        self.uml.push_str(&format!("{}}}\n", indent));
+    }
+
+    /// 类型生成一个类，其中的函数和属性作为成员，嵌套的类型与其并列；其他符号各自生成一个类
+    fn add_cell(&mut self, table_id: u32, cell: &Cell, indent_level: usize) {
+        let indent = "  ".repeat(indent_level);
+        let position = (table_id, cell.range_start.0, cell.range_start.1);
+        let alias = format!("s{}", cell_id(self.ids, position, '_'));
+        self.classes.insert(position, alias.clone());
+
+        let classes = cell.style.classes;
+        if !cell.is_container() {
+            let stereotype = [
+                CssClass::Function,
+                CssClass::Method,
+                CssClass::Constructor,
+                CssClass::Property,
+            ]
+            .into_iter()
+            .find(|class| classes.contains(*class))
+            .map(|class| format!(" <<{}>>", class.to_str()))
+            .unwrap_or_default();
+            self.uml.push_str(&format!(
+                "{}class \"{}\" as {}{}\n",
+                indent,
+                escape(&cell.title),
+                alias,
+                stereotype
+            ));
+
+            // 非容器类型节点的子节点与其并列
+            for child in &cell.children {
+                self.add_cell(table_id, child, indent_level);
+            }
+            return;
+        }
+
+        let keyword = if classes.contains(CssClass::Interface) {
+            "interface"
+        } else if cell.symbol_kind == Some(SymbolKind::Enum) {
+            "enum"
+        } else {
+            "class"
+        };
+        let stereotype = match classes.contains(CssClass::Module) {
+            true => " <<module>>",
+            false => "",
+        };
+        self.uml.push_str(&format!(
+            "{}{} \"{}\" as {}{}",
+            indent,
+            keyword,
+            escape(&cell.title),
+            alias,
+            stereotype
+        ));
+
+        let (nested, members): (Vec<_>, Vec<_>) =
+            cell.children.iter().partition(|child| child.is_container());
+        if members.is_empty() {
+            self.uml.push('\n');
+        } else {
+            self.uml.push_str(" {\n");
+            for member in members {
+                let callable = [CssClass::Function, CssClass::Method, CssClass::Constructor]
+                    .into_iter()
+                    .any(|class| member.style.classes.contains(class));
+                let parens = if callable { "()" } else { "" };
+                self.uml.push_str(&format!(
+                    "{}  {}{}\n",
+                    indent,
+                    escape(&member.title),
+                    parens
+                ));
+                self.add_member(table_id, member, &alias);
+            }
+            self.uml.push_str(&format!("{}}}\n", indent));
+        }
+
+        for child in nested {
+            self.add_cell(table_id, child, indent_level);
+        }
+    }
+
+    /// 成员及其子节点的边连接到成员所在的类
+    fn add_member(&mut self, table_id: u32, cell: &Cell, alias: &str) {
+        self.classes.insert(
+            (table_id, cell.range_start.0, cell.range_start.1),
+            alias.to_string(),
+        );
+        for child in &cell.children {
+            self.add_member(table_id, child, alias);
+        }
+    }
+
+    /// 连接同一对类的同种边合并为一条，类的成员之间的边不画
+    fn add_edges(&mut self, edges: &[Edge]) {
+        let kinds = CssClass::Impl | CssClass::Inherit | CssClass::Reference;
+
+        let mut merged: Vec<(&str, &str, EnumSet<CssClass>, usize)> = vec![];
+        let mut indices = HashMap::new();
+        for edge in edges {
+            let (Some(from), Some(to)) = (self.classes.get(&edge.from), self.classes.get(&edge.to))
+            else {
+                continue;
+            };
+            if from == to {
+                continue;
+            }
+
+            let i = *indices
+                .entry((from, to, edge.classes & kinds))
+                .or_insert_with(|| {
+                    merged.push((from, to, EnumSet::new(), 0));
+                    merged.len() - 1
+                });
+            merged[i].2 |= edge.classes;
+            merged[i].3 += edge.call_count();
+        }
+
+        let mut uml = String::new();
+        for (from, to, classes, count) in merged {
+            // 调用次数大于1时，在边上标注调用次数
+            let label = Some(count).filter(|count| *count > 1);
+            uml.push_str(&plantuml_edge(from, to, classes, label));
+        }
+        self.uml.push_str(&uml);
+    }
+}
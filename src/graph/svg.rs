@@ -0,0 +1,676 @@
+use {
+    super::{
+        cell_id,
+        dot::{
+            edge_color, escape_html, DotOptions, BG_COLOR, CLUSTER_LABEL_BG_COLOR, EDGE_COLOR,
+            FUNCTION_BG_COLOR, MAX_PENWIDTH, NODE_BG_COLOR, SYMBOL_DEFAULT_BORDER_COLOR,
+            SYMBOL_DEFAULT_TEXT_COLOR,
+        },
+        layout::{layout, LayoutEdge, LayoutNode, Point, Port, Spacing},
+        Cell, CssClass, Edge, Renderer, Subgraph, TableNode,
+    },
+    crate::graph_model::SymbolId,
+    enumset::EnumSet,
+    std::{collections::HashMap, fmt::Write},
+};
+
+const FONT_SIZE: f64 = 16.0;
+/// Estimated width of a character, wide characters count twice
+const CHAR_WIDTH: f64 = 9.0;
+/// Width of the icon in front of a cell title and the spaces after it
+const ICON_WIDTH: f64 = 3.0 * CHAR_WIDTH;
+
+const TITLE_HEIGHT: f64 = 36.0;
+const ROW_HEIGHT: f64 = 30.0;
+const TABLE_MIN_WIDTH: f64 = 230.0;
+const CELL_SPACING: f64 = 8.0;
+const CELL_PADDING: f64 = 8.0;
+
+const CLUSTER_PADDING: f64 = 12.0;
+const CLUSTER_LABEL_HEIGHT: f64 = 24.0;
+const MARGIN: f64 = 8.0;
+
+const ARROW_LENGTH: f64 = 10.0;
+const ARROW_HALF_WIDTH: f64 = 4.0;
+/// How far the edges between cells of the same table go right of it
+const LOOP_WIDTH: f64 = 40.0;
+
+const SPACING: Spacing = Spacing {
+    rank: 144.0,
+    node: 36.0,
+    edge: 12.0,
+    cluster_top: CLUSTER_PADDING + CLUSTER_LABEL_HEIGHT,
+    cluster_bottom: CLUSTER_PADDING,
+};
+
+/// How edges are drawn between the ports of their cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeRouting {
+    #[default]
+    Spline,
+    Orthogonal,
+}
+
+/// Renders symbol-level graphs as SVG documents without Graphviz. The elements have the ids,
+/// classes and data attributes the webview expects: `g.node` with `data-path`, `g.cell` with
+/// `data-kind`, `g.edge` with `data-from` and `data-to`, and `g.cluster`.
+pub struct SvgRenderer<'a> {
+    pub options: &'a DotOptions,
+    pub routing: EdgeRouting,
+}
+
+/// A cell placed in its table, relative to the top left corner of the table.
+struct CellBox<'a> {
+    cell: &'a Cell,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    /// Middle of the row showing the title of the cell
+    title_y: f64,
+    children: Vec<CellBox<'a>>,
+}
+
+struct TableBox<'a> {
+    table: &'a TableNode,
+    width: f64,
+    height: f64,
+    cells: Vec<CellBox<'a>>,
+    ports: HashMap<(u32, u32), Port>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Renderer for SvgRenderer<'_> {
+    fn render(&self, tables: Vec<TableNode>, edges: Vec<Edge>, subgraphs: &[Subgraph]) -> String {
+        let ids = match self.options.symbol_ids {
+            true => TableNode::symbol_ids(&tables),
+            false => HashMap::new(),
+        };
+        let boxes = tables.iter().map(table_box).collect::<Vec<_>>();
+        let index = tables
+            .iter()
+            .enumerate()
+            .map(|(i, table)| (table.id, i))
+            .collect::<HashMap<_, _>>();
+
+        let mut clusters = vec![vec![]; tables.len()];
+        assign_clusters(subgraphs, &index, &mut vec![], &mut 0, &mut clusters);
+
+        let nodes = boxes
+            .iter()
+            .zip(clusters)
+            .map(|(table, clusters)| LayoutNode {
+                width: table.width,
+                height: table.height,
+                clusters,
+            })
+            .collect::<Vec<_>>();
+
+        // edges between tables missing from the graph are dropped, edges from or to cells
+        // missing from their tables are attached to the table titles
+        let edges = edges
+            .iter()
+            .filter_map(|edge| {
+                let (from, to) = (*index.get(&edge.from.0)?, *index.get(&edge.to.0)?);
+                Some((
+                    edge,
+                    LayoutEdge {
+                        from,
+                        to,
+                        from_port: boxes[from].port((edge.from.1, edge.from.2)),
+                        to_port: boxes[to].port((edge.to.1, edge.to.2)),
+                    },
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let layout = layout(
+            &nodes,
+            &edges.iter().map(|(_, e)| e.clone()).collect::<Vec<_>>(),
+            &SPACING,
+        );
+
+        let routes = edges
+            .iter()
+            .zip(&layout.edges)
+            .map(|((_, edge), points)| match points.is_empty() {
+                true => self_loop(layout.nodes[edge.from], &boxes[edge.from], edge),
+                false => points.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let node_rects = boxes
+            .iter()
+            .zip(&layout.nodes)
+            .map(|(table, corner)| Rect {
+                x: corner.x,
+                y: corner.y,
+                width: table.width,
+                height: table.height,
+            })
+            .collect::<Vec<_>>();
+        let mut cluster_rects = vec![];
+        cluster_bounds(subgraphs, &index, &node_rects, &mut cluster_rects);
+
+        let bounds = node_rects
+            .iter()
+            .chain(cluster_rects.iter().map(|(_, rect)| rect))
+            .copied()
+            .chain(routes.iter().flatten().map(|p| Rect {
+                x: p.x,
+                y: p.y,
+                width: 0.0,
+                height: 0.0,
+            }))
+            .reduce(Rect::union)
+            .unwrap_or(Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 0.0,
+                height: 0.0,
+            });
+        let (width, height) = (bounds.width + 2.0 * MARGIN, bounds.height + 2.0 * MARGIN);
+
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.1}" height="{h:.1}" viewBox="0 0 {w:.1} {h:.1}">
+<g id="graph0" class="graph" transform="translate({x:.1} {y:.1})" font-family="Arial" font-size="{font}">
+<polygon fill="{bg}" stroke="none" points="{l:.1},{t:.1} {r:.1},{t:.1} {r:.1},{b:.1} {l:.1},{b:.1}"/>
+"#,
+            w = width,
+            h = height,
+            x = MARGIN - bounds.x,
+            y = MARGIN - bounds.y,
+            font = FONT_SIZE,
+            bg = BG_COLOR,
+            l = bounds.x - MARGIN,
+            t = bounds.y - MARGIN,
+            r = bounds.x + bounds.width + MARGIN,
+            b = bounds.y + bounds.height + MARGIN,
+        );
+
+        for (title, rect) in &cluster_rects {
+            write_cluster(&mut svg, title, rect);
+        }
+        for (table, corner) in boxes.iter().zip(&layout.nodes) {
+            write_table(&mut svg, table, *corner, &ids);
+        }
+        for ((edge, _), points) in edges.iter().zip(&routes) {
+            self.write_edge(&mut svg, edge, points, &ids);
+        }
+
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+}
+
+impl SvgRenderer<'_> {
+    fn write_edge(
+        &self,
+        svg: &mut String,
+        edge: &Edge,
+        points: &[Point],
+        ids: &HashMap<(u32, u32, u32), SymbolId>,
+    ) {
+        let from = cell_id(ids, edge.from, ':');
+        let to = cell_id(ids, edge.to, ':');
+
+        let classes = iter_classes(edge.classes)
+            .map(|class| format!(" {}", class))
+            .collect::<String>();
+        let call_sites = match edge.call_sites.is_empty() {
+            true => String::new(),
+            false => format!(
+                r#" data-callsites="{}""#,
+                edge.call_sites
+                    .iter()
+                    .map(|range| format!("{}:{}", range.start.line, range.start.character))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        };
+        let _ = writeln!(
+            svg,
+            r#"<g id="{from} -> {to}" class="edge{classes}" data-from="{from}" data-to="{to}"{call_sites}>"#,
+        );
+        let _ = writeln!(svg, "<title>{}-&gt;{}</title>", from, to);
+
+        let color = edge_color(edge.classes).unwrap_or(EDGE_COLOR);
+        let count = edge.call_count();
+        let width = match count > 1 {
+            true => self.options.edge_weighting.scale(count),
+            false => None,
+        }
+        .map_or(1.0, |scale| scale.min(MAX_PENWIDTH));
+        let dash = match edge.classes.contains(CssClass::Reference) {
+            true => r#" stroke-dasharray="2,4""#,
+            false => "",
+        };
+
+        let (end, tip) = (points[points.len() - 2], points[points.len() - 1]);
+        let direction = if tip.x >= end.x { 1.0 } else { -1.0 };
+        let base = Point {
+            x: tip.x - direction * ARROW_LENGTH,
+            y: tip.y,
+        };
+        let mut line = points.to_vec();
+        *line.last_mut().unwrap() = base;
+
+        let _ = writeln!(
+            svg,
+            r#"<path fill="none" stroke="{}" stroke-width="{:.2}"{} d="{}"/>"#,
+            color,
+            width,
+            dash,
+            self.path(&line),
+        );
+        // hollow triangles for inheritance, as in UML class diagrams
+        let fill = match edge.classes.contains(CssClass::Inherit) {
+            true => BG_COLOR,
+            false => color,
+        };
+        let _ = writeln!(
+            svg,
+            r#"<polygon fill="{fill}" stroke="{color}" points="{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}"/>"#,
+            tip.x,
+            tip.y,
+            base.x,
+            base.y - ARROW_HALF_WIDTH,
+            base.x,
+            base.y + ARROW_HALF_WIDTH,
+        );
+
+        if self.options.call_count_labels && count > 1 {
+            let middle = points[points.len() / 2];
+            let _ = writeln!(
+                svg,
+                r#"<text text-anchor="middle" x="{:.1}" y="{:.1}" fill="{}">{}</text>"#,
+                middle.x,
+                middle.y - 4.0,
+                SYMBOL_DEFAULT_TEXT_COLOR,
+                count,
+            );
+        }
+
+        svg.push_str("</g>\n");
+    }
+
+    fn path(&self, points: &[Point]) -> String {
+        let mut d = format!("M{:.1},{:.1}", points[0].x, points[0].y);
+        for pair in points.windows(2) {
+            let (p, q) = (pair[0], pair[1]);
+            let middle = (p.x + q.x) / 2.0;
+            let _ = match self.routing {
+                EdgeRouting::Spline => write!(
+                    d,
+                    " C{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}",
+                    middle, p.y, middle, q.y, q.x, q.y
+                ),
+                EdgeRouting::Orthogonal if p.y != q.y => write!(
+                    d,
+                    " L{:.1},{:.1} L{:.1},{:.1} L{:.1},{:.1}",
+                    middle, p.y, middle, q.y, q.x, q.y
+                ),
+                EdgeRouting::Orthogonal => write!(d, " L{:.1},{:.1}", q.x, q.y),
+            };
+        }
+        d
+    }
+}
+
+impl TableBox<'_> {
+    /// Port of the cell starting at `start`, or of the title if there is no such cell.
+    fn port(&self, start: (u32, u32)) -> Port {
+        self.ports.get(&start).copied().unwrap_or(Port {
+            y: CELL_SPACING + TITLE_HEIGHT / 2.0,
+            left: 0.0,
+            right: self.width,
+        })
+    }
+}
+
+impl Rect {
+    fn union(self, other: Rect) -> Rect {
+        let (x, y) = (self.x.min(other.x), self.y.min(other.y));
+        Rect {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+fn text_width(text: &str) -> f64 {
+    text.chars()
+        .map(|c| if c.is_ascii() { 1.0 } else { 2.0 })
+        .sum::<f64>()
+        * CHAR_WIDTH
+}
+
+fn title_width(cell: &Cell) -> f64 {
+    let icon = cell.style.icon.map_or(0.0, |_| ICON_WIDTH);
+    icon + text_width(&cell.title) + 2.0 * CELL_PADDING
+}
+
+/// Width the cell needs to show its title and its children.
+fn natural_width(cell: &Cell) -> f64 {
+    cell.children
+        .iter()
+        .map(|child| natural_width(child) + 2.0 * CELL_SPACING)
+        .fold(title_width(cell), f64::max)
+}
+
+fn place_cell(cell: &Cell, x: f64, y: f64, width: f64) -> CellBox<'_> {
+    if cell.children.is_empty() {
+        return CellBox {
+            cell,
+            x,
+            y,
+            width,
+            height: ROW_HEIGHT,
+            title_y: y + ROW_HEIGHT / 2.0,
+            children: vec![],
+        };
+    }
+
+    let mut bottom = y + CELL_SPACING + ROW_HEIGHT;
+    let children = cell
+        .children
+        .iter()
+        .map(|child| {
+            let placed = place_cell(
+                child,
+                x + CELL_SPACING,
+                bottom + CELL_SPACING,
+                width - 2.0 * CELL_SPACING,
+            );
+            bottom = placed.y + placed.height;
+            placed
+        })
+        .collect();
+
+    CellBox {
+        cell,
+        x,
+        y,
+        width,
+        height: bottom + CELL_SPACING - y,
+        title_y: y + CELL_SPACING + ROW_HEIGHT / 2.0,
+        children,
+    }
+}
+
+fn table_box(table: &TableNode) -> TableBox<'_> {
+    let inner_width = table.sections.iter().map(natural_width).fold(
+        TABLE_MIN_WIDTH.max(text_width(&table.title) + 2.0 * CELL_PADDING),
+        f64::max,
+    );
+
+    let mut bottom = CELL_SPACING + TITLE_HEIGHT;
+    let cells = table
+        .sections
+        .iter()
+        .map(|cell| {
+            let placed = place_cell(cell, CELL_SPACING, bottom + CELL_SPACING, inner_width);
+            bottom = placed.y + placed.height;
+            placed
+        })
+        .collect::<Vec<_>>();
+
+    let mut ports = HashMap::new();
+    add_ports(&cells, &mut ports);
+
+    TableBox {
+        table,
+        width: inner_width + 2.0 * CELL_SPACING,
+        height: bottom + CELL_SPACING,
+        cells,
+        ports,
+    }
+}
+
+fn add_ports(cells: &[CellBox], ports: &mut HashMap<(u32, u32), Port>) {
+    for cell in cells {
+        ports.insert(
+            cell.cell.range_start,
+            Port {
+                y: cell.title_y,
+                left: cell.x,
+                right: cell.x + cell.width,
+            },
+        );
+        add_ports(&cell.children, ports);
+    }
+}
+
+/// Clusters containing each table, outermost first, numbering the subgraphs depth first.
+fn assign_clusters(
+    subgraphs: &[Subgraph],
+    index: &HashMap<u32, usize>,
+    path: &mut Vec<usize>,
+    next: &mut usize,
+    clusters: &mut [Vec<usize>],
+) {
+    for subgraph in subgraphs {
+        path.push(*next);
+        *next += 1;
+
+        for node in &subgraph.nodes {
+            if let Some(&i) = node.parse().ok().and_then(|id: u32| index.get(&id)) {
+                clusters[i] = path.clone();
+            }
+        }
+        assign_clusters(&subgraph.subgraphs, index, path, next, clusters);
+
+        path.pop();
+    }
+}
+
+/// Bounds of the clusters, parents before their children, as the padded bounds of their
+/// tables and nested clusters.
+fn cluster_bounds(
+    subgraphs: &[Subgraph],
+    index: &HashMap<u32, usize>,
+    tables: &[Rect],
+    bounds: &mut Vec<(String, Rect)>,
+) -> Option<Rect> {
+    subgraphs
+        .iter()
+        .filter_map(|subgraph| {
+            let position = bounds.len();
+            let nested = cluster_bounds(&subgraph.subgraphs, index, tables, bounds);
+
+            let content = subgraph
+                .nodes
+                .iter()
+                .filter_map(|node| index.get(&node.parse::<u32>().ok()?))
+                .map(|&i| tables[i])
+                .chain(nested)
+                .reduce(Rect::union)?;
+            let rect = Rect {
+                x: content.x - CLUSTER_PADDING,
+                y: content.y - CLUSTER_PADDING - CLUSTER_LABEL_HEIGHT,
+                width: content.width + 2.0 * CLUSTER_PADDING,
+                height: content.height + 2.0 * CLUSTER_PADDING + CLUSTER_LABEL_HEIGHT,
+            };
+            bounds.insert(position, (subgraph.title.clone(), rect));
+            Some(rect)
+        })
+        .reduce(Rect::union)
+}
+
+/// Route of an edge between two cells of the same table, going around its right side.
+fn self_loop(corner: Point, table: &TableBox, edge: &LayoutEdge) -> Vec<Point> {
+    let right = corner.x + table.width;
+    let (from, to) = (corner.y + edge.from_port.y, corner.y + edge.to_port.y);
+    // an edge from a cell to itself still needs some height
+    let (from, to) = match from == to {
+        true => (from - ARROW_HALF_WIDTH, to + ARROW_HALF_WIDTH),
+        false => (from, to),
+    };
+
+    vec![
+        Point {
+            x: corner.x + edge.from_port.right,
+            y: from,
+        },
+        Point {
+            x: right + LOOP_WIDTH,
+            y: from,
+        },
+        Point {
+            x: right + LOOP_WIDTH,
+            y: to,
+        },
+        Point {
+            x: corner.x + edge.to_port.right,
+            y: to,
+        },
+    ]
+}
+
+fn iter_classes(classes: EnumSet<CssClass>) -> impl Iterator<Item = &'static str> {
+    classes.iter().map(|class| class.to_str())
+}
+
+fn write_cluster(svg: &mut String, title: &str, rect: &Rect) {
+    let title = escape_html(title);
+    let _ = writeln!(
+        svg,
+        r#"<g id="cluster_{title}" class="cluster">
+<title>cluster_{title}</title>
+<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" rx="4" fill="{}" stroke="{}"/>
+<text text-anchor="middle" x="{:.1}" y="{:.1}" fill="{}">{title}</text>
+</g>"#,
+        rect.x,
+        rect.y,
+        rect.width,
+        rect.height,
+        CLUSTER_LABEL_BG_COLOR,
+        SYMBOL_DEFAULT_BORDER_COLOR,
+        rect.x + rect.width / 2.0,
+        rect.y + CLUSTER_PADDING + FONT_SIZE * 0.75,
+        SYMBOL_DEFAULT_TEXT_COLOR,
+    );
+}
+
+fn write_table(
+    svg: &mut String,
+    table: &TableBox,
+    corner: Point,
+    ids: &HashMap<(u32, u32, u32), SymbolId>,
+) {
+    let id = table.table.id;
+    let path = table.table.path.as_deref().map_or(String::new(), |path| {
+        format!(r#" data-path="{}""#, escape_html(path))
+    });
+    let _ = writeln!(
+        svg,
+        r#"<g id="{id}" class="node"{path}>
+<title>{id}</title>
+<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" rx="8" fill="{}" stroke="{}"/>
+<g class="title">
+<text text-anchor="middle" x="{:.1}" y="{:.1}" fill="{}">{}</text>
+</g>"#,
+        corner.x,
+        corner.y,
+        table.width,
+        table.height,
+        NODE_BG_COLOR,
+        SYMBOL_DEFAULT_BORDER_COLOR,
+        corner.x + table.width / 2.0,
+        corner.y + CELL_SPACING + TITLE_HEIGHT / 2.0 + FONT_SIZE * 0.35,
+        SYMBOL_DEFAULT_TEXT_COLOR,
+        escape_html(&table.table.title),
+    );
+
+    for cell in &table.cells {
+        write_cell(svg, id, cell, corner, ids);
+    }
+    svg.push_str("</g>\n");
+}
+
+fn write_cell(
+    svg: &mut String,
+    table_id: u32,
+    cell: &CellBox,
+    corner: Point,
+    ids: &HashMap<(u32, u32, u32), SymbolId>,
+) {
+    let style = &cell.cell.style;
+    let classes = std::iter::once("cell")
+        .chain(iter_classes(style.classes - CssClass::Cell))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let kind = cell.cell.symbol_kind.map_or(String::new(), |kind| {
+        format!(r#" data-kind="{}""#, kind as u8)
+    });
+    let id = cell_id(
+        ids,
+        (table_id, cell.cell.range_start.0, cell.cell.range_start.1),
+        ':',
+    );
+    let _ = writeln!(svg, r#"<g id="{}" class="{}"{}>"#, id, classes, kind);
+
+    let fill = match (&style.bg_color, cell.children.is_empty()) {
+        (Some(color), _) => color.as_str(),
+        (None, true) => "none",
+        (None, false) => FUNCTION_BG_COLOR,
+    };
+    let stroke = match style.border {
+        Some(0) => r#"stroke="none""#.to_string(),
+        border => format!(
+            r#"stroke="{}" stroke-width="{}""#,
+            SYMBOL_DEFAULT_BORDER_COLOR,
+            border.unwrap_or(1)
+        ),
+    };
+    let (x, y) = (corner.x + cell.x, corner.y + cell.y);
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" rx="{}" fill="{}" {}/>"#,
+        x,
+        y,
+        cell.width,
+        cell.height,
+        if style.rounded { 6 } else { 0 },
+        fill,
+        stroke,
+    );
+
+    let baseline = corner.y + cell.title_y + FONT_SIZE * 0.35;
+    let mut text_x = x + CELL_PADDING;
+    if let Some(icon) = style.icon {
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" font-weight="bold" fill="{}">{}</text>"#,
+            text_x,
+            baseline,
+            SYMBOL_DEFAULT_TEXT_COLOR,
+            escape_html(&icon.to_string()),
+        );
+        text_x += ICON_WIDTH;
+    }
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" fill="{}">{}</text>"#,
+        text_x,
+        baseline,
+        SYMBOL_DEFAULT_TEXT_COLOR,
+        escape_html(&cell.cell.title),
+    );
+
+    for child in &cell.children {
+        write_cell(svg, table_id, child, corner, ids);
+    }
+    svg.push_str("</g>\n");
+}
@@ -1,15 +1,39 @@
 //! Graph model data structures for structured call graph representation
 //! This module provides an alternative to DOT-based generation for better frontend interaction
 
+mod cycles;
+mod diff;
+mod index;
+mod metrics;
+mod paths;
+mod query;
+mod search;
+mod subgraph;
+mod symbol_id;
+
+pub use cycles::{CycleNode, CycleReport};
+pub use diff::{DiffRelation, DiffSymbol, GraphDiff};
+pub use metrics::{FileMetrics, GraphMetrics, SymbolMetrics};
+pub use query::{Query, QueryError};
+pub use search::{SearchError, SearchMode};
+pub use symbol_id::SymbolId;
+pub(crate) use cycles::cycle_edges;
+
 use {
     crate::lsp_types,
-    serde::Serialize,
+    index::GraphIndex,
+    serde::{Deserialize, Serialize},
     serde_repr::Serialize_repr,
-    std::hash::{Hash, Hasher},
+    std::{
+        hash::{Hash, Hasher},
+        path::Path,
+        sync::OnceLock,
+    },
+    symbol_id::sibling_keys,
 };
 
 /// Serializable position structure for graph model
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
 pub struct Position {
     pub line: u32,
     pub character: u32,
@@ -24,8 +48,17 @@ impl From<lsp_types::Position> for Position {
     }
 }
 
+impl From<&Position> for lsp_types::Position {
+    fn from(pos: &Position) -> Self {
+        Self {
+            line: pos.line,
+            character: pos.character,
+        }
+    }
+}
+
 /// Serializable range structure for graph model
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
 pub struct Range {
     pub start: Position,
     pub end: Position,
@@ -40,6 +73,15 @@ impl From<lsp_types::Range> for Range {
     }
 }
 
+impl From<&Range> for lsp_types::Range {
+    fn from(range: &Range) -> Self {
+        Self {
+            start: lsp_types::Position::from(&range.start),
+            end: lsp_types::Position::from(&range.end),
+        }
+    }
+}
+
 /// Serializable symbol kind for graph model
 #[derive(Debug, Serialize, Clone, Copy)]
 #[repr(u8)]
@@ -105,11 +147,55 @@ impl From<lsp_types::SymbolKind> for SymbolKind {
     }
 }
 
+impl From<SymbolKind> for lsp_types::SymbolKind {
+    fn from(kind: SymbolKind) -> Self {
+        match kind {
+            SymbolKind::File => lsp_types::SymbolKind::File,
+            SymbolKind::Module => lsp_types::SymbolKind::Module,
+            SymbolKind::Namespace => lsp_types::SymbolKind::Namespace,
+            SymbolKind::Package => lsp_types::SymbolKind::Package,
+            SymbolKind::Class => lsp_types::SymbolKind::Class,
+            SymbolKind::Method => lsp_types::SymbolKind::Method,
+            SymbolKind::Property => lsp_types::SymbolKind::Property,
+            SymbolKind::Field => lsp_types::SymbolKind::Field,
+            SymbolKind::Constructor => lsp_types::SymbolKind::Constructor,
+            SymbolKind::Enum => lsp_types::SymbolKind::Enum,
+            SymbolKind::Interface => lsp_types::SymbolKind::Interface,
+            SymbolKind::Function => lsp_types::SymbolKind::Function,
+            SymbolKind::Variable => lsp_types::SymbolKind::Variable,
+            SymbolKind::Constant => lsp_types::SymbolKind::Constant,
+            SymbolKind::String => lsp_types::SymbolKind::String,
+            SymbolKind::Number => lsp_types::SymbolKind::Number,
+            SymbolKind::Boolean => lsp_types::SymbolKind::Boolean,
+            SymbolKind::Array => lsp_types::SymbolKind::Array,
+            SymbolKind::Object => lsp_types::SymbolKind::Object,
+            SymbolKind::Key => lsp_types::SymbolKind::Key,
+            SymbolKind::Null => lsp_types::SymbolKind::Null,
+            SymbolKind::EnumMember => lsp_types::SymbolKind::EnumMember,
+            SymbolKind::Struct => lsp_types::SymbolKind::Struct,
+            SymbolKind::Event => lsp_types::SymbolKind::Event,
+            SymbolKind::Operator => lsp_types::SymbolKind::Operator,
+            SymbolKind::TypeParameter => lsp_types::SymbolKind::TypeParameter,
+        }
+    }
+}
+
 /// Main graph structure containing files and their relationships
 #[derive(Debug, Serialize, Clone)]
 pub struct Graph {
+    /// Changes through `files_mut` drop the indexes, direct changes must be followed by `reindex`
     pub files: Vec<File>,
+    /// Changes through `relations_mut` drop the indexes, direct changes must be followed by
+    /// `reindex`
     pub relations: Vec<Relation>,
+    /// Functions and methods without callers, see `GraphGenerator::find_dead_code`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unreferenced: Vec<GlobalPosition>,
+    /// Directory the paths in the symbol ids and in diffs are relative to
+    #[serde(skip)]
+    root: String,
+    #[serde(skip)]
+    index: OnceLock<GraphIndex>,
 }
 
 /// Represents a source file with its symbols
@@ -128,26 +214,36 @@ pub struct Symbol {
     pub range: Range,
     pub children: Vec<Symbol>,
     pub global_position: GlobalPosition,
+    /// Identifier that does not change when the symbol moves within its file
+    pub id: SymbolId,
 }
 
 /// Represents a relationship between two symbols
 #[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Relation {
     pub from: GlobalPosition,
     pub to: GlobalPosition,
     pub kind: RelationKind,
+    /// Ranges in the caller's file where the calls happen, empty for non-call relations
+    pub call_sites: Vec<Range>,
+    /// Number of distinct call sites, at least 1 for calls whose call sites are unknown
+    pub call_count: usize,
 }
 
 impl Hash for Relation {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.from.hash(state);
         self.to.hash(state);
+        (self.kind.clone() as u8).hash(state);
     }
 }
 
 impl PartialEq for Relation {
     fn eq(&self, other: &Self) -> bool {
-        self.from == other.from && self.to == other.to
+        self.from == other.from
+            && self.to == other.to
+            && self.kind.clone() as u8 == other.kind.clone() as u8
     }
 }
 
@@ -160,10 +256,24 @@ pub enum RelationKind {
     Call = 1,
     Impl = 2,
     Inherit = 3,
+    Reference = 4,
+}
+
+/// Level of detail of a graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    /// One node per symbol, grouped in a table per file
+    #[default]
+    Symbol,
+    /// One node per file
+    File,
+    /// One node per directory
+    Directory,
 }
 
 /// Global position that uniquely identifies a symbol across files
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GlobalPosition {
     pub file_id: u32,
@@ -189,25 +299,44 @@ impl GlobalPosition {
 pub struct GraphBuilder {
     files: Vec<File>,
     relations: Vec<Relation>,
+    /// Index of each relation in `relations` by its ends and kind
+    relation_indices: std::collections::HashMap<(GlobalPosition, GlobalPosition, u8), usize>,
     file_id_map: std::collections::HashMap<String, u32>,
     next_file_id: u32,
+    /// Directory the paths in the symbol ids are relative to
+    root: String,
 }
 
 impl GraphBuilder {
     pub fn new() -> Self {
+        Self::with_root("")
+    }
+
+    /// A builder whose symbol ids use the paths of the files relative to `root`, so that they
+    /// are the same in every checkout of a project.
+    pub fn with_root(root: &str) -> Self {
         Self {
             files: Vec::new(),
             relations: Vec::new(),
+            relation_indices: std::collections::HashMap::new(),
             file_id_map: std::collections::HashMap::new(),
             next_file_id: 1,
+            root: root.to_string(),
         }
     }
     
     pub fn add_file(&mut self, path: String, symbols: Vec<crate::lsp_types::DocumentSymbol>) -> u32 {
         let file_id = self.next_file_id;
-        self.next_file_id += 1;
+        self.add_file_with_id(file_id, path, symbols);
+        file_id
+    }
+    
+    /// Adds a file with an id assigned by the caller, so that relations built from the same ids stay consistent
+    pub fn add_file_with_id(&mut self, file_id: u32, path: String, symbols: Vec<crate::lsp_types::DocumentSymbol>) {
+        self.next_file_id = self.next_file_id.max(file_id + 1);
         
-        let graph_symbols = self.convert_symbols(file_id, &symbols);
+        let relative = relative_path(&self.root, &path);
+        let graph_symbols = self.convert_symbols(file_id, &format!("{}#", relative), &symbols);
         
         let file = File {
             id: file_id,
@@ -217,45 +346,99 @@ impl GraphBuilder {
         
         self.files.push(file);
         self.file_id_map.insert(path, file_id);
-        
-        file_id
     }
     
+    /// Adds a relation unless there is already one of the same kind between the same symbols
     pub fn add_relation(&mut self, from: GlobalPosition, to: GlobalPosition, kind: RelationKind) {
-        let relation = Relation { from, to, kind };
-        if !self.relations.contains(&relation) {
-            self.relations.push(relation);
-        }
+        self.relation_index(from, to, kind);
+    }
+
+    /// Index of the relation of `kind` from `from` to `to`, which is added if missing
+    fn relation_index(&mut self, from: GlobalPosition, to: GlobalPosition, kind: RelationKind) -> usize {
+        let relations = &mut self.relations;
+        *self
+            .relation_indices
+            .entry((from, to, kind.clone() as u8))
+            .or_insert_with(|| {
+                relations.push(Relation {
+                    from,
+                    to,
+                    kind,
+                    call_sites: Vec::new(),
+                    call_count: 0,
+                });
+                relations.len() - 1
+            })
+    }
+
+    /// Adds a call relation, merging the call sites into the existing call between the same symbols
+    pub fn add_call_relation(
+        &mut self,
+        from: GlobalPosition,
+        to: GlobalPosition,
+        call_sites: &[lsp_types::Range],
+    ) {
+        let index = self.relation_index(from, to, RelationKind::Call);
+        self.relations[index]
+            .call_sites
+            .extend(call_sites.iter().map(|range| Range::from(*range)));
     }
     
-    pub fn build(self) -> Graph {
-        Graph {
-            files: self.files,
-            relations: self.relations,
+    /// Builds the graph, with the files sorted by id and the relations by their ends, so that
+    /// the graph does not depend on the order things were added in. The call sites of each call
+    /// are sorted and deduplicated here rather than on every `add_call_relation`.
+    pub fn build(mut self) -> Graph {
+        for relation in &mut self.relations {
+            if let RelationKind::Call = relation.kind {
+                relation.call_sites.sort_by_key(|range| {
+                    (range.start.line, range.start.character, range.end.line, range.end.character)
+                });
+                relation.call_sites.dedup();
+                relation.call_count = relation.call_sites.len().max(1);
+            }
         }
+
+        self.files.sort_by_key(|file| file.id);
+        self.relations
+            .sort_by_key(|relation| (relation.from, relation.to, relation.kind.clone() as u8));
+        let mut graph = Graph::new(self.files, self.relations);
+        graph.root = self.root;
+        graph
     }
     
     pub fn get_file_id(&self, path: &str) -> Option<u32> {
         self.file_id_map.get(path).copied()
     }
     
-    fn convert_symbols(&self, file_id: u32, symbols: &[crate::lsp_types::DocumentSymbol]) -> Vec<Symbol> {
-        symbols.iter().map(|symbol| self.convert_symbol(file_id, symbol)).collect()
+    /// Converts the symbols of a file or the children of a symbol, whose ids start with `parent`
+    fn convert_symbols(&self, file_id: u32, parent: &str, symbols: &[crate::lsp_types::DocumentSymbol]) -> Vec<Symbol> {
+        symbols
+            .iter()
+            .zip(sibling_keys(parent, symbols))
+            .map(|(symbol, key)| self.convert_symbol(file_id, &key, symbol))
+            .collect()
     }
     
-    fn convert_symbol(&self, file_id: u32, symbol: &lsp_types::DocumentSymbol) -> Symbol {
+    fn convert_symbol(&self, file_id: u32, key: &str, symbol: &lsp_types::DocumentSymbol) -> Symbol {
         let global_position = GlobalPosition::from_range_start(file_id, &symbol.selection_range);
         
         Symbol {
             name: symbol.name.clone(),
             kind: SymbolKind::from(symbol.kind),
             range: Range::from(symbol.range),
-            children: self.convert_symbols(file_id, &symbol.children),
+            children: self.convert_symbols(file_id, &format!("{}::", key), &symbol.children),
             global_position,
+            id: SymbolId::from_key(key),
         }
     }
 }
 
+/// `path` relative to `root`, or `path` itself if it is not under `root`
+pub(crate) fn relative_path(root: &str, path: &str) -> String {
+    let path = Path::new(path);
+    path.strip_prefix(root).unwrap_or(path).to_string_lossy().into_owned()
+}
+
 impl Default for GraphBuilder {
     fn default() -> Self {
         Self::new()
@@ -264,104 +447,58 @@ impl Default for GraphBuilder {
 
 /// Search functionality for Graph model
 impl Graph {
-    /// Search for symbols by name with optional filters
+    /// Search for symbols whose name contains the query, best matches first
     pub fn search_symbols(&self, query: &str, case_sensitive: bool) -> Vec<SearchResult> {
-        let mut results = Vec::new();
-        let search_query = if case_sensitive { query.to_string() } else { query.to_lowercase() };
-        
-        for file in &self.files {
-            self.search_symbols_in_file(file, &search_query, case_sensitive, &mut results);
-        }
-        
-        results
+        self.search_symbols_with_mode(query, SearchMode::Substring, case_sensitive)
+            .unwrap_or_default()
     }
     
     /// Search for symbols by type
     pub fn search_by_symbol_kind(&self, kind: SymbolKind) -> Vec<SearchResult> {
-        let mut results = Vec::new();
-        
-        for file in &self.files {
-            self.search_by_kind_in_file(file, kind, &mut results);
-        }
-        
-        results
+        self.symbols_of_kind(kind as u8)
+            .iter()
+            .filter_map(|position| {
+                let file = self.get_file(position.file_id)?;
+                let ancestry = self.symbol_ancestry(*position)?;
+                let qualified_name = ancestry.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join("::");
+                Some(SearchResult::new(file, ancestry.last()?, qualified_name, MatchType::SymbolKind, 0))
+            })
+            .collect()
     }
     
-    /// Search for files by path
+    /// Search for files by path, the files whose name matches coming first
     pub fn search_files(&self, query: &str, case_sensitive: bool) -> Vec<FileSearchResult> {
         let search_query = if case_sensitive { query.to_string() } else { query.to_lowercase() };
         
-        self.files.iter()
-            .filter(|file| {
+        let mut results = self.files.iter()
+            .filter_map(|file| {
                 let file_path = if case_sensitive { file.path.clone() } else { file.path.to_lowercase() };
-                file_path.contains(&search_query)
+                let file_name = file_path.rsplit('/').next().unwrap_or_default();
+                let match_type = if file_name.contains(&search_query) {
+                    FileMatchType::Name
+                } else if file_path.contains(&search_query) {
+                    FileMatchType::Path
+                } else {
+                    return None;
+                };
+                Some(FileSearchResult {
+                    file_id: file.id,
+                    file_path: file.path.clone(),
+                    match_type,
+                })
             })
-            .map(|file| FileSearchResult {
-                file_id: file.id,
-                file_path: file.path.clone(),
-                match_type: FileMatchType::Path,
-            })
-            .collect()
+            .collect::<Vec<_>>();
+        results.sort_by_key(|result| !matches!(result.match_type, FileMatchType::Name));
+        results
     }
     
     /// Get all relations involving a specific symbol
     pub fn get_symbol_relations(&self, global_pos: GlobalPosition) -> Vec<&Relation> {
-        self.relations.iter()
-            .filter(|relation| relation.from == global_pos || relation.to == global_pos)
+        self.relation_indices(global_pos)
+            .into_iter()
+            .map(|i| &self.relations[i])
             .collect()
     }
-    
-    fn search_symbols_in_file(&self, file: &File, query: &str, case_sensitive: bool, results: &mut Vec<SearchResult>) {
-        for symbol in &file.symbols {
-            self.search_symbol_recursive(file, symbol, query, case_sensitive, results);
-        }
-    }
-    
-    fn search_symbol_recursive(&self, file: &File, symbol: &Symbol, query: &str, case_sensitive: bool, results: &mut Vec<SearchResult>) {
-        let symbol_name = if case_sensitive { symbol.name.clone() } else { symbol.name.to_lowercase() };
-        
-        if symbol_name.contains(query) {
-            results.push(SearchResult {
-                file_id: file.id,
-                file_path: file.path.clone(),
-                symbol_name: symbol.name.clone(),
-                symbol_kind: symbol.kind,
-                global_position: symbol.global_position,
-                match_type: MatchType::SymbolName,
-                range: symbol.range.clone(),
-            });
-        }
-        
-        // Search in child symbols
-        for child in &symbol.children {
-            self.search_symbol_recursive(file, child, query, case_sensitive, results);
-        }
-    }
-    
-    fn search_by_kind_in_file(&self, file: &File, target_kind: SymbolKind, results: &mut Vec<SearchResult>) {
-        for symbol in &file.symbols {
-            self.search_by_kind_recursive(file, symbol, target_kind, results);
-        }
-    }
-    
-    fn search_by_kind_recursive(&self, file: &File, symbol: &Symbol, target_kind: SymbolKind, results: &mut Vec<SearchResult>) {
-        if std::mem::discriminant(&symbol.kind) == std::mem::discriminant(&target_kind) {
-            results.push(SearchResult {
-                file_id: file.id,
-                file_path: file.path.clone(),
-                symbol_name: symbol.name.clone(),
-                symbol_kind: symbol.kind,
-                global_position: symbol.global_position,
-                match_type: MatchType::SymbolKind,
-                range: symbol.range.clone(),
-            });
-        }
-        
-        // Search in child symbols
-        for child in &symbol.children {
-            self.search_by_kind_recursive(file, child, target_kind, results);
-        }
-    }
 }
 
 /// Search result for symbols
@@ -374,6 +511,26 @@ pub struct SearchResult {
     pub global_position: GlobalPosition,
     pub match_type: MatchType,
     pub range: Range,
+    /// Name prefixed with the names of the containing symbols, such as `Type::method`
+    pub qualified_name: String,
+    /// Relevance of the match, higher is better
+    pub score: i64,
+}
+
+impl SearchResult {
+    fn new(file: &File, symbol: &Symbol, qualified_name: String, match_type: MatchType, score: i64) -> Self {
+        Self {
+            file_id: file.id,
+            file_path: file.path.clone(),
+            symbol_name: symbol.name.clone(),
+            symbol_kind: symbol.kind,
+            global_position: symbol.global_position,
+            match_type,
+            range: symbol.range.clone(),
+            qualified_name,
+            score,
+        }
+    }
 }
 
 /// Search result for files
@@ -390,6 +547,7 @@ pub enum MatchType {
     SymbolName,
     SymbolKind,
     FilePath,
+    QualifiedName,
 }
 
 /// Type of file match
@@ -0,0 +1,193 @@
+//! Strongly connected components of the graph, to find recursion and circular dependencies.
+
+use {
+    super::{GlobalPosition, Granularity, Graph},
+    serde::Serialize,
+    std::{
+        collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
+        hash::Hash,
+        path::Path,
+    },
+};
+
+/// A node of a cycle, depending on the granularity of the report
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(untagged)]
+pub enum CycleNode {
+    Symbol(GlobalPosition),
+    File(u32),
+    Directory(String),
+}
+
+/// Strongly connected components that form cycles, i.e. those with more than one node or
+/// with a node depending on itself
+#[derive(Debug, Clone, Serialize)]
+pub struct CycleReport {
+    pub granularity: Granularity,
+    pub components: Vec<Vec<CycleNode>>,
+}
+
+/// Tarjan's algorithm, iterative so that long call chains don't overflow the stack.
+///
+/// Components are returned in reverse topological order, with their nodes sorted.
+pub(crate) fn strongly_connected_components<N>(edges: &[(N, N)]) -> Vec<Vec<N>>
+where
+    N: Copy + Eq + Hash + Ord,
+{
+    let mut successors: BTreeMap<N, Vec<N>> = BTreeMap::new();
+    for &(from, to) in edges {
+        successors.entry(from).or_default().push(to);
+        successors.entry(to).or_default();
+    }
+
+    let mut next_index = 0;
+    let mut index = HashMap::new();
+    let mut lowlink = HashMap::new();
+    let mut stack = vec![];
+    let mut on_stack = HashSet::new();
+    let mut components = vec![];
+
+    for &root in successors.keys() {
+        if index.contains_key(&root) {
+            continue;
+        }
+
+        let mut call_stack = vec![(root, 0)];
+        index.insert(root, next_index);
+        lowlink.insert(root, next_index);
+        next_index += 1;
+        stack.push(root);
+        on_stack.insert(root);
+
+        while let Some((node, i)) = call_stack.last_mut() {
+            let node = *node;
+
+            if let Some(&next) = successors[&node].get(*i) {
+                *i += 1;
+
+                if let Entry::Vacant(entry) = index.entry(next) {
+                    entry.insert(next_index);
+                    lowlink.insert(next, next_index);
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack.insert(next);
+                    call_stack.push((next, 0));
+                } else if on_stack.contains(&next) {
+                    let low = lowlink[&node].min(index[&next]);
+                    lowlink.insert(node, low);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                let low = lowlink[&parent].min(lowlink[&node]);
+                lowlink.insert(parent, low);
+            }
+
+            if lowlink[&node] == index[&node] {
+                let mut component = vec![];
+                while let Some(member) = stack.pop() {
+                    on_stack.remove(&member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+/// Whether each edge lies on a cycle, i.e. both of its ends are in the same cyclic component.
+pub(crate) fn cycle_edges<N>(edges: &[(N, N)]) -> Vec<bool>
+where
+    N: Copy + Eq + Hash + Ord,
+{
+    let component_of = strongly_connected_components(edges)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, component)| component.into_iter().map(move |node| (node, i)))
+        .collect::<HashMap<_, _>>();
+
+    edges
+        .iter()
+        .map(|(from, to)| component_of[from] == component_of[to])
+        .collect()
+}
+
+impl Graph {
+    /// Finds the cycles formed by the relations of a symbol-level graph, grouping the symbols
+    /// by file or directory depending on `granularity`.
+    pub fn find_cycles(&self, granularity: Granularity) -> CycleReport {
+        let directories = self
+            .files
+            .iter()
+            .map(|file| {
+                let dir = Path::new(&file.path)
+                    .parent()
+                    .map(|dir| dir.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                (file.id, dir)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let node = |position: &GlobalPosition| match granularity {
+            Granularity::Symbol => Some(CycleNode::Symbol(*position)),
+            Granularity::File => Some(CycleNode::File(position.file_id)),
+            Granularity::Directory => directories
+                .get(&position.file_id)
+                .map(|dir| CycleNode::Directory(dir.clone())),
+        };
+
+        let nodes = self
+            .relations
+            .iter()
+            .filter_map(|relation| Some((node(&relation.from)?, node(&relation.to)?)))
+            .collect::<Vec<_>>();
+
+        // components are computed on indices, since the nodes are not `Copy`
+        let mut ids = HashMap::new();
+        let mut edges = vec![];
+        for (from, to) in &nodes {
+            let next = ids.len();
+            let from_id = *ids.entry(from).or_insert(next);
+            let next = ids.len();
+            let to_id = *ids.entry(to).or_insert(next);
+            edges.push((from_id, to_id));
+        }
+        let self_loops = edges
+            .iter()
+            .filter(|(from, to)| from == to)
+            .map(|(from, _)| *from)
+            .collect::<HashSet<_>>();
+
+        let by_id = ids
+            .into_iter()
+            .map(|(node, id)| (id, node))
+            .collect::<HashMap<_, _>>();
+
+        let mut components = strongly_connected_components(&edges)
+            .into_iter()
+            .filter(|component| component.len() > 1 || self_loops.contains(&component[0]))
+            .map(|component| {
+                let mut component = component
+                    .into_iter()
+                    .map(|id| by_id[&id].clone())
+                    .collect::<Vec<_>>();
+                component.sort();
+                component
+            })
+            .collect::<Vec<_>>();
+        components.sort();
+
+        CycleReport {
+            granularity,
+            components,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Differences between two versions of a graph, such as the graphs of a base branch and of a
+//! pull request.
+
+use {
+    super::{
+        relative_path, search::collect_symbols, GlobalPosition, Graph, RelationKind, SymbolKind,
+    },
+    serde::Serialize,
+    std::collections::{BTreeMap, HashMap},
+};
+
+/// A symbol matched across the two graphs by its file path and qualified name
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSymbol {
+    /// Path of the file relative to the root of the graphs
+    pub path: String,
+    /// Name prefixed with the names of the containing symbols, such as `Type::method`
+    pub qualified_name: String,
+    pub kind: SymbolKind,
+    /// Position in the old graph, `None` for added symbols
+    pub old_position: Option<GlobalPosition>,
+    /// Position in the new graph, `None` for removed symbols
+    pub new_position: Option<GlobalPosition>,
+}
+
+/// A relation matched across the two graphs by its ends and kind
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffRelation {
+    pub from: DiffSymbol,
+    pub to: DiffSymbol,
+    pub kind: RelationKind,
+}
+
+/// Symbols and relations added, removed or kept between two graphs, see `Graph::diff`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphDiff {
+    pub added_symbols: Vec<DiffSymbol>,
+    pub removed_symbols: Vec<DiffSymbol>,
+    pub unchanged_symbols: Vec<DiffSymbol>,
+    pub added_relations: Vec<DiffRelation>,
+    pub removed_relations: Vec<DiffRelation>,
+    pub unchanged_relations: Vec<DiffRelation>,
+}
+
+/// Path relative to the root, qualified name, and the rank of the symbol among those with the
+/// same path and qualified name, such as overloads, in the order they appear in the file
+type SymbolKey = (String, String, usize);
+
+/// Symbols of a graph by key, and the keys by position
+struct Symbols {
+    by_key: BTreeMap<SymbolKey, (GlobalPosition, SymbolKind)>,
+    keys: HashMap<GlobalPosition, SymbolKey>,
+}
+
+impl Symbols {
+    fn new(graph: &Graph) -> Self {
+        let mut symbols = Symbols {
+            by_key: BTreeMap::new(),
+            keys: HashMap::new(),
+        };
+
+        for file in &graph.files {
+            let path = relative_path(&graph.root, &file.path);
+            let mut collected = vec![];
+            collect_symbols(&file.symbols, &mut vec![], &mut collected);
+
+            let mut ranks: HashMap<String, usize> = HashMap::new();
+            for (symbol, qualified_name) in collected {
+                let rank = ranks.entry(qualified_name.clone()).or_default();
+                let key = (path.clone(), qualified_name, *rank);
+                *rank += 1;
+
+                symbols
+                    .by_key
+                    .insert(key.clone(), (symbol.global_position, symbol.kind));
+                symbols.keys.insert(symbol.global_position, key);
+            }
+        }
+
+        symbols
+    }
+
+    /// Relations of `graph` by their ends and kind
+    fn relations(&self, graph: &Graph) -> BTreeMap<(SymbolKey, SymbolKey, u8), RelationKind> {
+        graph
+            .relations
+            .iter()
+            .filter_map(|relation| {
+                let from = self.keys.get(&relation.from)?.clone();
+                let to = self.keys.get(&relation.to)?.clone();
+                Some((
+                    (from, to, relation.kind.clone() as u8),
+                    relation.kind.clone(),
+                ))
+            })
+            .collect()
+    }
+}
+
+impl Graph {
+    /// Compares the graph with a newer version of it. Symbols are matched by file path and
+    /// qualified name rather than by position, since lines shift between versions. Paths are
+    /// taken relative to the roots of the graphs, so that two checkouts of a project in
+    /// different directories can be compared. The symbols and relations of each list are
+    /// sorted by path and name.
+    pub fn diff(&self, new: &Graph) -> GraphDiff {
+        let (old_symbols, new_symbols) = (Symbols::new(self), Symbols::new(new));
+
+        let mut symbols: BTreeMap<SymbolKey, DiffSymbol> = BTreeMap::new();
+        for (key, &(position, kind)) in &old_symbols.by_key {
+            symbols.insert(
+                key.clone(),
+                DiffSymbol {
+                    path: key.0.clone(),
+                    qualified_name: key.1.clone(),
+                    kind,
+                    old_position: Some(position),
+                    new_position: None,
+                },
+            );
+        }
+        for (key, &(position, kind)) in &new_symbols.by_key {
+            let symbol = symbols.entry(key.clone()).or_insert_with(|| DiffSymbol {
+                path: key.0.clone(),
+                qualified_name: key.1.clone(),
+                kind,
+                old_position: None,
+                new_position: None,
+            });
+            symbol.kind = kind;
+            symbol.new_position = Some(position);
+        }
+
+        let mut diff = GraphDiff::default();
+        for symbol in symbols.values() {
+            let list = match (symbol.old_position, symbol.new_position) {
+                (Some(_), Some(_)) => &mut diff.unchanged_symbols,
+                (Some(_), None) => &mut diff.removed_symbols,
+                _ => &mut diff.added_symbols,
+            };
+            list.push(symbol.clone());
+        }
+
+        let old_relations = old_symbols.relations(self);
+        let mut new_relations = new_symbols.relations(new);
+        for (key, kind) in old_relations {
+            let list = match new_relations.remove(&key) {
+                Some(_) => &mut diff.unchanged_relations,
+                None => &mut diff.removed_relations,
+            };
+            list.push(DiffRelation {
+                from: symbols[&key.0].clone(),
+                to: symbols[&key.1].clone(),
+                kind,
+            });
+        }
+        for (key, kind) in new_relations {
+            diff.added_relations.push(DiffRelation {
+                from: symbols[&key.0].clone(),
+                to: symbols[&key.1].clone(),
+                kind,
+            });
+        }
+
+        diff
+    }
+}
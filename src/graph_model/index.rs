@@ -0,0 +1,179 @@
+//! Hash indexes of a `Graph`, so that lookups by position take O(degree) instead of a scan.
+
+use {
+    super::{File, GlobalPosition, Graph, Relation, Symbol, SymbolId},
+    std::collections::HashMap,
+};
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct GraphIndex {
+    /// Index of each file in `Graph::files`
+    files: HashMap<u32, usize>,
+    /// Indices of each symbol, first in `Graph::files` then in the `children` down to it
+    symbols: HashMap<GlobalPosition, (usize, Vec<usize>)>,
+    /// Position of the symbol with each id
+    ids: HashMap<SymbolId, GlobalPosition>,
+    /// Positions of the symbols of each kind, in depth-first order
+    kinds: HashMap<u8, Vec<GlobalPosition>>,
+    /// Indices in `Graph::relations` of the relations starting from each symbol
+    outgoing: HashMap<GlobalPosition, Vec<usize>>,
+    /// Indices in `Graph::relations` of the relations ending at each symbol
+    incoming: HashMap<GlobalPosition, Vec<usize>>,
+}
+
+impl GraphIndex {
+    fn new(files: &[File], relations: &[Relation]) -> Self {
+        let mut index = Self::default();
+
+        for (i, file) in files.iter().enumerate() {
+            index.files.insert(file.id, i);
+            index.add_symbols(i, &mut vec![], &file.symbols);
+        }
+
+        for (i, relation) in relations.iter().enumerate() {
+            index.outgoing.entry(relation.from).or_default().push(i);
+            index.incoming.entry(relation.to).or_default().push(i);
+        }
+
+        index
+    }
+
+    fn add_symbols(&mut self, file: usize, path: &mut Vec<usize>, symbols: &[Symbol]) {
+        for (i, symbol) in symbols.iter().enumerate() {
+            path.push(i);
+            self.symbols
+                .entry(symbol.global_position)
+                .or_insert_with(|| (file, path.clone()));
+            self.ids
+                .entry(symbol.id.clone())
+                .or_insert(symbol.global_position);
+            self.kinds
+                .entry(symbol.kind as u8)
+                .or_default()
+                .push(symbol.global_position);
+            self.add_symbols(file, path, &symbol.children);
+            path.pop();
+        }
+    }
+}
+
+impl Graph {
+    pub fn new(files: Vec<File>, relations: Vec<Relation>) -> Self {
+        Self {
+            files,
+            relations,
+            unreferenced: Vec::new(),
+            root: String::new(),
+            index: Default::default(),
+        }
+    }
+
+    /// Directory the paths of the files are relative to in the symbol ids and in diffs, empty
+    /// for graphs not built by a `GraphBuilder` with a root.
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    pub fn files(&self) -> &[File] {
+        &self.files
+    }
+
+    pub fn relations(&self) -> &[Relation] {
+        &self.relations
+    }
+
+    /// Mutable access to the files, which drops the indexes.
+    pub fn files_mut(&mut self) -> &mut Vec<File> {
+        self.reindex();
+        &mut self.files
+    }
+
+    /// Mutable access to the relations, which drops the indexes.
+    pub fn relations_mut(&mut self) -> &mut Vec<Relation> {
+        self.reindex();
+        &mut self.relations
+    }
+
+    /// Indexes are built on the first lookup and dropped by `files_mut` and `relations_mut`.
+    pub(super) fn index(&self) -> &GraphIndex {
+        self.index
+            .get_or_init(|| GraphIndex::new(&self.files, &self.relations))
+    }
+
+    /// Drops the indexes, to be rebuilt on the next lookup. Needed after changing `files` or
+    /// `relations` directly rather than through `files_mut` and `relations_mut`.
+    pub fn reindex(&mut self) {
+        self.index = Default::default();
+    }
+
+    pub fn get_file(&self, file_id: u32) -> Option<&File> {
+        let i = *self.index().files.get(&file_id)?;
+        Some(&self.files[i])
+    }
+
+    pub fn get_symbol(&self, position: GlobalPosition) -> Option<&Symbol> {
+        self.symbol_ancestry(position)?.pop()
+    }
+
+    /// Id of the symbol at `position`
+    pub fn symbol_id(&self, position: GlobalPosition) -> Option<&SymbolId> {
+        Some(&self.get_symbol(position)?.id)
+    }
+
+    /// Position of the symbol with id `id`
+    pub fn symbol_position(&self, id: &SymbolId) -> Option<GlobalPosition> {
+        self.index().ids.get(id).copied()
+    }
+
+    /// The symbol at `position` preceded by the symbols containing it, outermost first.
+    pub(super) fn symbol_ancestry(&self, position: GlobalPosition) -> Option<Vec<&Symbol>> {
+        let (file, path) = self.index().symbols.get(&position)?;
+        let (first, rest) = path.split_first()?;
+
+        let mut ancestry = vec![&self.files[*file].symbols[*first]];
+        for i in rest {
+            let symbol = &ancestry[ancestry.len() - 1].children[*i];
+            ancestry.push(symbol);
+        }
+        Some(ancestry)
+    }
+
+    /// Relations starting from the symbol at `position`
+    pub fn outgoing_relations(&self, position: GlobalPosition) -> Vec<&Relation> {
+        self.relations_at(self.index().outgoing.get(&position))
+    }
+
+    /// Relations ending at the symbol at `position`
+    pub fn incoming_relations(&self, position: GlobalPosition) -> Vec<&Relation> {
+        self.relations_at(self.index().incoming.get(&position))
+    }
+
+    pub(super) fn symbols_of_kind(&self, kind: u8) -> &[GlobalPosition] {
+        self.index()
+            .kinds
+            .get(&kind)
+            .map_or(&[], |positions| positions.as_slice())
+    }
+
+    /// Indices in `relations` of the relations involving the symbol at `position`, in order.
+    pub(super) fn relation_indices(&self, position: GlobalPosition) -> Vec<usize> {
+        let index = self.index();
+        let mut indices = [&index.outgoing, &index.incoming]
+            .into_iter()
+            .filter_map(|relations| relations.get(&position))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    fn relations_at(&self, indices: Option<&Vec<usize>>) -> Vec<&Relation> {
+        indices
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.relations[i])
+            .collect()
+    }
+}
@@ -0,0 +1,255 @@
+//! Per-symbol and per-file metrics of the call graph, to find god functions and hubs.
+
+use {
+    super::{
+        cycles::strongly_connected_components, GlobalPosition, Graph, RelationKind, Symbol,
+        SymbolKind,
+    },
+    serde::Serialize,
+    std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+};
+
+/// Metrics of a function, method or any other symbol taking part in calls
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolMetrics {
+    pub position: GlobalPosition,
+    /// Number of distinct callers, recursive calls excluded
+    pub fan_in: usize,
+    /// Number of distinct callees, recursive calls excluded
+    pub fan_out: usize,
+    /// Length of the longest call chain from an entry point, counting each cycle as a single
+    /// step, `None` if the symbol can't be reached from any entry point
+    pub depth: Option<usize>,
+    /// Number of shortest call chains between other symbols passing through the symbol
+    pub betweenness: f64,
+}
+
+/// Metrics of a file, aggregated from the ones of its symbols
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMetrics {
+    pub file_id: u32,
+    /// Number of measured symbols in the file
+    pub symbols: usize,
+    /// Number of calls coming from other files
+    pub fan_in: usize,
+    /// Number of calls going to other files
+    pub fan_out: usize,
+    pub max_depth: Option<usize>,
+    pub max_betweenness: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphMetrics {
+    /// Sorted by position
+    pub symbols: Vec<SymbolMetrics>,
+    /// Sorted by file id
+    pub files: Vec<FileMetrics>,
+}
+
+fn is_function(kind: SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::Function | SymbolKind::Method | SymbolKind::Constructor
+    )
+}
+
+fn collect_functions(symbols: &[Symbol], nodes: &mut BTreeSet<GlobalPosition>) {
+    for symbol in symbols {
+        if is_function(symbol.kind) {
+            nodes.insert(symbol.global_position);
+        }
+        collect_functions(&symbol.children, nodes);
+    }
+}
+
+/// Brandes' algorithm on an unweighted directed graph.
+fn betweenness(successors: &[Vec<usize>]) -> Vec<f64> {
+    let n = successors.len();
+    let mut centrality = vec![0.0; n];
+
+    for source in 0..n {
+        let mut stack = vec![];
+        let mut predecessors = vec![vec![]; n];
+        let mut paths = vec![0.0; n];
+        let mut distance = vec![None; n];
+        paths[source] = 1.0;
+        distance[source] = Some(0);
+
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            stack.push(node);
+            let next_distance = distance[node].map(|d: usize| d + 1);
+            for &next in &successors[node] {
+                if distance[next].is_none() {
+                    distance[next] = next_distance;
+                    queue.push_back(next);
+                }
+                if distance[next] == next_distance {
+                    paths[next] += paths[node];
+                    predecessors[next].push(node);
+                }
+            }
+        }
+
+        let mut dependency = vec![0.0; n];
+        while let Some(node) = stack.pop() {
+            for &predecessor in &predecessors[node] {
+                dependency[predecessor] +=
+                    paths[predecessor] / paths[node] * (1.0 + dependency[node]);
+            }
+            if node != source {
+                centrality[node] += dependency[node];
+            }
+        }
+    }
+
+    centrality
+}
+
+/// Longest call chains from `entries` over the condensation of the graph.
+fn depths(n: usize, edges: &[(usize, usize)], entries: &[bool]) -> Vec<Option<usize>> {
+    let components = strongly_connected_components(edges);
+
+    // nodes without calls are in no component
+    let mut component_of = vec![None; n];
+    for (i, component) in components.iter().enumerate() {
+        for &node in component {
+            component_of[node] = Some(i);
+        }
+    }
+
+    let mut successors = vec![vec![]; components.len()];
+    for &(from, to) in edges {
+        if let (Some(from), Some(to)) = (component_of[from], component_of[to]) {
+            if from != to {
+                successors[from].push(to);
+            }
+        }
+    }
+
+    // components come in reverse topological order
+    let mut component_depth = vec![None; components.len()];
+    for i in (0..components.len()).rev() {
+        if components[i].iter().any(|&node| entries[node]) {
+            component_depth[i] = component_depth[i].max(Some(0));
+        }
+        if let Some(depth) = component_depth[i] {
+            for &next in &successors[i] {
+                component_depth[next] = component_depth[next].max(Some(depth + 1));
+            }
+        }
+    }
+
+    (0..n)
+        .map(|node| match component_of[node] {
+            Some(i) => component_depth[i],
+            None => entries[node].then_some(0),
+        })
+        .collect()
+}
+
+impl Graph {
+    /// Computes the metrics of the functions and of the symbols taking part in calls.
+    ///
+    /// Depths are measured from `entry_points`, or from the symbols without callers if empty.
+    pub fn compute_metrics(&self, entry_points: &[GlobalPosition]) -> GraphMetrics {
+        let calls = self
+            .relations
+            .iter()
+            .filter(|relation| matches!(relation.kind, RelationKind::Call))
+            .filter(|relation| relation.from != relation.to)
+            .map(|relation| (relation.from, relation.to))
+            .collect::<BTreeSet<_>>();
+
+        let mut nodes = BTreeSet::new();
+        for file in &self.files {
+            collect_functions(&file.symbols, &mut nodes);
+        }
+        nodes.extend(calls.iter().flat_map(|&(from, to)| [from, to]));
+
+        let nodes = nodes.into_iter().collect::<Vec<_>>();
+        let index = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, position)| (*position, i))
+            .collect::<HashMap<_, _>>();
+        let edges = calls
+            .iter()
+            .map(|(from, to)| (index[from], index[to]))
+            .collect::<Vec<_>>();
+
+        let mut successors = vec![vec![]; nodes.len()];
+        let mut fan_in = vec![0; nodes.len()];
+        for &(from, to) in &edges {
+            successors[from].push(to);
+            fan_in[to] += 1;
+        }
+
+        let entries = if entry_points.is_empty() {
+            fan_in
+                .iter()
+                .map(|&callers| callers == 0)
+                .collect::<Vec<_>>()
+        } else {
+            nodes
+                .iter()
+                .map(|position| entry_points.contains(position))
+                .collect()
+        };
+
+        let depth = depths(nodes.len(), &edges, &entries);
+        let betweenness = betweenness(&successors);
+
+        let symbols = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, position)| SymbolMetrics {
+                position: *position,
+                fan_in: fan_in[i],
+                fan_out: successors[i].len(),
+                depth: depth[i],
+                betweenness: betweenness[i],
+            })
+            .collect::<Vec<_>>();
+
+        let mut files = self
+            .files
+            .iter()
+            .map(|file| {
+                (
+                    file.id,
+                    FileMetrics {
+                        file_id: file.id,
+                        symbols: 0,
+                        fan_in: 0,
+                        fan_out: 0,
+                        max_depth: None,
+                        max_betweenness: 0.0,
+                    },
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        for metrics in &symbols {
+            if let Some(file) = files.get_mut(&metrics.position.file_id) {
+                file.symbols += 1;
+                file.max_depth = file.max_depth.max(metrics.depth);
+                file.max_betweenness = file.max_betweenness.max(metrics.betweenness);
+            }
+        }
+        for (from, to) in calls.iter().filter(|(from, to)| from.file_id != to.file_id) {
+            if let Some(file) = files.get_mut(&from.file_id) {
+                file.fan_out += 1;
+            }
+            if let Some(file) = files.get_mut(&to.file_id) {
+                file.fan_in += 1;
+            }
+        }
+
+        GraphMetrics {
+            symbols,
+            files: files.into_values().collect(),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Call chains between two symbols.
+
+use {
+    super::{GlobalPosition, Graph, RelationKind},
+    std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque},
+};
+
+impl Graph {
+    /// Finds up to `max_paths` call chains of at most `max_len` calls going from `from` to `to`,
+    /// shortest first. Chains never visit a symbol twice, so the only chain from a symbol to
+    /// itself is the empty one.
+    pub fn find_paths(
+        &self,
+        from: GlobalPosition,
+        to: GlobalPosition,
+        max_paths: usize,
+        max_len: usize,
+    ) -> Vec<Vec<GlobalPosition>> {
+        if from == to {
+            return match max_paths {
+                0 => vec![],
+                _ => vec![vec![from]],
+            };
+        }
+
+        let mut callees: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        let mut callers: HashMap<_, Vec<_>> = HashMap::new();
+        for relation in &self.relations {
+            if matches!(relation.kind, RelationKind::Call) && relation.from != relation.to {
+                callees.entry(relation.from).or_default().push(relation.to);
+                callers.entry(relation.to).or_default().push(relation.from);
+            }
+        }
+        callees.values_mut().for_each(|next| next.sort());
+
+        // number of calls from each symbol to `to`, to prune the chains that can't reach it in time
+        let mut distance = HashMap::from([(to, 0)]);
+        let mut queue = VecDeque::from([to]);
+        while let Some(position) = queue.pop_front() {
+            let next_distance = distance[&position] + 1;
+            for caller in callers.get(&position).into_iter().flatten() {
+                if let Entry::Vacant(entry) = distance.entry(*caller) {
+                    entry.insert(next_distance);
+                    queue.push_back(*caller);
+                }
+            }
+        }
+
+        let mut paths = vec![];
+        if max_paths == 0 || distance.get(&from).is_none_or(|&d| d > max_len) {
+            return paths;
+        }
+
+        // a depth-first search per length, so that the chains come out shortest first while only
+        // the current chain is kept in memory
+        for len in distance[&from]..=max_len {
+            chains_of_len(&callees, &distance, from, to, len, max_paths, &mut paths);
+            if paths.len() == max_paths {
+                break;
+            }
+        }
+
+        paths
+    }
+}
+
+/// Appends to `paths` the chains of exactly `len` calls from `from` to `to`, in the order of
+/// `callees`, until there are `max_paths` chains.
+fn chains_of_len(
+    callees: &BTreeMap<GlobalPosition, Vec<GlobalPosition>>,
+    distance: &HashMap<GlobalPosition, usize>,
+    from: GlobalPosition,
+    to: GlobalPosition,
+    len: usize,
+    max_paths: usize,
+    paths: &mut Vec<Vec<GlobalPosition>>,
+) {
+    let mut path = vec![from];
+    let mut on_path = HashSet::from([from]);
+    // index in `callees` of the next callee to try for each symbol of `path`
+    let mut next = vec![0];
+
+    while let Some(&start) = next.last() {
+        let last = *path.last().unwrap();
+        let depth = path.len() - 1;
+
+        if last == to {
+            paths.push(path.clone());
+            if paths.len() == max_paths {
+                return;
+            }
+        } else {
+            let candidates = callees.get(&last).map_or(&[][..], Vec::as_slice);
+            // `to` must be reached with exactly `len` calls, the other symbols early enough to
+            // reach it
+            let found = candidates[start..].iter().position(|callee| {
+                !on_path.contains(callee)
+                    && distance.get(callee).is_some_and(|d| match *callee == to {
+                        true => depth + 1 == len,
+                        false => depth + 1 + d <= len,
+                    })
+            });
+            if let Some(offset) = found {
+                let callee = candidates[start + offset];
+                *next.last_mut().unwrap() = start + offset + 1;
+                path.push(callee);
+                on_path.insert(callee);
+                next.push(0);
+                continue;
+            }
+        }
+
+        on_path.remove(&path.pop().unwrap());
+        next.pop();
+    }
+}
@@ -0,0 +1,277 @@
+//! Filter expressions selecting a part of a graph, such as
+//! `kind:method file:src/net/** calls:send_* -callers:test_*`.
+//!
+//! A query is a list of terms separated by whitespace, which all have to match a symbol for it
+//! to be kept. A term is `key:value`, or a bare value matched against the symbol names, and is
+//! negated by a leading `-`. Terms that don't start with one of the keys below and a single `:`,
+//! such as `Circle::new`, are bare values. The keys are:
+//!
+//! - `name`: the name of the symbol matches the glob
+//! - `kind`: the kind of the symbol is one of the comma-separated kinds, such as `method,function`
+//! - `file`: the path of the file, or a trailing part of it, matches the glob
+//! - `calls`: the symbol calls a symbol whose name matches the glob
+//! - `callers`: the symbol is called by a symbol whose name matches the glob
+//!
+//! In globs, `*` matches any characters but `/`, `**` any characters and `?` a single one.
+
+use {
+    super::{File, GlobalPosition, Graph, Relation, RelationKind, Symbol, SymbolKind},
+    regex::Regex,
+    std::{fmt, str::FromStr},
+};
+
+const KEYS: [&str; 5] = ["name", "kind", "file", "calls", "callers"];
+
+const KIND_NAMES: [(&str, SymbolKind); 26] = [
+    ("file", SymbolKind::File),
+    ("module", SymbolKind::Module),
+    ("namespace", SymbolKind::Namespace),
+    ("package", SymbolKind::Package),
+    ("class", SymbolKind::Class),
+    ("method", SymbolKind::Method),
+    ("property", SymbolKind::Property),
+    ("field", SymbolKind::Field),
+    ("constructor", SymbolKind::Constructor),
+    ("enum", SymbolKind::Enum),
+    ("interface", SymbolKind::Interface),
+    ("function", SymbolKind::Function),
+    ("variable", SymbolKind::Variable),
+    ("constant", SymbolKind::Constant),
+    ("string", SymbolKind::String),
+    ("number", SymbolKind::Number),
+    ("boolean", SymbolKind::Boolean),
+    ("array", SymbolKind::Array),
+    ("object", SymbolKind::Object),
+    ("key", SymbolKind::Key),
+    ("null", SymbolKind::Null),
+    ("enum-member", SymbolKind::EnumMember),
+    ("struct", SymbolKind::Struct),
+    ("event", SymbolKind::Event),
+    ("operator", SymbolKind::Operator),
+    ("type-parameter", SymbolKind::TypeParameter),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    UnknownKind(String),
+    /// A term has nothing after its key or its `-`.
+    EmptyValue(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::UnknownKind(kind) => write!(f, "unknown symbol kind `{}`", kind),
+            QueryError::EmptyValue(term) => write!(f, "missing value in query term `{}`", term),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A parsed query, see the module documentation for the syntax.
+///
+/// Queries are displayed in a normalized form that parses back to the same query, so they can
+/// be saved and shared as strings.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    negated: bool,
+    filter: Filter,
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    Name(Glob),
+    Kind(Vec<SymbolKind>),
+    File(Glob),
+    Calls(Glob),
+    Callers(Glob),
+}
+
+#[derive(Debug, Clone)]
+struct Glob {
+    pattern: String,
+    regex: Regex,
+}
+
+impl Glob {
+    /// With `suffix`, the glob also matches the parts of the text after a `/`.
+    fn new(pattern: &str, suffix: bool) -> Self {
+        let mut regex = String::from(if suffix { "(?:^|/)" } else { "^" });
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push('$');
+
+        Self {
+            pattern: pattern.to_string(),
+            // every special character has been escaped
+            regex: Regex::new(&regex).unwrap(),
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let terms = query
+            .split_whitespace()
+            .map(Term::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    fn matches(&self, graph: &Graph, file: &File, symbol: &Symbol) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.filter.matches(graph, file, symbol) != term.negated)
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, term) in self.terms.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            if term.negated {
+                f.write_str("-")?;
+            }
+            match &term.filter {
+                // `name:` followed by another `:` would be parsed as a bare value
+                Filter::Name(glob) if glob.pattern.starts_with(':') => {
+                    f.write_str(&glob.pattern)?
+                }
+                Filter::Name(glob) => write!(f, "name:{}", glob.pattern)?,
+                Filter::Kind(kinds) => {
+                    let names = kinds
+                        .iter()
+                        .map(|kind| kind_name(*kind))
+                        .collect::<Vec<_>>();
+                    write!(f, "kind:{}", names.join(","))?
+                }
+                Filter::File(glob) => write!(f, "file:{}", glob.pattern)?,
+                Filter::Calls(glob) => write!(f, "calls:{}", glob.pattern)?,
+                Filter::Callers(glob) => write!(f, "callers:{}", glob.pattern)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Term {
+    fn parse(term: &str) -> Result<Self, QueryError> {
+        let (negated, rest) = match term.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, term),
+        };
+        let (key, value) = match rest.split_once(':') {
+            Some((key, value)) if KEYS.contains(&key) && !value.starts_with(':') => (key, value),
+            _ => ("name", rest),
+        };
+        if value.is_empty() {
+            return Err(QueryError::EmptyValue(term.to_string()));
+        }
+
+        let filter = match key {
+            "name" => Filter::Name(Glob::new(value, false)),
+            "kind" => Filter::Kind(
+                value
+                    .split(',')
+                    .map(|name| {
+                        KIND_NAMES
+                            .iter()
+                            .find(|(kind_name, _)| kind_name.eq_ignore_ascii_case(name))
+                            .map(|(_, kind)| *kind)
+                            .ok_or_else(|| QueryError::UnknownKind(name.to_string()))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            "file" => Filter::File(Glob::new(value, true)),
+            "calls" => Filter::Calls(Glob::new(value, false)),
+            "callers" => Filter::Callers(Glob::new(value, false)),
+            key => unreachable!("unknown query key `{}`", key),
+        };
+        Ok(Self { negated, filter })
+    }
+}
+
+impl Filter {
+    fn matches(&self, graph: &Graph, file: &File, symbol: &Symbol) -> bool {
+        let position = symbol.global_position;
+        match self {
+            Filter::Name(glob) => glob.matches(&symbol.name),
+            Filter::Kind(kinds) => kinds.iter().any(|kind| *kind as u8 == symbol.kind as u8),
+            Filter::File(glob) => glob.matches(&file.path),
+            Filter::Calls(glob) => graph
+                .outgoing_relations(position)
+                .into_iter()
+                .any(|relation| is_call_with(graph, relation, relation.to, glob)),
+            Filter::Callers(glob) => graph
+                .incoming_relations(position)
+                .into_iter()
+                .any(|relation| is_call_with(graph, relation, relation.from, glob)),
+        }
+    }
+}
+
+/// Whether `relation` is a call with `other` at its other end, whose name matches `glob`
+fn is_call_with(graph: &Graph, relation: &Relation, other: GlobalPosition, glob: &Glob) -> bool {
+    matches!(relation.kind, RelationKind::Call)
+        && graph
+            .get_symbol(other)
+            .is_some_and(|symbol| glob.matches(&symbol.name))
+}
+
+fn kind_name(kind: SymbolKind) -> &'static str {
+    KIND_NAMES
+        .iter()
+        .find(|(_, k)| *k as u8 == kind as u8)
+        .map_or("", |(name, _)| name)
+}
+
+impl Graph {
+    /// Keeps the symbols matching `query`, see `Graph::subgraph`.
+    pub fn filter(&self, query: &Query) -> Graph {
+        self.subgraph(|file, symbol| query.matches(self, file, symbol))
+    }
+
+    /// Parses `query` and filters the graph with it, see `Graph::filter`.
+    pub fn query(&self, query: &str) -> Result<Graph, QueryError> {
+        Ok(self.filter(&Query::parse(query)?))
+    }
+}
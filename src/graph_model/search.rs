@@ -0,0 +1,343 @@
+//! Symbol search with several matching modes, ranked by how well each symbol matches.
+
+use {
+    super::{Graph, MatchType, SearchResult, Symbol},
+    regex::{Regex, RegexBuilder},
+    std::fmt,
+};
+
+/// How a search query is matched against symbol names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    /// The name contains the query.
+    #[default]
+    Substring,
+    /// The name starts with the query.
+    Prefix,
+    /// The name matches the query as a regular expression.
+    Regex,
+    /// The query characters appear in the name in order, like `gsr` in `get_symbol_relations`.
+    Fuzzy,
+    /// The query is made of prefixes of consecutive camelCase or snake_case words of the name,
+    /// like `getSymRel` or `get_sym_rel` in `getSymbolRelations`.
+    WordPrefix,
+}
+
+#[derive(Debug)]
+pub enum SearchError {
+    InvalidRegex(regex::Error),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::InvalidRegex(e) => write!(f, "invalid regex: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<regex::Error> for SearchError {
+    fn from(e: regex::Error) -> Self {
+        SearchError::InvalidRegex(e)
+    }
+}
+
+const EXACT_SCORE: i64 = 100;
+const PREFIX_SCORE: i64 = 50;
+const BOUNDARY_SCORE: i64 = 25;
+const CONTAINS_SCORE: i64 = 10;
+
+/// Fuzzy bonus for a query character matched at the start of a word
+const WORD_START_BONUS: i64 = 8;
+/// Fuzzy bonus for a query character matched right after the previous one
+const CONSECUTIVE_BONUS: i64 = 5;
+/// Word prefix penalty for each word skipped between two matched ones
+const SKIPPED_WORD_PENALTY: i64 = 10;
+
+impl Graph {
+    /// Searches for symbols matching `query` in the given `mode`, best matches first.
+    ///
+    /// Queries containing `::` are matched against the names qualified with the containing
+    /// symbols, such as `Type::method`, and queries containing `/` against the file paths
+    /// followed by the qualified names.
+    pub fn search_symbols_with_mode(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        case_sensitive: bool,
+    ) -> Result<Vec<SearchResult>, SearchError> {
+        let matcher = Matcher::new(query, mode, case_sensitive)?;
+        let match_type = if query.contains('/') {
+            MatchType::FilePath
+        } else if query.contains("::") {
+            MatchType::QualifiedName
+        } else {
+            MatchType::SymbolName
+        };
+
+        let mut results = vec![];
+        for file in &self.files {
+            let mut symbols = vec![];
+            collect_symbols(&file.symbols, &mut vec![], &mut symbols);
+
+            for (symbol, qualified_name) in symbols {
+                let score = match match_type {
+                    MatchType::FilePath => {
+                        matcher.score(&format!("{}::{}", file.path, qualified_name))
+                    }
+                    MatchType::QualifiedName => matcher.score(&qualified_name),
+                    _ => matcher.score(&symbol.name),
+                };
+                if let Some(score) = score {
+                    results.push(SearchResult::new(
+                        file,
+                        symbol,
+                        qualified_name,
+                        match_type.clone(),
+                        score,
+                    ));
+                }
+            }
+        }
+
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.symbol_name.len().cmp(&b.symbol_name.len()))
+                .then_with(|| a.file_path.cmp(&b.file_path))
+                .then(a.global_position.cmp(&b.global_position))
+        });
+        Ok(results)
+    }
+}
+
+/// Collects the symbols with their names qualified with the containing symbols.
+pub(super) fn collect_symbols<'a>(
+    symbols: &'a [Symbol],
+    containers: &mut Vec<&'a str>,
+    collected: &mut Vec<(&'a Symbol, String)>,
+) {
+    for symbol in symbols {
+        containers.push(&symbol.name);
+        collected.push((symbol, containers.join("::")));
+        collect_symbols(&symbol.children, containers, collected);
+        containers.pop();
+    }
+}
+
+enum Matcher {
+    Chars {
+        query: Vec<char>,
+        mode: SearchMode,
+        case_sensitive: bool,
+    },
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn new(query: &str, mode: SearchMode, case_sensitive: bool) -> Result<Self, SearchError> {
+        Ok(match mode {
+            SearchMode::Regex => Matcher::Regex(
+                RegexBuilder::new(query)
+                    .case_insensitive(!case_sensitive)
+                    .build()?,
+            ),
+            _ => Matcher::Chars {
+                query: fold(query, case_sensitive),
+                mode,
+                case_sensitive,
+            },
+        })
+    }
+
+    /// The score of `target` for the query, or `None` if it doesn't match.
+    fn score(&self, target: &str) -> Option<i64> {
+        let (query, mode, case_sensitive) = match self {
+            Matcher::Regex(regex) => {
+                let found = regex.find(target)?;
+                let full = found.start() == 0 && found.end() == target.len();
+                return Some(if full {
+                    EXACT_SCORE
+                } else {
+                    CONTAINS_SCORE - penalty(found.start())
+                });
+            }
+            Matcher::Chars {
+                query,
+                mode,
+                case_sensitive,
+            } => (query, *mode, *case_sensitive),
+        };
+
+        let original = target.chars().collect::<Vec<_>>();
+        let target = fold(target, case_sensitive);
+        if query.is_empty() {
+            return Some(0);
+        }
+        if *query == target {
+            return match mode {
+                SearchMode::Fuzzy => fuzzy_score(query, &target, &original),
+                SearchMode::WordPrefix => word_prefix_score(query, &target, &original),
+                _ => Some(0),
+            }
+            .map(|score| score.max(0) + EXACT_SCORE);
+        }
+
+        match mode {
+            SearchMode::Substring => {
+                let starts = (0..target.len())
+                    .filter(|&i| target[i..].starts_with(query))
+                    .collect::<Vec<_>>();
+                let first = *starts.first()?;
+                Some(if first == 0 {
+                    PREFIX_SCORE
+                } else if starts.iter().any(|&i| is_word_start(&original, i)) {
+                    BOUNDARY_SCORE
+                } else {
+                    CONTAINS_SCORE - penalty(first)
+                })
+            }
+            SearchMode::Prefix => target
+                .starts_with(query)
+                .then(|| PREFIX_SCORE - penalty(target.len() - query.len())),
+            SearchMode::Fuzzy => fuzzy_score(query, &target, &original),
+            SearchMode::WordPrefix => word_prefix_score(query, &target, &original),
+            SearchMode::Regex => unreachable!(),
+        }
+    }
+}
+
+/// Small penalty growing with `n`, that never outweighs the score of a better kind of match
+fn penalty(n: usize) -> i64 {
+    n.min(9) as i64
+}
+
+/// Lowercases each character separately, so that indices stay the same as in `s`.
+fn fold(s: &str, case_sensitive: bool) -> Vec<char> {
+    s.chars()
+        .map(|c| {
+            if case_sensitive {
+                c
+            } else {
+                c.to_lowercase().next().unwrap_or(c)
+            }
+        })
+        .collect()
+}
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '_' | '-' | ':' | '.' | '/' | ' ')
+}
+
+/// Whether a camelCase or snake_case word starts at `i`
+fn is_word_start(chars: &[char], i: usize) -> bool {
+    if is_separator(chars[i]) {
+        return false;
+    }
+    match i.checked_sub(1).map(|prev| chars[prev]) {
+        None => true,
+        Some(prev) => is_separator(prev) || (prev.is_lowercase() && chars[i].is_uppercase()),
+    }
+}
+
+/// Best in-order match of the query characters, rewarding matches at word starts and
+/// consecutive matches, and penalizing the characters skipped in between.
+fn fuzzy_score(query: &[char], target: &[char], original: &[char]) -> Option<i64> {
+    let bonus = |j: usize| {
+        if is_word_start(original, j) {
+            1 + WORD_START_BONUS
+        } else {
+            1
+        }
+    };
+
+    // best[j]: best score with the current query character matched at `j`
+    let mut best = (0..target.len())
+        .map(|j| (target[j] == query[0]).then(|| bonus(j) - j as i64))
+        .collect::<Vec<_>>();
+
+    for &c in &query[1..] {
+        let mut next = vec![None; target.len()];
+        // best of `best[k] + k` for the `k` leaving a gap before `j`
+        let mut gapped: Option<i64> = None;
+        for j in 1..target.len() {
+            if j >= 2 {
+                if let Some(score) = best[j - 2] {
+                    let score = score + (j - 2) as i64;
+                    gapped = Some(gapped.map_or(score, |g| g.max(score)));
+                }
+            }
+            if target[j] != c {
+                continue;
+            }
+            let consecutive = best[j - 1].map(|s| s + CONSECUTIVE_BONUS);
+            let skipped = gapped.map(|g| g - (j - 1) as i64);
+            next[j] = consecutive.max(skipped).map(|s| s + bonus(j));
+        }
+        best = next;
+    }
+
+    best.into_iter().flatten().max()
+}
+
+/// Matches the query as a sequence of non-empty prefixes of words of the target, in order.
+fn word_prefix_score(query: &[char], target: &[char], original: &[char]) -> Option<i64> {
+    let words = (0..target.len())
+        .filter(|&i| is_word_start(original, i))
+        .map(|start| {
+            let end = (start + 1..target.len())
+                .find(|&i| is_separator(original[i]) || is_word_start(original, i))
+                .unwrap_or(target.len());
+            &target[start..end]
+        })
+        .collect::<Vec<_>>();
+
+    // separators typed in the query, as in `get_sym`, delimit words too
+    let query = query
+        .iter()
+        .copied()
+        .filter(|&c| !is_separator(c))
+        .collect::<Vec<_>>();
+    if query.is_empty() {
+        return None;
+    }
+
+    let mut memo = vec![vec![None; words.len() + 1]; query.len() + 1];
+    best_word_prefixes(&query, &words, 0, 0, &mut memo).map(|score| score + query.len() as i64)
+}
+
+fn best_word_prefixes(
+    query: &[char],
+    words: &[&[char]],
+    q: usize,
+    w: usize,
+    memo: &mut Vec<Vec<Option<Option<i64>>>>,
+) -> Option<i64> {
+    if q == query.len() {
+        // unmatched trailing words make the match a little less relevant
+        return Some(-((words.len() - w) as i64));
+    }
+    if let Some(score) = memo[q][w] {
+        return score;
+    }
+
+    let mut best = None;
+    for (i, word) in words.iter().enumerate().skip(w) {
+        let skipped = (i - w) as i64 * SKIPPED_WORD_PENALTY;
+        let common = word
+            .iter()
+            .zip(&query[q..])
+            .take_while(|(a, b)| a == b)
+            .count();
+        for len in 1..=common {
+            if let Some(score) = best_word_prefixes(query, words, q + len, i + 1, memo) {
+                best = best.max(Some(score - skipped));
+            }
+        }
+    }
+
+    memo[q][w] = Some(best);
+    best
+}
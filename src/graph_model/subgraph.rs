@@ -0,0 +1,82 @@
+//! Restrictions of a graph to some of its symbols.
+
+use {
+    super::{File, GlobalPosition, Graph, Symbol},
+    std::collections::HashSet,
+};
+
+impl Graph {
+    /// Keeps the symbols for which `predicate` holds along with the symbols containing them,
+    /// the files with kept symbols and the relations between kept symbols. Files and symbols
+    /// keep their ids and positions, so the subgraph can be rendered like the whole graph.
+    pub fn subgraph<F>(&self, predicate: F) -> Graph
+    where
+        F: Fn(&File, &Symbol) -> bool,
+    {
+        let mut kept = HashSet::new();
+        let files = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let symbols = prune_symbols(file, &file.symbols, &predicate, &mut kept);
+                (!symbols.is_empty()).then(|| File {
+                    id: file.id,
+                    path: file.path.clone(),
+                    symbols,
+                })
+            })
+            .collect();
+
+        let relations = self
+            .relations
+            .iter()
+            .filter(|relation| kept.contains(&relation.from) && kept.contains(&relation.to))
+            .cloned()
+            .collect();
+
+        let mut graph = Graph::new(files, relations);
+        graph.root = self.root.clone();
+        graph.unreferenced = self
+            .unreferenced
+            .iter()
+            .filter(|position| kept.contains(*position))
+            .copied()
+            .collect();
+        graph
+    }
+
+    /// The subgraph of the symbols at `nodes`, see `Graph::subgraph`.
+    pub fn induced(&self, nodes: &HashSet<GlobalPosition>) -> Graph {
+        self.subgraph(|_, symbol| nodes.contains(&symbol.global_position))
+    }
+}
+
+fn prune_symbols<F>(
+    file: &File,
+    symbols: &[Symbol],
+    predicate: &F,
+    kept: &mut HashSet<GlobalPosition>,
+) -> Vec<Symbol>
+where
+    F: Fn(&File, &Symbol) -> bool,
+{
+    symbols
+        .iter()
+        .filter_map(|symbol| {
+            let children = prune_symbols(file, &symbol.children, predicate, kept);
+            if children.is_empty() && !predicate(file, symbol) {
+                return None;
+            }
+
+            kept.insert(symbol.global_position);
+            Some(Symbol {
+                name: symbol.name.clone(),
+                kind: symbol.kind,
+                range: symbol.range.clone(),
+                children,
+                global_position: symbol.global_position,
+                id: symbol.id.clone(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,92 @@
+//! Identifiers of symbols that survive edits moving the symbols around in their files.
+
+use {
+    super::SymbolKind,
+    crate::lsp_types::DocumentSymbol,
+    serde::{Deserialize, Serialize},
+    std::{collections::HashMap, fmt},
+};
+
+/// Identifier of a symbol derived from the path of its file relative to the root, the names of
+/// the symbols containing it and its own name, rather than from its position. It is a hash of
+/// these, so that it can be used as is in DOT and Mermaid ids.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SymbolId(String);
+
+impl SymbolId {
+    /// The id of the symbol named by `key`, such as `src/a.rs#S::run`.
+    pub(crate) fn from_key(key: &str) -> Self {
+        // FNV-1a, which unlike the hashers of the standard library is stable across releases
+        let hash = key.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        Self(format!("{:016x}", hash))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for SymbolId {
+    fn from(id: String) -> Self {
+        Self(id)
+    }
+}
+
+/// Keys of `symbols`, the symbols of a file or the children of a symbol, made of `parent` and
+/// the names of the symbols. Symbols with the same name, such as overloads, are told apart by
+/// their kinds, then by their details, and at last by their order.
+pub(super) fn sibling_keys(parent: &str, symbols: &[DocumentSymbol]) -> Vec<String> {
+    let segment_of = |symbol: &DocumentSymbol, level: usize| {
+        let kind = SymbolKind::from(symbol.kind) as u8;
+        match level {
+            0 => symbol.name.clone(),
+            1 => format!("{}@{}", symbol.name, kind),
+            _ => format!(
+                "{}@{}({})",
+                symbol.name,
+                kind,
+                symbol.detail.as_deref().unwrap_or_default()
+            ),
+        }
+    };
+    let counts = |segments: &[String]| {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for segment in segments {
+            *counts.entry(segment.clone()).or_default() += 1;
+        }
+        counts
+    };
+
+    let mut segments = symbols.iter().map(|s| segment_of(s, 0)).collect::<Vec<_>>();
+    for level in 1..=2 {
+        let counts = counts(&segments);
+        for (segment, symbol) in segments.iter_mut().zip(symbols) {
+            if counts[segment.as_str()] > 1 {
+                *segment = segment_of(symbol, level);
+            }
+        }
+    }
+
+    let counts = counts(&segments);
+    let mut ranks: HashMap<String, usize> = HashMap::new();
+    segments
+        .into_iter()
+        .map(|segment| match counts[segment.as_str()] {
+            1 => format!("{}{}", parent, segment),
+            _ => {
+                let rank = ranks.entry(segment.clone()).or_default();
+                *rank += 1;
+                format!("{}{}~{}", parent, segment, rank)
+            }
+        })
+        .collect()
+}
@@ -16,6 +16,18 @@ pub(crate) trait Language: Send + Sync {
         false
     }
 
+    /// Whether `symbol` is a test, or a module of tests, run by the test harness rather than
+    /// called by other code.
+    fn is_test(&self, _file: &str, _symbol: &DocumentSymbol) -> bool {
+        false
+    }
+
+    /// Whether `symbol` may be used outside of its package. Only languages whose document
+    /// symbols tell the visibility apart override this.
+    fn is_exported(&self, _symbol: &DocumentSymbol) -> bool {
+        false
+    }
+
     fn file_repr(&self, file: &FileOutline) -> TableNode {
         let sections = file
             .symbols
@@ -47,6 +59,7 @@ pub(crate) trait Language: Send + Sync {
             range_end: (range.end.line, range.end.character),
             style: self.symbol_style(symbol),
             symbol_kind: Some(symbol.kind),
+            symbol_id: None,
             title: symbol.name.clone(),
             children,
         }
@@ -1,9 +1,18 @@
-use super::Language;
+use {super::Language, crate::lsp_types::DocumentSymbol};
 
 pub(crate) struct Go;
 
 impl Language for Go {
+    /// Test files are left out of the graph, so Go has no tests for the dead code analysis to
+    /// exempt. The functions called from the tests still count as called when the incoming
+    /// calls from the tests are added, as `crawl_files` does.
     fn should_filter_out_file(&self, file: &str) -> bool {
         file.ends_with("_test.go")
     }
+
+    fn is_exported(&self, symbol: &DocumentSymbol) -> bool {
+        // methods are named like `(*T).Method` by gopls
+        let name = symbol.name.rsplit('.').next().unwrap_or(&symbol.name);
+        name.starts_with(|c: char| c.is_uppercase())
+    }
 }
@@ -16,4 +16,8 @@ impl Language for Jsts {
             _ => true,
         }
     }
+
+    fn is_test(&self, file: &str, _symbol: &DocumentSymbol) -> bool {
+        file.contains(".test.") || file.contains(".spec.") || file.contains("/__tests__/")
+    }
 }
\ No newline at end of file
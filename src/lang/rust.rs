@@ -14,4 +14,11 @@ impl Language for Rust {
             _ => true,
         }
     }
+
+    fn is_test(&self, _file: &str, symbol: &DocumentSymbol) -> bool {
+        match symbol.kind {
+            SymbolKind::Module => symbol.name == "tests",
+            _ => symbol.name.starts_with("test_"),
+        }
+    }
 }
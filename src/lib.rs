@@ -2,12 +2,20 @@ mod generator;
 mod graph;
 mod graph_model;
 mod lang;
-mod lsp_types;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lsp_client;
+pub mod lsp_types;
 
-pub use generator::GraphGenerator;
+pub use generator::{
+    CallHierarchySource, DeadCodeOptions, Direction, GraphGenerator, SnapshotError, TraversalOptions,
+    SNAPSHOT_VERSION,
+};
+pub use graph::dot::{DotOptions, DotRenderer, EdgeWeighting, NodeMetric};
+pub use graph::svg::{EdgeRouting, SvgRenderer};
+pub use graph::{mermaid::MermaidRenderer, plantuml::PlantUmlRenderer, Cell, CssClass, Edge, Renderer, Style, Subgraph, TableNode};
 pub use graph_model::{
-    Graph, GraphBuilder, File, Symbol, Relation, RelationKind, GlobalPosition,
-    SearchResult, FileSearchResult, MatchType, FileMatchType
+    CycleNode, CycleReport, DiffRelation, DiffSymbol, GraphDiff, FileMetrics, Granularity, GraphMetrics, SymbolMetrics, Graph, GraphBuilder, File, Symbol, SymbolId, SymbolKind, Relation, RelationKind, GlobalPosition,
+    SearchResult, FileSearchResult, MatchType, FileMatchType, SearchMode, SearchError, Query, QueryError
 };
 
 // 当编译目标是wasm32时，使用wee_alloc作为全局分配器
@@ -0,0 +1,517 @@
+//! A JSON-RPC client for talking to language servers over stdio.
+//!
+//! It implements the `Content-Length` framing, correlates responses with requests by id,
+//! performs the `initialize`/`initialized`/`shutdown` handshake and exposes the requests
+//! crabviz needs as typed methods, so that editor integrations other than VS Code can reuse
+//! the whole pipeline.
+
+use {
+    crate::{
+        generator::CallHierarchySource,
+        lsp_types::{
+            CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall,
+            DocumentSymbol, Location, LocationLink, Position, Uri,
+        },
+    },
+    serde::{de::DeserializeOwned, Serialize},
+    serde_json::{json, Value},
+    std::{
+        collections::HashMap,
+        fmt::{self, Display},
+        io::{self, BufRead, BufReader, Read, Write},
+        process::{Child, Command, ExitStatus, Stdio},
+        sync::{
+            mpsc::{self, RecvTimeoutError, Sender},
+            Arc, Mutex,
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long `shutdown` waits for the server to exit before killing it.
+const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// An error response sent by the server.
+    Rpc {
+        code: i64,
+        message: String,
+    },
+    /// The server did not respond to the request in time.
+    Timeout(String),
+    /// The server closed the connection.
+    Closed,
+    /// The server does not advertise the capability needed by the request.
+    Unsupported(&'static str),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::Json(e) => write!(f, "invalid message: {}", e),
+            Error::Rpc { code, message } => write!(f, "server error {}: {}", code, message),
+            Error::Timeout(method) => write!(f, "request '{}' timed out", method),
+            Error::Closed => write!(f, "language server closed the connection"),
+            Error::Unsupported(capability) => {
+                write!(f, "language server does not support '{}'", capability)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Reads one framed message, returns `None` at the end of the stream.
+pub fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Value>> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                len = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let len = len.ok_or(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "missing Content-Length header",
+    ))?;
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+pub fn write_message<W: Write + ?Sized>(writer: &mut W, msg: &Value) -> Result<()> {
+    let body = msg.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Waits for `child` to exit, and kills it if it is still running after `timeout`.
+fn wait_or_kill(child: &mut Child, timeout: Duration) -> io::Result<ExitStatus> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if start.elapsed() >= timeout {
+            // the child may have exited in the meantime, which `wait` still reports
+            let _ = child.kill();
+            return child.wait();
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Joins the thread of `handle` if it finishes within `timeout`, and detaches it otherwise.
+fn join_or_detach(handle: JoinHandle<()>, timeout: Duration) {
+    let start = Instant::now();
+    while !handle.is_finished() {
+        if start.elapsed() >= timeout {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    let _ = handle.join();
+}
+
+/// The server capabilities crabviz cares about.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    pub document_symbol: bool,
+    pub call_hierarchy: bool,
+    pub implementation: bool,
+}
+
+impl ServerCapabilities {
+    fn from_value(caps: &Value) -> Self {
+        // a provider is either a boolean or an options object
+        let provided = |name: &str| match caps.get(name) {
+            None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+            Some(_) => true,
+        };
+
+        Self {
+            document_symbol: provided("documentSymbolProvider"),
+            call_hierarchy: provided("callHierarchyProvider"),
+            implementation: provided("implementationProvider"),
+        }
+    }
+}
+
+type Pending = Arc<Mutex<HashMap<u64, Sender<Result<Value>>>>>;
+type SharedWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+pub struct LspClient {
+    writer: SharedWriter,
+    pending: Pending,
+    next_id: u64,
+    timeout: Duration,
+    capabilities: ServerCapabilities,
+
+    child: Option<Child>,
+    reader_thread: Option<JoinHandle<()>>,
+}
+
+impl LspClient {
+    /// Starts a language server process and connects to its stdio.
+    pub fn spawn(command: &[String]) -> Result<Self> {
+        let (program, args) = command.split_first().ok_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "empty language server command",
+        ))?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+
+        let writer = child.stdin.take().unwrap();
+        let reader = child.stdout.take().unwrap();
+
+        let mut client = Self::connect(reader, writer);
+        client.child = Some(child);
+
+        Ok(client)
+    }
+
+    /// Connects to a server through an arbitrary pair of streams.
+    pub fn connect<R, W>(reader: R, writer: W) -> Self
+    where
+        R: Read + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        let writer: SharedWriter = Arc::new(Mutex::new(Box::new(writer)));
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+
+        let reader_thread = {
+            let writer = writer.clone();
+            let pending = pending.clone();
+            thread::spawn(move || Self::dispatch(BufReader::new(reader), writer, pending))
+        };
+
+        Self {
+            writer,
+            pending,
+            next_id: 1,
+            timeout: DEFAULT_TIMEOUT,
+            capabilities: ServerCapabilities::default(),
+
+            child: None,
+            reader_thread: Some(reader_thread),
+        }
+    }
+
+    fn dispatch<R: BufRead>(mut reader: R, writer: SharedWriter, pending: Pending) {
+        while let Ok(Some(msg)) = read_message(&mut reader) {
+            match (msg.get("id"), msg.get("method")) {
+                // requests from the server, e.g. `workspace/configuration`, are answered with null
+                (Some(id), Some(_)) => {
+                    let resp = json!({ "jsonrpc": "2.0", "id": id, "result": null });
+                    if let Ok(mut writer) = writer.lock() {
+                        let _ = write_message(&mut *writer, &resp);
+                    }
+                }
+                (Some(id), None) => {
+                    let sender = id.as_u64().and_then(|id| pending.lock().ok()?.remove(&id));
+
+                    if let Some(sender) = sender {
+                        let result = match msg.get("error") {
+                            Some(err) => Err(Error::Rpc {
+                                code: err["code"].as_i64().unwrap_or_default(),
+                                message: err["message"].as_str().unwrap_or_default().to_string(),
+                            }),
+                            None => Ok(msg.get("result").cloned().unwrap_or(Value::Null)),
+                        };
+                        let _ = sender.send(result);
+                    }
+                }
+                // notifications, e.g. diagnostics and progress, are not used
+                _ => {}
+            }
+        }
+
+        // wake up all the requests still waiting
+        if let Ok(mut pending) = pending.lock() {
+            pending.drain().for_each(|(_, sender)| {
+                let _ = sender.send(Err(Error::Closed));
+            });
+        }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn capabilities(&self) -> &ServerCapabilities {
+        &self.capabilities
+    }
+
+    fn send(&self, msg: &Value) -> Result<()> {
+        let mut writer = self.writer.lock().map_err(|_| Error::Closed)?;
+        write_message(&mut *writer, msg)
+    }
+
+    pub fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    pub fn request<P, T>(&mut self, method: &str, params: P) -> Result<T>
+    where
+        P: Serialize,
+        T: DeserializeOwned,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let (sender, receiver) = mpsc::channel();
+        self.pending
+            .lock()
+            .map_err(|_| Error::Closed)?
+            .insert(id, sender);
+
+        if let Err(e) =
+            self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+        {
+            self.forget(id);
+            return Err(e);
+        }
+
+        match receiver.recv_timeout(self.timeout) {
+            Ok(result) => Ok(serde_json::from_value(result?)?),
+            Err(RecvTimeoutError::Timeout) => {
+                self.forget(id);
+                let _ = self.notify("$/cancelRequest", json!({ "id": id }));
+                Err(Error::Timeout(method.to_string()))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(Error::Closed),
+        }
+    }
+
+    fn forget(&self, id: u64) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&id);
+        }
+    }
+
+    /// Performs the `initialize` handshake and records the capabilities of the server.
+    pub fn initialize(&mut self, root: &str) -> Result<ServerCapabilities> {
+        let root_uri = Uri::from_file_path(root).to_file_uri();
+
+        let result: Value = self.request(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": root_uri,
+                "workspaceFolders": [{ "uri": root_uri, "name": "root" }],
+                "capabilities": {
+                    "textDocument": {
+                        "documentSymbol": { "hierarchicalDocumentSymbolSupport": true },
+                        "callHierarchy": {},
+                        "implementation": { "linkSupport": true },
+                    },
+                },
+            }),
+        )?;
+        self.notify("initialized", json!({}))?;
+
+        self.capabilities = ServerCapabilities::from_value(&result["capabilities"]);
+        Ok(self.capabilities)
+    }
+
+    /// Sends `shutdown` and `exit`, then waits for the server to terminate. Servers still
+    /// running after a few seconds are killed. Servers connected through other streams are
+    /// waited for the same time to close them, and left alone after that.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.request::<_, Value>("shutdown", Value::Null)?;
+        self.notify("exit", Value::Null)?;
+
+        if let Some(mut child) = self.child.take() {
+            wait_or_kill(&mut child, EXIT_TIMEOUT)?;
+        }
+        if let Some(handle) = self.reader_thread.take() {
+            join_or_detach(handle, EXIT_TIMEOUT);
+        }
+
+        Ok(())
+    }
+
+    pub fn did_open(&self, path: &str, language_id: &str, text: &str) -> Result<()> {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": Uri::from_file_path(path),
+                    "languageId": language_id,
+                    "version": 1,
+                    "text": text,
+                },
+            }),
+        )
+    }
+
+    /// Servers without hierarchical symbol support respond with `SymbolInformation`s, which are reported as an error.
+    pub fn document_symbols(&mut self, path: &str) -> Result<Vec<DocumentSymbol>> {
+        if !self.capabilities.document_symbol {
+            return Err(Error::Unsupported("documentSymbolProvider"));
+        }
+
+        let symbols: Option<Vec<DocumentSymbol>> = self.request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": Uri::from_file_path(path) } }),
+        )?;
+        Ok(symbols.unwrap_or_default())
+    }
+
+    pub fn prepare_call_hierarchy(
+        &mut self,
+        path: &str,
+        position: Position,
+    ) -> Result<Vec<CallHierarchyItem>> {
+        if !self.capabilities.call_hierarchy {
+            return Err(Error::Unsupported("callHierarchyProvider"));
+        }
+
+        let items: Option<Vec<CallHierarchyItem>> = self.request(
+            "textDocument/prepareCallHierarchy",
+            json!({
+                "textDocument": { "uri": Uri::from_file_path(path) },
+                "position": position,
+            }),
+        )?;
+        Ok(items.unwrap_or_default())
+    }
+
+    pub fn incoming_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyIncomingCall>> {
+        if !self.capabilities.call_hierarchy {
+            return Err(Error::Unsupported("callHierarchyProvider"));
+        }
+
+        let calls: Option<Vec<CallHierarchyIncomingCall>> =
+            self.request("callHierarchy/incomingCalls", json!({ "item": item }))?;
+        Ok(calls.unwrap_or_default())
+    }
+
+    pub fn outgoing_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyOutgoingCall>> {
+        if !self.capabilities.call_hierarchy {
+            return Err(Error::Unsupported("callHierarchyProvider"));
+        }
+
+        let calls: Option<Vec<CallHierarchyOutgoingCall>> =
+            self.request("callHierarchy/outgoingCalls", json!({ "item": item }))?;
+        Ok(calls.unwrap_or_default())
+    }
+
+    /// `LocationLink`s are converted to `Location`s pointing at the target selection range.
+    pub fn implementations(&mut self, path: &str, position: Position) -> Result<Vec<Location>> {
+        if !self.capabilities.implementation {
+            return Err(Error::Unsupported("implementationProvider"));
+        }
+
+        let result: Value = self.request(
+            "textDocument/implementation",
+            json!({
+                "textDocument": { "uri": Uri::from_file_path(path) },
+                "position": position,
+            }),
+        )?;
+
+        let locations = match result {
+            Value::Array(ref items) if items.iter().any(|item| item.get("targetUri").is_some()) => {
+                serde_json::from_value::<Vec<LocationLink>>(result)?
+                    .into_iter()
+                    .map(|link| Location {
+                        uri: link.target_uri,
+                        range: link.target_selection_range,
+                    })
+                    .collect()
+            }
+            Value::Array(_) => serde_json::from_value(result)?,
+            Value::Object(_) => vec![serde_json::from_value(result)?],
+            _ => vec![],
+        };
+
+        Ok(locations)
+    }
+}
+
+impl CallHierarchySource for LspClient {
+    type Error = Error;
+
+    fn document_symbols(&mut self, path: &str) -> Result<Vec<DocumentSymbol>> {
+        LspClient::document_symbols(self, path)
+    }
+
+    fn prepare(&mut self, path: &str, position: Position) -> Result<Vec<CallHierarchyItem>> {
+        self.prepare_call_hierarchy(path, position)
+    }
+
+    fn incoming_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyIncomingCall>> {
+        LspClient::incoming_calls(self, item)
+    }
+
+    fn outgoing_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyOutgoingCall>> {
+        LspClient::outgoing_calls(self, item)
+    }
+
+    fn implementations(&mut self, path: &str, position: Position) -> Result<Vec<Location>> {
+        LspClient::implementations(self, path, position)
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
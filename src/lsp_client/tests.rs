@@ -0,0 +1,225 @@
+use {
+    super::{join_or_detach, read_message, wait_or_kill, write_message, Error, LspClient},
+    crate::lsp_types::{Position, SymbolKind, Uri},
+    serde_json::{json, Value},
+    std::{
+        io::{self, BufReader, Cursor},
+        process::Command,
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// 启动一个内存中的假语言服务器，`handler`根据请求返回要发送的消息列表
+fn fake_server<F>(handler: F) -> LspClient
+where
+    F: Fn(&Value) -> Vec<Value> + Send + 'static,
+{
+    let (client_reader, mut server_writer) = io::pipe().unwrap();
+    let (server_reader, client_writer) = io::pipe().unwrap();
+
+    thread::spawn(move || {
+        let mut reader = BufReader::new(server_reader);
+        while let Ok(Some(msg)) = read_message(&mut reader) {
+            if msg["method"] == "exit" {
+                break;
+            }
+            for reply in handler(&msg) {
+                write_message(&mut server_writer, &reply).unwrap();
+            }
+        }
+    });
+
+    LspClient::connect(client_reader, client_writer)
+}
+
+fn respond(msg: &Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": msg["id"], "result": result })
+}
+
+fn initialize_result(msg: &Value) -> Vec<Value> {
+    vec![respond(
+        msg,
+        json!({
+            "capabilities": {
+                "documentSymbolProvider": true,
+                "callHierarchyProvider": { "workDoneProgress": false },
+                "implementationProvider": false,
+            }
+        }),
+    )]
+}
+
+#[test]
+fn message_framing() {
+    let mut buf = vec![];
+    write_message(&mut buf, &json!({ "jsonrpc": "2.0", "method": "初始化" })).unwrap();
+    write_message(&mut buf, &json!({ "jsonrpc": "2.0", "id": 1 })).unwrap();
+
+    let text = String::from_utf8(buf.clone()).unwrap();
+    assert!(
+        text.starts_with("Content-Length: "),
+        "消息应该以Content-Length开头"
+    );
+
+    let mut reader = Cursor::new(buf);
+    let first = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(first["method"], "初始化", "多字节字符的长度应该按字节计算");
+    let second = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(second["id"], 1);
+    assert!(
+        read_message(&mut reader).unwrap().is_none(),
+        "流结束时应该返回None"
+    );
+}
+
+#[test]
+fn initialize_negotiates_capabilities() {
+    let mut client = fake_server(|msg| match msg["method"].as_str() {
+        Some("initialize") => initialize_result(msg),
+        Some("shutdown") => vec![respond(msg, Value::Null)],
+        _ => vec![],
+    });
+
+    let caps = client.initialize("/project").unwrap();
+    assert!(caps.document_symbol);
+    assert!(caps.call_hierarchy, "对象形式的provider也应该视为支持");
+    assert!(!caps.implementation, "false应该视为不支持");
+
+    let err = client
+        .implementations("/project/a.rs", Position::default())
+        .unwrap_err();
+    assert!(matches!(err, Error::Unsupported("implementationProvider")));
+
+    client.shutdown().unwrap();
+}
+
+#[test]
+fn responses_are_correlated_by_id() {
+    let mut client = fake_server(|msg| match msg["method"].as_str() {
+        Some("initialize") => initialize_result(msg),
+        Some("textDocument/documentSymbol") => vec![
+            // 通知、服务器发起的请求以及过期的响应都应该被跳过
+            json!({ "jsonrpc": "2.0", "method": "window/logMessage", "params": { "type": 3, "message": "indexing" } }),
+            json!({ "jsonrpc": "2.0", "id": "cfg", "method": "workspace/configuration", "params": { "items": [] } }),
+            json!({ "jsonrpc": "2.0", "id": 999, "result": [] }),
+            respond(
+                msg,
+                json!([{
+                    "name": "main",
+                    "kind": 12,
+                    "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 2, "character": 1 } },
+                    "selectionRange": { "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 7 } },
+                }]),
+            ),
+        ],
+        Some("shutdown") => vec![respond(msg, Value::Null)],
+        _ => vec![],
+    });
+
+    client.initialize("/project").unwrap();
+    let symbols = client.document_symbols("/project/main.rs").unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "main");
+    assert_eq!(symbols[0].kind, SymbolKind::Function);
+
+    client.shutdown().unwrap();
+}
+
+#[test]
+fn error_responses_are_reported() {
+    let mut client = fake_server(|msg| match msg["method"].as_str() {
+        Some("initialize") => initialize_result(msg),
+        Some("textDocument/prepareCallHierarchy") => vec![json!({
+            "jsonrpc": "2.0",
+            "id": msg["id"],
+            "error": { "code": -32801, "message": "content modified" },
+        })],
+        _ => vec![],
+    });
+
+    client.initialize("/project").unwrap();
+    let err = client
+        .prepare_call_hierarchy("/project/main.rs", Position::default())
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::Rpc { code: -32801, .. }),
+        "应该返回服务器的错误: {}",
+        err
+    );
+}
+
+#[test]
+fn requests_time_out() {
+    let mut client = fake_server(|msg| match msg["method"].as_str() {
+        Some("initialize") => initialize_result(msg),
+        _ => vec![],
+    });
+    client.initialize("/project").unwrap();
+
+    client.set_timeout(Duration::from_millis(50));
+    let err = client.document_symbols("/project/main.rs").unwrap_err();
+    assert!(matches!(err, Error::Timeout(ref method) if method == "textDocument/documentSymbol"));
+}
+
+#[test]
+fn closed_connection() {
+    let (client_reader, server_writer) = io::pipe().unwrap();
+    let (_server_reader, client_writer) = io::pipe().unwrap();
+    drop(server_writer);
+
+    let mut client = LspClient::connect(client_reader, client_writer);
+    let err = client.initialize("/project").unwrap_err();
+    assert!(
+        matches!(err, Error::Closed),
+        "连接关闭时应该返回Closed: {}",
+        err
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn servers_ignoring_exit_are_killed() {
+    let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+    let start = Instant::now();
+    let status = wait_or_kill(&mut child, Duration::from_millis(50)).unwrap();
+    assert!(!status.success(), "未退出的进程应该被杀死");
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    let mut child = Command::new("true").spawn().unwrap();
+    let status = wait_or_kill(&mut child, Duration::from_secs(10)).unwrap();
+    assert!(status.success(), "已退出的进程不应该被杀死");
+}
+
+#[test]
+fn peers_keeping_the_connection_open_are_detached() {
+    // 对端在 exit 之后不关闭连接时，读取线程会一直阻塞
+    let (client_reader, _server_writer) = io::pipe().unwrap();
+    let reader = thread::spawn(move || {
+        let _ = read_message(&mut BufReader::new(client_reader));
+    });
+    let start = Instant::now();
+    join_or_detach(reader, Duration::from_millis(50));
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    let finished = thread::spawn(|| {});
+    join_or_detach(finished, Duration::from_secs(10));
+}
+
+#[test]
+fn file_uris() {
+    let parse = |uri: &str| serde_json::from_value::<Uri>(json!(uri)).unwrap().path;
+
+    let unix = Uri::from_file_path("/root/a b.rs");
+    assert_eq!(unix.to_file_uri(), "file:///root/a%20b.rs");
+    assert_eq!(parse(&unix.to_file_uri()), "/root/a b.rs");
+
+    // Windows路径中的反斜杠写为斜杠，盘符后的冒号不转义
+    let windows = Uri::from_file_path(r"C:\src\a b.rs");
+    assert_eq!(windows.to_file_uri(), "file:///C:/src/a%20b.rs");
+    assert_eq!(parse(&windows.to_file_uri()), r"C:\src\a b.rs");
+
+    // 语言服务器可能转义冒号并使用小写盘符
+    assert_eq!(parse("file:///c%3A/src/a.rs"), r"C:\src\a.rs");
+    assert_eq!(parse("file:///C:/"), r"C:\");
+}
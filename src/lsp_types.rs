@@ -1,9 +1,13 @@
 //! Some Language Server Protocol types used in crabviz, copied from gluon-lang/lsp-types with some modifications.
 
-use {serde::Deserialize, serde_json::Value, serde_repr::Deserialize_repr};
+use {
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    serde_json::Value,
+    serde_repr::{Deserialize_repr, Serialize_repr},
+};
 
 /// A symbol kind.
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Deserialize_repr)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum SymbolKind {
     File = if cfg!(feature = "vscode") { 0 } else { 1 },
@@ -35,7 +39,7 @@ pub enum SymbolKind {
     TypeParameter,
 }
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum SymbolTag {
     /**
@@ -46,7 +50,7 @@ pub enum SymbolTag {
 
 /// Position in a text document expressed as zero-based line and character offset.
 /// A position is between two characters like an 'insert' cursor in a editor.
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Default, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Default, Serialize, Deserialize)]
 pub struct Position {
     /// Line position in a document (zero-based).
     pub line: u32,
@@ -60,7 +64,7 @@ pub struct Position {
 
 /// A range in a text document expressed as (zero-based) start and end positions.
 /// A range is comparable to a selection in an editor. Therefore the end position is exclusive.
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize, Deserialize)]
 pub struct Range {
     /// The range's start position.
     pub start: Position,
@@ -68,7 +72,7 @@ pub struct Range {
     pub end: Position,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DocumentSymbol {
     /// The name of this symbol.
@@ -95,7 +99,7 @@ pub struct DocumentSymbol {
     pub children: Vec<DocumentSymbol>,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CallHierarchyItem {
     /// The name of this item.
@@ -127,8 +131,41 @@ pub struct CallHierarchyItem {
     pub data: Option<Value>,
 }
 
+/// Represents an item of a type hierarchy, e.g. a class or an interface.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItem {
+    /// The name of this item.
+    pub name: String,
+
+    /// The kind of this item.
+    pub kind: SymbolKind,
+
+    /// Tags for this item.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<SymbolTag>>,
+
+    /// More detail for this item, e.g. the signature of a function.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// The resource identifier of this item.
+    pub uri: Uri,
+
+    /// The range enclosing this symbol not including leading/trailing whitespace but everything else, e.g. comments and code.
+    pub range: Range,
+
+    /// The range that should be selected and revealed when this symbol is being picked, e.g. the name of a class.
+    /// Must be contained by the [`range`](#TypeHierarchyItem.range).
+    pub selection_range: Range,
+
+    /// A data entry field that is preserved between a type hierarchy prepare and supertypes or subtypes requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
 /// Represents an incoming call, e.g. a caller of a method or constructor.
-#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CallHierarchyIncomingCall {
     /// The item that makes the call.
@@ -140,7 +177,7 @@ pub struct CallHierarchyIncomingCall {
 }
 
 /// Represents an outgoing call, e.g. calling a getter from a method or a method from a constructor etc.
-#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CallHierarchyOutgoingCall {
     /// The item that is called.
@@ -152,20 +189,129 @@ pub struct CallHierarchyOutgoingCall {
     pub from_ranges: Vec<Range>,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Clone, Eq)]
+/// The path part of a document URI.
+///
+/// Language servers send URIs as `file://` strings, while the VS Code extension hands over
+/// `vscode.Uri` objects, so both forms are accepted when deserializing. It is always
+/// serialized as a `file://` string.
+///
+/// Windows paths like `C:\a\b.rs` are written as `file:///C:/a/b.rs`, and `file://` strings
+/// with a drive letter are read back with backslashes and an uppercase drive letter, the form
+/// of the paths the standard library returns on Windows.
+#[derive(Debug, PartialEq, Clone, Eq)]
 pub struct Uri {
     pub path: String,
 }
 
+impl Uri {
+    pub fn from_file_path(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+
+    pub fn to_file_uri(&self) -> String {
+        let windows = has_drive_letter(&self.path);
+
+        let mut uri = String::from("file://");
+        if !self.path.starts_with('/') {
+            uri.push('/');
+        }
+        for (i, b) in self.path.bytes().enumerate() {
+            match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                    uri.push(b as char)
+                }
+                b'\\' if windows => uri.push('/'),
+                b':' if windows && i == 1 => uri.push(':'),
+                _ => uri.push_str(&format!("%{:02X}", b)),
+            }
+        }
+        uri
+    }
+
+    fn parse(uri: &str) -> Self {
+        let path = match uri.find("://") {
+            Some(i) => {
+                let rest = &uri[i + 3..];
+                // skip the authority, which is empty for local files
+                &rest[rest.find('/').unwrap_or(rest.len())..]
+            }
+            None => uri,
+        };
+        let path = percent_decode(path);
+
+        match path.strip_prefix('/').filter(|path| has_drive_letter(path)) {
+            Some(windows_path) => {
+                let mut windows_path = windows_path.replace('/', "\\");
+                windows_path[..1].make_ascii_uppercase();
+                Self { path: windows_path }
+            }
+            None => Self { path },
+        }
+    }
+}
+
+/// Whether `path` starts with a Windows drive letter, like `C:\` or `c:/`
+fn has_drive_letter(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && bytes.get(2).is_none_or(|b| matches!(b, b'/' | b'\\'))
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(b) = s.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+impl Serialize for Uri {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_file_uri())
+    }
+}
+
+impl<'de> Deserialize<'de> for Uri {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Uri(String),
+            Object { path: String },
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Uri(uri) => Uri::parse(&uri),
+            Repr::Object { path } => Uri { path },
+        })
+    }
+}
+
 /// Represents a location inside a resource, such as a line inside a text file.
-#[derive(Debug, Eq, PartialEq, Clone, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Location {
     pub uri: Uri,
     pub range: Range,
 }
 
 /// Represents a link between a source and a target location.
-#[derive(Debug, Eq, PartialEq, Clone, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LocationLink {
     /// Span of the origin of this link.
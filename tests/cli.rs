@@ -0,0 +1,222 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const FAKE_LSP: &str = env!("CARGO_BIN_EXE_crabviz-fake-lsp");
+const CRABVIZ: &str = env!("CARGO_BIN_EXE_crabviz");
+
+/// 创建一个包含两个Rust源文件的临时项目目录
+fn setup_project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("crabviz-cli-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+
+    fs::write(
+        dir.join("src/main.rs"),
+        "fn main() {\n    helper();\n    helper();\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/util.rs"),
+        "pub fn helper() {\n}\n\npub trait Shape {\n    fn area(&self) -> f64;\n}\n\npub struct Square;\n",
+    )
+    .unwrap();
+
+    dir
+}
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn run_crabviz(root: &Path, fixture_name: &str, format: &str) -> String {
+    let output = Command::new(CRABVIZ)
+        .args([
+            "--root",
+            root.to_str().unwrap(),
+            "--lang",
+            "Rust",
+            "--format",
+            format,
+        ])
+        .args([
+            "--server",
+            FAKE_LSP,
+            fixture(fixture_name).to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run crabviz");
+
+    assert!(
+        output.status.success(),
+        "crabviz 应该成功退出: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cli_generates_dot() {
+    let root = setup_project("dot");
+    let dot = run_crabviz(&root, "simple.json", "dot");
+
+    assert!(dot.contains("digraph"), "dot源码应该包含digraph关键字");
+    assert!(
+        dot.contains("main.rs") && dot.contains("util.rs"),
+        "dot源码应该包含两个文件"
+    );
+    assert!(dot.contains("helper"), "dot源码应该包含函数名");
+    // main -> helper 的调用边
+    assert!(
+        dot.contains(r#"1:"0_3" -> 2:"0_7""#),
+        "dot源码应该包含调用边:\n{}",
+        dot
+    );
+    // main 中调用 helper 的两个位置
+    assert!(
+        dot.contains(r#"datacallsites="1:4 2:4""#),
+        "调用边应该包含调用位置:\n{}",
+        dot
+    );
+    // Square 实现了 Shape
+    assert!(
+        dot.contains(r#"2:"8_11" -> 2:"4_10""#),
+        "dot源码应该包含实现边:\n{}",
+        dot
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cli_generates_mermaid() {
+    let root = setup_project("mermaid");
+    let mermaid = run_crabviz(&root, "simple.json", "mermaid");
+
+    assert!(
+        mermaid.starts_with("flowchart LR"),
+        "mermaid源码应该以flowchart开头"
+    );
+    assert!(
+        mermaid.contains("1_0_3 -->|2| 2_0_7"),
+        "mermaid源码应该包含带调用次数的调用边:\n{}",
+        mermaid
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cli_generates_plantuml() {
+    let root = setup_project("plantuml");
+    let uml = run_crabviz(&root, "simple.json", "plantuml");
+
+    assert!(
+        uml.starts_with("@startuml") && uml.ends_with("@enduml\n"),
+        "PlantUML源码应该以@startuml开头，以@enduml结尾"
+    );
+    assert!(
+        uml.contains("s1_0_3 ..> s2_0_7 : 2"),
+        "PlantUML源码应该包含带调用次数的依赖箭头:\n{}",
+        uml
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cli_generates_json() {
+    let root = setup_project("json");
+    let json = run_crabviz(&root, "simple.json", "json");
+
+    let graph: serde_json::Value = serde_json::from_str(&json).expect("输出应该是合法的JSON");
+    assert_eq!(
+        graph["files"].as_array().unwrap().len(),
+        2,
+        "应该包含两个文件"
+    );
+    let relations = graph["relations"].as_array().unwrap();
+    assert!(!relations.is_empty(), "应该包含关系");
+
+    // incoming calls 和 outgoing calls 中的相同调用位置只计算一次
+    let call = relations
+        .iter()
+        .find(|r| r["kind"] == 1)
+        .expect("应该包含调用关系");
+    assert_eq!(call["callCount"], 2);
+    assert_eq!(call["callSites"][1]["start"]["line"], 2);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cli_without_call_hierarchy_support() {
+    let root = setup_project("no-call-hierarchy");
+    let dot = run_crabviz(&root, "no_call_hierarchy.json", "dot");
+
+    assert!(dot.contains("helper"), "即使不支持调用层次，也应该输出符号");
+    assert!(
+        !dot.contains("->"),
+        "不支持调用层次时不应该有调用边:\n{}",
+        dot
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cli_rejects_missing_server() {
+    let output = Command::new(CRABVIZ)
+        .args(["--lang", "Rust"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "缺少语言服务器命令时应该报错"
+    );
+}
+
+#[test]
+fn cli_rejects_unknown_language() {
+    let output = Command::new(CRABVIZ)
+        .args(["--lang", "Cobol", "--server", FAKE_LSP])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2), "未知的语言应该报错");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("unknown language 'Cobol', pass --ext"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn cli_matches_languages_case_insensitively() {
+    let root = setup_project("lowercase-lang");
+    let output = Command::new(CRABVIZ)
+        .args(["--root", root.to_str().unwrap(), "--lang", "rust"])
+        .args([
+            "--server",
+            FAKE_LSP,
+            fixture("simple.json").to_str().unwrap(),
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("helper"),
+        "小写的语言名也应该找到 Rust 源文件"
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,104 @@
+//! A fake language server for testing the `crabviz` command line tool.
+//!
+//! It replays canned responses from a JSON fixture given as the first argument. Occurrences of
+//! `${root}` in the fixture are replaced with the root URI sent in the `initialize` request.
+//! Responses are looked up by method name, then by the document path relative to the root,
+//! followed by `:line:character` for position-based requests, e.g. `"src/main.rs:3:7"`.
+
+use {
+    serde_json::{json, Value},
+    std::{
+        env, fs,
+        io::{self, BufRead, Write},
+    },
+};
+
+fn receive(reader: &mut impl BufRead) -> Option<Value> {
+    let mut len = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            len = value.trim().parse().ok()?;
+        }
+    }
+
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+fn send(writer: &mut impl Write, msg: &Value) {
+    let body = msg.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    writer.flush().unwrap();
+}
+
+fn main() {
+    let fixture_path = env::args().nth(1).expect("fixture path");
+    let fixture_src = fs::read_to_string(fixture_path).expect("fixture");
+
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let mut writer = io::stdout();
+
+    let mut root = String::new();
+    let mut fixture = Value::Null;
+
+    while let Some(msg) = receive(&mut reader) {
+        let method = msg["method"].as_str().unwrap_or_default();
+        let params = &msg["params"];
+
+        let id = match msg.get("id") {
+            Some(id) => id.clone(),
+            None if method == "exit" => break,
+            None => continue,
+        };
+
+        let key = |uri: &Value, position: &Value| {
+            let path = uri.as_str().unwrap_or_default();
+            let path = path
+                .strip_prefix(root.as_str())
+                .unwrap_or(path)
+                .trim_start_matches('/');
+            if position.is_null() {
+                path.to_string()
+            } else {
+                format!("{}:{}:{}", path, position["line"], position["character"])
+            }
+        };
+
+        let result = match method {
+            "initialize" => {
+                root = params["rootUri"].as_str().unwrap_or_default().to_string();
+                fixture = serde_json::from_str(&fixture_src.replace("${root}", &root))
+                    .expect("valid fixture");
+                json!({ "capabilities": fixture["capabilities"] })
+            }
+            "textDocument/documentSymbol" => {
+                fixture["documentSymbol"][key(&params["textDocument"]["uri"], &Value::Null)].clone()
+            }
+            "textDocument/prepareCallHierarchy" | "textDocument/implementation" => {
+                let section = method.trim_start_matches("textDocument/");
+                fixture[section][key(&params["textDocument"]["uri"], &params["position"])].clone()
+            }
+            "callHierarchy/incomingCalls" | "callHierarchy/outgoingCalls" => {
+                let section = method.trim_start_matches("callHierarchy/");
+                let item = &params["item"];
+                fixture[section][key(&item["uri"], &item["selectionRange"]["start"])].clone()
+            }
+            _ => Value::Null,
+        };
+
+        send(
+            &mut writer,
+            &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        );
+    }
+}